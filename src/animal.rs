@@ -0,0 +1,261 @@
+// animals, and building them by species name at runtime
+//
+// Animal::create gives back Self, so the caller has to know the type when
+// compiling. a Registry maps species strings ("human", "cat") to constructors
+// that return a Box<dyn Animal>, which is what config files and user input
+// need: the species is only known once the program runs
+//
+// create is only there for sized types (the where Self: Sized), that keeps
+// the trait usable as dyn Animal
+//
+// names are Cow<'static, str>: a literal is borrowed for free, a name read
+// at runtime is owned. moving an impl from the old &'static str names:
+//   - the field becomes Cow<'static, str> and create stores name.into(),
+//     which takes literals and Strings alike
+//   - name() returns &str, &self.name
+//   - struct literals write Cat { name: "Misty".into() }
+// an impl whose name() still returns a &'static str compiles unchanged, the
+// longer lifetime is allowed, but it has nowhere to keep a runtime name
+
+use crate::animal_serde::tagged;
+
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt;
+
+pub trait Animal // when defining a class we can choose to implement this trait
+{
+    fn create(name: impl Into<Cow<'static, str>>) -> Self where Self: Sized; // Self is the type of the class that implements this trait
+
+    fn name(&self) -> &str;
+
+    fn talk(&self)
+    {
+        println!("{} cannot talk", self.name());
+    }
+
+    // the animal as json with its species tag, which is how a Box<dyn Animal>
+    // is saved (see animal_serde.rs). None for animals that can't be saved
+    fn save(&self) -> Option<serde_json::Value> { None }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Human
+{
+    pub name: Cow<'static, str>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Cat
+{
+    pub name: Cow<'static, str>,
+}
+
+impl Animal for Human
+{
+    fn create(name: impl Into<Cow<'static, str>>) -> Human
+    {
+        Human { name: name.into() }
+    }
+
+    fn name(&self) -> &str { &self.name }
+
+    fn talk(&self)
+    {
+        println!("{} says hello", self.name);
+    }
+
+    fn save(&self) -> Option<serde_json::Value> { Some(tagged(self)) }
+}
+
+impl Animal for Cat
+{
+    fn create(name: impl Into<Cow<'static, str>>) -> Cat
+    {
+        Cat { name: name.into() }
+    }
+
+    fn name(&self) -> &str { &self.name }
+
+    fn talk(&self)
+    {
+        println!("{} says meow", self.name);
+    }
+
+    fn save(&self) -> Option<serde_json::Value> { Some(tagged(self)) }
+}
+
+// the closed set of species, without a box. an Animal itself, it passes
+// the calls on to the one inside
+pub enum Creature
+{
+    Human(Human),
+    Cat(Cat),
+}
+
+impl Creature
+{
+    pub fn as_animal(&self) -> &dyn Animal
+    {
+        match self {
+            Creature::Human(h) => h,
+            Creature::Cat(c) => c,
+        }
+    }
+}
+
+impl Animal for Creature
+{
+    // a creature has to be some species, humans are the default
+    fn create(name: impl Into<Cow<'static, str>>) -> Creature { Creature::Human(Human::create(name)) }
+
+    fn name(&self) -> &str { self.as_animal().name() }
+
+    fn talk(&self) { self.as_animal().talk() }
+
+    fn save(&self) -> Option<serde_json::Value> { self.as_animal().save() }
+}
+
+impl From<Human> for Creature
+{
+    fn from(h: Human) -> Creature { Creature::Human(h) }
+}
+
+impl From<Cat> for Creature
+{
+    fn from(c: Cat) -> Creature { Creature::Cat(c) }
+}
+
+/////////////////////////////////////
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistryError
+{
+    // the species already has a constructor
+    Duplicate(String),
+    Unknown(String),
+}
+
+impl fmt::Display for RegistryError
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self {
+            RegistryError::Duplicate(species) => write!(f, "species {:?} is already registered", species),
+            RegistryError::Unknown(species) => write!(f, "unknown species {:?}", species),
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {}
+
+pub type Constructor = Box<dyn Fn(Cow<'static, str>) -> Box<dyn Animal>>;
+
+// species -> constructor. kept sorted, so species() lists them in order
+#[derive(Default)]
+pub struct Registry
+{
+    constructors: BTreeMap<String, Constructor>,
+}
+
+impl Registry
+{
+    // empty, with_defaults() knows the species of this file
+    pub fn new() -> Registry { Registry::default() }
+
+    pub fn with_defaults() -> Registry
+    {
+        let mut registry = Registry::new();
+        registry.register_type::<Human>("human").unwrap();
+        registry.register_type::<Cat>("cat").unwrap();
+        registry
+    }
+
+    // a species registered twice is an error, the first constructor stays
+    pub fn register<F>(&mut self, species: &str, constructor: F) -> Result<(), RegistryError>
+    where
+        F: Fn(Cow<'static, str>) -> Box<dyn Animal> + 'static,
+    {
+        if self.constructors.contains_key(species) {
+            return Err(RegistryError::Duplicate(species.to_string()));
+        }
+        self.constructors.insert(species.to_string(), Box::new(constructor));
+        Ok(())
+    }
+
+    // the usual case: the species is a type and Animal::create builds it
+    pub fn register_type<A: Animal + 'static>(&mut self, species: &str) -> Result<(), RegistryError>
+    {
+        self.register(species, |name| Box::new(A::create(name)))
+    }
+
+    pub fn create(&self, species: &str, name: impl Into<Cow<'static, str>>) -> Result<Box<dyn Animal>, RegistryError>
+    {
+        match self.constructors.get(species) {
+            Some(constructor) => Ok(constructor(name.into())),
+            None => Err(RegistryError::Unknown(species.to_string())),
+        }
+    }
+
+    pub fn contains(&self, species: &str) -> bool { self.constructors.contains_key(species) }
+
+    // in alphabetical order
+    pub fn species(&self) -> impl Iterator<Item = &str> { self.constructors.keys().map(|s| s.as_str()) }
+}
+
+/////////////////////////////////////
+
+pub fn main()
+{
+    // an impl moved over from &'static str names
+    struct Dog
+    {
+        name: Cow<'static, str>,
+    }
+
+    impl Animal for Dog
+    {
+        fn create(name: impl Into<Cow<'static, str>>) -> Dog { Dog { name: name.into() } }
+
+        fn name(&self) -> &str { &self.name }
+
+        fn talk(&self) { println!("{} says woof", self.name); }
+    }
+
+    let mut registry = Registry::with_defaults();
+    registry.register_type::<Dog>("dog").unwrap();
+    // a constructor doesn't have to be a type's create
+    registry.register("stray", |_| Box::new(Cat::create("nobody's cat"))).unwrap();
+    assert_eq!(registry.species().collect::<Vec<_>>(), ["cat", "dog", "human", "stray"]);
+
+    assert_eq!(registry.register_type::<Cat>("human").unwrap_err(), RegistryError::Duplicate("human".to_string()));
+    // the first human constructor is still the one used
+    registry.create("human", "Bob").unwrap().talk();
+
+    // what a config file would say, one "species name" per line
+    let config = "human John\ncat Misty\ndog Rex\nstray Tom\n";
+    let zoo: Vec<Box<dyn Animal>> = config
+        .lines()
+        .map(|line| {
+            let (species, name) = line.split_once(' ').unwrap();
+            registry.create(species, name.to_string()).unwrap()
+        })
+        .collect();
+    for animal in &zoo { animal.talk(); }
+    assert_eq!(zoo.iter().map(|a| a.name()).collect::<Vec<_>>(), ["John", "Misty", "Rex", "nobody's cat"]);
+    assert_eq!(registry.create("dog", "Rex").unwrap().name(), "Rex");
+
+    let unknown = registry.create("dragon", "Smaug").err().unwrap();
+    assert_eq!(unknown, RegistryError::Unknown("dragon".to_string()));
+    println!("{}", unknown);
+    assert!(registry.contains("cat") && !Registry::new().contains("cat"));
+
+    // literals stay borrowed, only runtime names allocate
+    let typed = String::from("Tom");
+    let creatures: Vec<Creature> = vec![Human::create("John").into(), Cat::create(typed).into()];
+    assert!(matches!(&creatures[0], Creature::Human(h) if matches!(h.name, Cow::Borrowed(_))));
+    assert!(matches!(&creatures[1], Creature::Cat(c) if matches!(c.name, Cow::Owned(_))));
+    for c in &creatures { c.talk(); }
+    assert_eq!(creatures.iter().map(|c| c.name()).collect::<Vec<_>>(), ["John", "Tom"]);
+}
@@ -0,0 +1,259 @@
+// saving and loading mixed collections of animals
+//
+// a Box<dyn Animal> is written as its fields plus a species tag:
+//
+//   [{"name":"John","species":"human"},{"name":"Misty","species":"cat"}]
+//
+// writing goes through Animal::save, reading through a Loader that knows
+// which species tag belongs to which type. a tag the loader doesn't know is
+// either an error or kept as an Opaque animal that saves back exactly as it
+// was read, the loader's UnknownSpecies decides
+//
+// the values in between are serde_json::Value, but both directions are
+// plain serde, any format with self describing data works
+
+use crate::animal::{Animal, Cat, Human, RegistryError};
+
+use serde::de::{self, DeserializeOwned, DeserializeSeed};
+use serde::{ser, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+pub const TAG: &str = "species";
+
+// a type that can be saved and loaded under a species tag
+pub trait Species: Animal + Serialize + DeserializeOwned + 'static
+{
+    const SPECIES: &'static str;
+}
+
+impl Species for Human
+{
+    const SPECIES: &'static str = "human";
+}
+
+impl Species for Cat
+{
+    const SPECIES: &'static str = "cat";
+}
+
+// the fields of animal with the tag added, for Animal::save
+pub fn tagged<A: Species>(animal: &A) -> Value
+{
+    let mut value = serde_json::to_value(animal).expect("an animal that doesn't serialize");
+    match value.as_object_mut() {
+        Some(fields) => { fields.insert(TAG.to_string(), Value::String(A::SPECIES.to_string())); }
+        None => panic!("{} isn't saved as a map, it can't carry a tag", A::SPECIES),
+    }
+    value
+}
+
+impl Serialize for dyn Animal
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    {
+        match self.save() {
+            Some(value) => value.serialize(serializer),
+            None => Err(ser::Error::custom(format!("{} is of a species that can't be saved", self.name()))),
+        }
+    }
+}
+
+/////////////////////////////////////
+
+// an animal of a species the loader didn't know, kept as it was read
+pub struct Opaque
+{
+    pub species: String,
+    // every field, the tag included
+    pub value: Value,
+}
+
+impl Animal for Opaque
+{
+    // an opaque animal only comes from a loader
+    fn create(_: impl Into<std::borrow::Cow<'static, str>>) -> Opaque
+    {
+        Opaque { species: String::new(), value: Value::Object(Map::new()) }
+    }
+
+    // the name field if it has one
+    fn name(&self) -> &str { self.value.get("name").and_then(Value::as_str).unwrap_or("") }
+
+    fn talk(&self)
+    {
+        println!("{} is an unknown {}", self.name(), self.species);
+    }
+
+    fn save(&self) -> Option<Value> { Some(self.value.clone()) }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnknownSpecies
+{
+    Error,
+    Opaque,
+}
+
+type Load = fn(Value) -> Result<Box<dyn Animal>, serde_json::Error>;
+
+fn load<A: Species>(value: Value) -> Result<Box<dyn Animal>, serde_json::Error>
+{
+    Ok(Box::new(serde_json::from_value::<A>(value)?))
+}
+
+// species tag -> type. with_defaults() knows humans and cats
+pub struct Loader
+{
+    species: BTreeMap<&'static str, Load>,
+    pub unknown: UnknownSpecies,
+}
+
+impl Loader
+{
+    pub fn new(unknown: UnknownSpecies) -> Loader { Loader { species: BTreeMap::new(), unknown } }
+
+    pub fn with_defaults(unknown: UnknownSpecies) -> Loader
+    {
+        let mut loader = Loader::new(unknown);
+        loader.register::<Human>().unwrap();
+        loader.register::<Cat>().unwrap();
+        loader
+    }
+
+    pub fn register<A: Species>(&mut self) -> Result<(), RegistryError>
+    {
+        if self.species.contains_key(A::SPECIES) {
+            return Err(RegistryError::Duplicate(A::SPECIES.to_string()));
+        }
+        self.species.insert(A::SPECIES, load::<A>);
+        Ok(())
+    }
+
+    pub fn species(&self) -> impl Iterator<Item = &str> { self.species.keys().copied() }
+
+    // one saved animal, load_json reads a whole list
+    pub fn load(&self, mut value: Value) -> Result<Box<dyn Animal>, serde_json::Error>
+    {
+        use serde::de::Error;
+
+        let species = match value.as_object_mut().and_then(|fields| fields.remove(TAG)) {
+            Some(Value::String(species)) => species,
+            Some(other) => return Err(Error::custom(format!("the species tag has to be a string, not {}", other))),
+            None => return Err(Error::missing_field(TAG)),
+        };
+        match (self.species.get(species.as_str()), self.unknown) {
+            (Some(load), _) => load(value),
+            (None, UnknownSpecies::Error) => Err(Error::custom(RegistryError::Unknown(species))),
+            (None, UnknownSpecies::Opaque) => {
+                // put the tag back, so the animal saves as it came
+                value[TAG] = Value::String(species.clone());
+                Ok(Box::new(Opaque { species, value }))
+            }
+        }
+    }
+
+    pub fn load_json(&self, json: &str) -> Result<Vec<Box<dyn Animal>>, serde_json::Error>
+    {
+        let values: Vec<Value> = serde_json::from_str(json)?;
+        values.into_iter().map(|value| self.load(value)).collect()
+    }
+}
+
+// for serde, deserializing a single animal with a loader:
+// loader.deserialize(&mut deserializer)
+impl<'de> DeserializeSeed<'de> for &Loader
+{
+    type Value = Box<dyn Animal>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Box<dyn Animal>, D::Error>
+    {
+        let value = Value::deserialize(deserializer)?;
+        self.load(value).map_err(de::Error::custom)
+    }
+}
+
+/////////////////////////////////////
+
+pub fn main()
+{
+    use crate::animal::Creature;
+
+    #[derive(Serialize, Deserialize)]
+    struct Parrot
+    {
+        name: String,
+        words: Vec<String>,
+    }
+
+    impl Animal for Parrot
+    {
+        fn create(name: impl Into<std::borrow::Cow<'static, str>>) -> Parrot
+        {
+            Parrot { name: name.into().into_owned(), words: vec![] }
+        }
+
+        fn name(&self) -> &str { &self.name }
+
+        fn talk(&self) { println!("{} says {}", self.name, self.words.join(", ")); }
+
+        fn save(&self) -> Option<Value> { Some(tagged(self)) }
+    }
+
+    impl Species for Parrot
+    {
+        const SPECIES: &'static str = "parrot";
+    }
+
+    let zoo: Vec<Box<dyn Animal>> = vec![
+        Box::new(Human::create("John")),
+        Box::new(Cat::create("Misty".to_string())),
+        Box::new(Parrot { name: "Polly".into(), words: vec!["hello".into(), "cracker".into()] }),
+        Box::new(Creature::Cat(Cat::create("Tom"))),
+    ];
+    let json = serde_json::to_string(&zoo).unwrap();
+    println!("{}", json);
+    assert!(json.starts_with(r#"[{"name":"John","species":"human"},{"name":"Misty","species":"cat"}"#));
+
+    // a loader that knows parrots gets the same zoo back
+    let mut loader = Loader::with_defaults(UnknownSpecies::Error);
+    loader.register::<Parrot>().unwrap();
+    assert_eq!(loader.register::<Cat>().unwrap_err(), RegistryError::Duplicate("cat".to_string()));
+    let back = loader.load_json(&json).unwrap();
+    for animal in &back { animal.talk(); }
+    assert_eq!(back.iter().map(|a| a.name()).collect::<Vec<_>>(), ["John", "Misty", "Polly", "Tom"]);
+    assert_eq!(serde_json::to_string(&back).unwrap(), json);
+
+    // one that doesn't: an error, or the parrot passes through untouched
+    let strict = Loader::with_defaults(UnknownSpecies::Error);
+    let error = strict.load_json(&json).err().unwrap();
+    println!("{}", error);
+    assert!(error.to_string().contains(r#"unknown species "parrot""#));
+
+    let lenient = Loader::with_defaults(UnknownSpecies::Opaque);
+    let kept = lenient.load_json(&json).unwrap();
+    kept[2].talk();
+    assert_eq!(kept[2].name(), "Polly");
+    assert_eq!(serde_json::to_string(&kept).unwrap(), json);
+
+    // broken input says what's wrong
+    assert!(strict.load_json(r#"[{"name":"Rex"}]"#).err().unwrap().to_string().contains("missing field `species`"));
+    assert!(strict.load_json(r#"[{"species":"cat"}]"#).err().unwrap().to_string().contains("missing field `name`"));
+    assert!(strict.load_json(r#"[{"species":7}]"#).is_err());
+
+    // the seed works inside any serde deserializer
+    let mut deserializer = serde_json::Deserializer::from_str(r#"{"species":"human","name":"Ada"}"#);
+    let ada = (&loader).deserialize(&mut deserializer).unwrap();
+    assert_eq!(ada.name(), "Ada");
+
+    // animals without a species can't be saved, saying which one
+    struct Ghost;
+    impl Animal for Ghost
+    {
+        fn create(_: impl Into<std::borrow::Cow<'static, str>>) -> Ghost { Ghost }
+
+        fn name(&self) -> &str { "Boo" }
+    }
+    let haunted: Vec<Box<dyn Animal>> = vec![Box::new(Ghost)];
+    assert!(serde_json::to_string(&haunted).unwrap_err().to_string().contains("Boo"));
+}
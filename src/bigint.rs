@@ -0,0 +1,840 @@
+// arbitrary precision integers, stored as a sign and a magnitude
+//
+// the magnitude is a vector of 64 bit limbs, least significant first, with no
+// zero limbs on top: zero is the empty vector and is never negative, so every
+// number has exactly one representation and == compares the fields
+//
+// multiplication is schoolbook for short numbers and karatsuba once both
+// sides have KARATSUBA_THRESHOLD limbs, division is knuth's algorithm D.
+// / and % truncate toward zero like the primitive integers do, the remainder
+// takes the sign of the dividend
+//
+// BigInt has every operator by value, so it works as the parts of a Complex,
+// and implements CheckedInt (nothing ever overflows) for Rational<BigInt>
+
+use crate::num::{Integer, One, Signed, Zero};
+use crate::rational::CheckedInt;
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter::{Product, Sum};
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Rem, RemAssign, Sub, SubAssign};
+use std::str::FromStr;
+
+// below this many limbs the three half size products of karatsuba cost more
+// than the four of schoolbook save
+const KARATSUBA_THRESHOLD: usize = 32;
+
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct BigInt
+{
+    negative: bool,
+    mag: Vec<u64>,
+}
+
+/////////////////////////////////////
+// magnitudes: little endian limbs, the functions below never look at signs
+
+fn trim(v: &mut Vec<u64>)
+{
+    while v.last() == Some(&0) { v.pop(); }
+}
+
+fn cmp_mag(a: &[u64], b: &[u64]) -> Ordering
+{
+    a.len().cmp(&b.len()).then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_mag(a: &[u64], b: &[u64]) -> Vec<u64>
+{
+    let (long, short) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    let mut out = Vec::with_capacity(long.len() + 1);
+    let mut carry = 0;
+    for (i, &x) in long.iter().enumerate() {
+        let (s1, c1) = x.overflowing_add(short.get(i).copied().unwrap_or(0));
+        let (s2, c2) = s1.overflowing_add(carry);
+        out.push(s2);
+        carry = c1 as u64 + c2 as u64;
+    }
+    if carry > 0 { out.push(carry); }
+    out
+}
+
+// a - b, a must be at least b
+fn sub_mag(a: &[u64], b: &[u64]) -> Vec<u64>
+{
+    let mut out = Vec::with_capacity(a.len());
+    let mut borrow = 0;
+    for (i, &x) in a.iter().enumerate() {
+        let (d1, b1) = x.overflowing_sub(b.get(i).copied().unwrap_or(0));
+        let (d2, b2) = d1.overflowing_sub(borrow);
+        out.push(d2);
+        borrow = b1 as u64 + b2 as u64;
+    }
+    debug_assert_eq!(borrow, 0, "sub_mag needs a >= b");
+    trim(&mut out);
+    out
+}
+
+// dst[offset..] += src, dst has to be long enough for the carries
+fn add_into(dst: &mut [u64], src: &[u64], offset: usize)
+{
+    let mut carry = 0;
+    for (d, &x) in dst[offset..].iter_mut().zip(src) {
+        let (s1, c1) = d.overflowing_add(x);
+        let (s2, c2) = s1.overflowing_add(carry);
+        *d = s2;
+        carry = c1 as u64 + c2 as u64;
+    }
+    for d in dst[offset + src.len()..].iter_mut() {
+        if carry == 0 { break; }
+        let (s, c) = d.overflowing_add(carry);
+        *d = s;
+        carry = c as u64;
+    }
+}
+
+// (2^64 - 1)^2 plus two more limbs still fits in a u128, so no carry is lost
+fn mul_schoolbook(a: &[u64], b: &[u64]) -> Vec<u64>
+{
+    let mut out = vec![0; a.len() + b.len()];
+    for (i, &x) in a.iter().enumerate() {
+        let mut carry = 0u128;
+        for (j, &y) in b.iter().enumerate() {
+            let t = x as u128 * y as u128 + out[i + j] as u128 + carry;
+            out[i + j] = t as u64;
+            carry = t >> 64;
+        }
+        out[i + b.len()] = carry as u64;
+    }
+    trim(&mut out);
+    out
+}
+
+// karatsuba: with a = a1 B + a0 and b = b1 B + b0,
+// ab = a1 b1 B^2 + ((a0 + a1)(b0 + b1) - a0 b0 - a1 b1) B + a0 b0
+// three products of half the size instead of four
+fn mul_mag(a: &[u64], b: &[u64]) -> Vec<u64>
+{
+    if a.is_empty() || b.is_empty() { return vec![]; }
+    if a.len().min(b.len()) < KARATSUBA_THRESHOLD { return mul_schoolbook(a, b); }
+
+    let m = a.len().max(b.len()) / 2;
+    let mut out = vec![0; a.len() + b.len()];
+    if a.len() <= m || b.len() <= m {
+        // too lopsided to split both: cut the long one, the pieces go back through here
+        let (long, short) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+        let (lo, hi) = long.split_at(m);
+        add_into(&mut out, &mul_mag(lo, short), 0);
+        add_into(&mut out, &mul_mag(hi, short), m);
+    } else {
+        let (a0, a1) = a.split_at(m);
+        let (b0, b1) = b.split_at(m);
+        let z0 = mul_mag(a0, b0);
+        let z2 = mul_mag(a1, b1);
+        let z1 = sub_mag(&sub_mag(&mul_mag(&add_mag(a0, a1), &add_mag(b0, b1)), &z0), &z2);
+        add_into(&mut out, &z0, 0);
+        add_into(&mut out, &z1, m);
+        add_into(&mut out, &z2, 2 * m);
+    }
+    trim(&mut out);
+    out
+}
+
+// a * mul + add in place
+fn mul_small_add(a: &mut Vec<u64>, mul: u64, add: u64)
+{
+    let mut carry = add as u128;
+    for x in a.iter_mut() {
+        let t = *x as u128 * mul as u128 + carry;
+        *x = t as u64;
+        carry = t >> 64;
+    }
+    if carry > 0 { a.push(carry as u64); }
+}
+
+fn divrem_small(a: &[u64], d: u64) -> (Vec<u64>, u64)
+{
+    let mut q = vec![0; a.len()];
+    let mut r = 0u128;
+    for (qi, &x) in q.iter_mut().zip(a).rev() {
+        let cur = (r << 64) | x as u128;
+        *qi = (cur / d as u128) as u64;
+        r = cur % d as u128;
+    }
+    trim(&mut q);
+    (q, r as u64)
+}
+
+// shifted left by s < 64 bits, always one limb longer than v
+fn shl_bits(v: &[u64], s: u32) -> Vec<u64>
+{
+    let mut out = Vec::with_capacity(v.len() + 1);
+    let mut carry = 0;
+    for &x in v {
+        out.push(if s == 0 { x } else { (x << s) | carry });
+        carry = if s == 0 { 0 } else { x >> (64 - s) };
+    }
+    out.push(carry);
+    out
+}
+
+// knuth's algorithm D (the art of computer programming 4.3.1, in the shape of
+// hacker's delight divmnu). v needs at least one limb
+fn divrem_mag(u: &[u64], v: &[u64]) -> (Vec<u64>, Vec<u64>)
+{
+    if cmp_mag(u, v) == Ordering::Less { return (vec![], u.to_vec()); }
+    if v.len() == 1 {
+        let (q, r) = divrem_small(u, v[0]);
+        return (q, if r == 0 { vec![] } else { vec![r] });
+    }
+
+    // normalize so the top limb of the divisor has its high bit set, then the
+    // estimate from the top two limbs is at most 2 too big
+    let s = v[v.len() - 1].leading_zeros();
+    let mut vn = shl_bits(v, s);
+    vn.pop();
+    let mut un = shl_bits(u, s);
+    let n = vn.len();
+    let m = u.len() - n;
+    let base = 1u128 << 64;
+    let mut q = vec![0; m + 1];
+
+    for j in (0..=m).rev() {
+        let top = ((un[j + n] as u128) << 64) | un[j + n - 1] as u128;
+        let mut qhat = top / vn[n - 1] as u128;
+        let mut rhat = top % vn[n - 1] as u128;
+        while qhat >= base || qhat * vn[n - 2] as u128 > ((rhat << 64) | un[j + n - 2] as u128) {
+            qhat -= 1;
+            rhat += vn[n - 1] as u128;
+            if rhat >= base { break; }
+        }
+
+        // un[j..=j + n] -= qhat * vn, k is the borrow
+        let mut k = 0i128;
+        for (i, &d) in vn.iter().enumerate() {
+            let p = qhat * d as u128;
+            let t = un[i + j] as i128 - k - (p as u64) as i128;
+            un[i + j] = t as u64;
+            k = (p >> 64) as i128 - (t >> 64);
+        }
+        let t = un[j + n] as i128 - k;
+        un[j + n] = t as u64;
+
+        // qhat was still one too big (rare): add the divisor back
+        if t < 0 {
+            qhat -= 1;
+            let mut carry = 0u128;
+            for (i, &d) in vn.iter().enumerate() {
+                let s = un[i + j] as u128 + d as u128 + carry;
+                un[i + j] = s as u64;
+                carry = s >> 64;
+            }
+            un[j + n] = un[j + n].wrapping_add(carry as u64);
+        }
+        q[j] = qhat as u64;
+    }
+
+    // the remainder is what's left in the low n limbs, shifted back
+    let mut r: Vec<u64> = (0..n)
+        .map(|i| if s == 0 { un[i] } else { (un[i] >> s) | (un[i + 1] << (64 - s)) })
+        .collect();
+    trim(&mut q);
+    trim(&mut r);
+    (q, r)
+}
+
+// the largest power of radix that fits in a u64, and its exponent: that many
+// digits go in or out with every single limb operation
+fn radix_chunk(radix: u32) -> (u64, usize)
+{
+    let (mut power, mut digits) = (radix as u64, 1);
+    while let Some(next) = power.checked_mul(radix as u64) {
+        power = next;
+        digits += 1;
+    }
+    (power, digits)
+}
+
+/////////////////////////////////////
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseErrorKind
+{
+    Empty,
+    InvalidDigit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseBigIntError
+{
+    pub kind: ParseErrorKind,
+    // byte offset into the input where the bad part starts
+    pub position: usize,
+}
+
+impl fmt::Display for ParseBigIntError
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        let what = match self.kind {
+            ParseErrorKind::Empty => "no digits",
+            ParseErrorKind::InvalidDigit => "invalid digit",
+        };
+        write!(f, "cannot parse integer: {} at position {}", what, self.position)
+    }
+}
+
+impl std::error::Error for ParseBigIntError {}
+
+impl BigInt
+{
+    fn from_parts(negative: bool, mut mag: Vec<u64>) -> BigInt
+    {
+        trim(&mut mag);
+        BigInt { negative: negative && !mag.is_empty(), mag }
+    }
+
+    pub fn zero() -> BigInt { BigInt::default() }
+
+    pub fn one() -> BigInt { BigInt::from_parts(false, vec![1]) }
+
+    pub fn is_zero(&self) -> bool { self.mag.is_empty() }
+
+    pub fn is_negative(&self) -> bool { self.negative }
+
+    pub fn is_even(&self) -> bool { self.mag.first().is_none_or(|x| x & 1 == 0) }
+
+    // -1, 0 or 1
+    pub fn signum(&self) -> i32
+    {
+        if self.negative { -1 } else if self.is_zero() { 0 } else { 1 }
+    }
+
+    pub fn abs(&self) -> BigInt { BigInt::from_parts(false, self.mag.clone()) }
+
+    // the number of bits of |self|, 0 for zero
+    pub fn bits(&self) -> u64
+    {
+        match self.mag.last() {
+            Some(top) => self.mag.len() as u64 * 64 - top.leading_zeros() as u64,
+            None => 0,
+        }
+    }
+
+    fn bit(&self, i: u64) -> bool
+    {
+        self.mag.get((i / 64) as usize).is_some_and(|limb| limb >> (i % 64) & 1 == 1)
+    }
+
+    // truncating division, panics on a zero divisor like the primitives do
+    pub fn div_rem(&self, other: &BigInt) -> (BigInt, BigInt)
+    {
+        assert!(!other.is_zero(), "attempt to divide by zero");
+        let (q, r) = divrem_mag(&self.mag, &other.mag);
+        (BigInt::from_parts(self.negative != other.negative, q), BigInt::from_parts(self.negative, r))
+    }
+
+    // the remainder in [0, |m|), whatever the signs
+    pub fn rem_euclid(&self, m: &BigInt) -> BigInt
+    {
+        let r = self.div_rem(m).1;
+        if r.negative { &r + &m.abs() } else { r }
+    }
+
+    // by squaring, 0^0 = 1
+    pub fn pow(&self, mut exp: u32) -> BigInt
+    {
+        let mut base = self.clone();
+        let mut result = BigInt::one();
+        while exp > 0 {
+            if exp & 1 == 1 { result = &result * &base; }
+            exp >>= 1;
+            if exp > 0 { base = &base * &base; }
+        }
+        result
+    }
+
+    // self^exp mod m, in [0, |m|). left to right square and multiply, reduced
+    // after every step so nothing grows past m^2. panics if m is 0 or exp is
+    // negative
+    pub fn modpow(&self, exp: &BigInt, m: &BigInt) -> BigInt
+    {
+        assert!(!exp.negative, "modpow needs a non negative exponent");
+        let m = m.abs();
+        assert!(!m.is_zero(), "modpow with a zero modulus");
+
+        let base = self.rem_euclid(&m);
+        let mut result = BigInt::one().rem_euclid(&m);
+        for i in (0..exp.bits()).rev() {
+            result = (&result * &result).rem_euclid(&m);
+            if exp.bit(i) { result = (&result * &base).rem_euclid(&m); }
+        }
+        result
+    }
+
+    // never negative, gcd(0, 0) = 0
+    pub fn gcd(&self, other: &BigInt) -> BigInt
+    {
+        let (mut a, mut b) = (self.abs(), other.abs());
+        while !b.is_zero() {
+            let r = a.div_rem(&b).1;
+            a = b;
+            b = r;
+        }
+        a
+    }
+
+    /////////////////////////////////////
+
+    // an optional sign and digits 0-9 then a-z (either case) for radix 2 to 36.
+    // panics for any other radix, like i64::from_str_radix
+    pub fn from_str_radix(s: &str, radix: u32) -> Result<BigInt, ParseBigIntError>
+    {
+        assert!((2..=36).contains(&radix), "radix must be between 2 and 36, got {}", radix);
+
+        let (negative, start) = match s.as_bytes().first() {
+            Some(b'-') => (true, 1),
+            Some(b'+') => (false, 1),
+            _ => (false, 0),
+        };
+        let digits = &s[start..];
+        if digits.is_empty() { return Err(ParseBigIntError { kind: ParseErrorKind::Empty, position: start }); }
+        if let Some(i) = digits.char_indices().find(|(_, c)| !c.is_digit(radix)).map(|(i, _)| i) {
+            return Err(ParseBigIntError { kind: ParseErrorKind::InvalidDigit, position: start + i });
+        }
+
+        // the leading chunk takes whatever is left over, the rest are full
+        let (power, chunk) = radix_chunk(radix);
+        let first = match digits.len() % chunk {
+            0 => chunk,
+            r => r,
+        };
+        let value = |text: &str| u64::from_str_radix(text, radix).unwrap();
+
+        let mut mag = vec![];
+        mul_small_add(&mut mag, 1, value(&digits[..first]));
+        for i in (first..digits.len()).step_by(chunk) {
+            mul_small_add(&mut mag, power, value(&digits[i..i + chunk]));
+        }
+        Ok(BigInt::from_parts(negative, mag))
+    }
+
+    // lowercase digits, a leading - for negative numbers
+    pub fn to_str_radix(&self, radix: u32) -> String
+    {
+        assert!((2..=36).contains(&radix), "radix must be between 2 and 36, got {}", radix);
+        if self.is_zero() { return "0".to_string(); }
+
+        // peel off chunks from the bottom, every one but the top is zero padded
+        let (power, chunk) = radix_chunk(radix);
+        let mut chunks = vec![];
+        let mut rest = self.mag.clone();
+        while !rest.is_empty() {
+            let (q, r) = divrem_small(&rest, power);
+            chunks.push(r);
+            rest = q;
+        }
+
+        let digits = |mut x: u64, width: usize| {
+            let mut out = vec![b'0'; width];
+            for slot in out.iter_mut().rev() {
+                *slot = std::char::from_digit((x % radix as u64) as u32, radix).unwrap() as u8;
+                x /= radix as u64;
+            }
+            String::from_utf8(out).unwrap()
+        };
+        let mut s = String::from(if self.negative { "-" } else { "" });
+        let top = chunks.pop().unwrap();
+        s.push_str(digits(top, chunk).trim_start_matches('0'));
+        for c in chunks.iter().rev() { s.push_str(&digits(*c, chunk)); }
+        s
+    }
+
+    pub fn to_i64(&self) -> Option<i64>
+    {
+        match self.mag.len() {
+            0 => Some(0),
+            1 if self.negative => 0i64.checked_sub_unsigned(self.mag[0]),
+            1 => i64::try_from(self.mag[0]).ok(),
+            _ => None,
+        }
+    }
+
+    // the nearest f64 (up to rounding of the top bits), infinite past f64::MAX
+    pub fn to_f64(&self) -> f64
+    {
+        // the top 128 bits are more than the mantissa can hold anyway
+        let n = self.mag.len();
+        let value = match n {
+            0 => 0.0,
+            1 => self.mag[0] as f64,
+            _ => {
+                let top = ((self.mag[n - 1] as u128) << 64) | self.mag[n - 2] as u128;
+                top as f64 * 2f64.powi(64 * (n as i32 - 2))
+            }
+        };
+        if self.negative { -value } else { value }
+    }
+}
+
+/////////////////////////////////////
+
+macro_rules! bigint_from_signed {
+    ($($t:ty),*) => {$(
+        impl From<$t> for BigInt
+        {
+            fn from(x: $t) -> BigInt
+            {
+                let m = (x as i128).unsigned_abs();
+                BigInt::from_parts(x < 0, vec![m as u64, (m >> 64) as u64])
+            }
+        }
+    )*};
+}
+
+macro_rules! bigint_from_unsigned {
+    ($($t:ty),*) => {$(
+        impl From<$t> for BigInt
+        {
+            fn from(x: $t) -> BigInt
+            {
+                let m = x as u128;
+                BigInt::from_parts(false, vec![m as u64, (m >> 64) as u64])
+            }
+        }
+    )*};
+}
+
+bigint_from_signed!(i8, i16, i32, i64, i128, isize);
+bigint_from_unsigned!(u8, u16, u32, u64, u128, usize);
+
+impl Ord for BigInt
+{
+    fn cmp(&self, other: &BigInt) -> Ordering
+    {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => cmp_mag(&self.mag, &other.mag),
+            (true, true) => cmp_mag(&other.mag, &self.mag),
+        }
+    }
+}
+
+impl PartialOrd for BigInt
+{
+    fn partial_cmp(&self, other: &BigInt) -> Option<Ordering> { Some(self.cmp(other)) }
+}
+
+/////////////////////////////////////
+
+// a + b where b's sign is given separately, so subtraction needs no copy of b
+fn add_signed(a: &BigInt, b_mag: &[u64], b_negative: bool) -> BigInt
+{
+    if a.negative == b_negative { return BigInt::from_parts(a.negative, add_mag(&a.mag, b_mag)); }
+    match cmp_mag(&a.mag, b_mag) {
+        Ordering::Equal => BigInt::zero(),
+        Ordering::Greater => BigInt::from_parts(a.negative, sub_mag(&a.mag, b_mag)),
+        Ordering::Less => BigInt::from_parts(b_negative, sub_mag(b_mag, &a.mag)),
+    }
+}
+
+impl Add<&BigInt> for &BigInt
+{
+    type Output = BigInt;
+
+    fn add(self, rhs: &BigInt) -> BigInt { add_signed(self, &rhs.mag, rhs.negative) }
+}
+
+impl Sub<&BigInt> for &BigInt
+{
+    type Output = BigInt;
+
+    fn sub(self, rhs: &BigInt) -> BigInt { add_signed(self, &rhs.mag, !rhs.negative) }
+}
+
+impl Mul<&BigInt> for &BigInt
+{
+    type Output = BigInt;
+
+    fn mul(self, rhs: &BigInt) -> BigInt
+    {
+        BigInt::from_parts(self.negative != rhs.negative, mul_mag(&self.mag, &rhs.mag))
+    }
+}
+
+impl Div<&BigInt> for &BigInt
+{
+    type Output = BigInt;
+
+    fn div(self, rhs: &BigInt) -> BigInt { self.div_rem(rhs).0 }
+}
+
+impl Rem<&BigInt> for &BigInt
+{
+    type Output = BigInt;
+
+    fn rem(self, rhs: &BigInt) -> BigInt { self.div_rem(rhs).1 }
+}
+
+impl Neg for BigInt
+{
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt { BigInt::from_parts(!self.negative, self.mag) }
+}
+
+impl Neg for &BigInt
+{
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt { -self.clone() }
+}
+
+// the by value versions and the assignments borrow and forward to the ones above
+macro_rules! bigint_value_ops {
+    ($($imp:ident $method:ident $assign_imp:ident $assign_method:ident),*) => {$(
+        impl $imp for BigInt
+        {
+            type Output = BigInt;
+
+            fn $method(self, rhs: BigInt) -> BigInt { (&self).$method(&rhs) }
+        }
+
+        impl<'b> $imp<&'b BigInt> for BigInt
+        {
+            type Output = BigInt;
+
+            fn $method(self, rhs: &'b BigInt) -> BigInt { (&self).$method(rhs) }
+        }
+
+        impl<'a> $imp<BigInt> for &'a BigInt
+        {
+            type Output = BigInt;
+
+            fn $method(self, rhs: BigInt) -> BigInt { self.$method(&rhs) }
+        }
+
+        impl $assign_imp for BigInt
+        {
+            fn $assign_method(&mut self, rhs: BigInt) { *self = (&*self).$method(&rhs); }
+        }
+
+        impl<'b> $assign_imp<&'b BigInt> for BigInt
+        {
+            fn $assign_method(&mut self, rhs: &'b BigInt) { *self = (&*self).$method(rhs); }
+        }
+    )*};
+}
+
+bigint_value_ops!(
+    Add add AddAssign add_assign,
+    Sub sub SubAssign sub_assign,
+    Mul mul MulAssign mul_assign,
+    Div div DivAssign div_assign,
+    Rem rem RemAssign rem_assign
+);
+
+impl Sum for BigInt
+{
+    fn sum<I: Iterator<Item = BigInt>>(iter: I) -> BigInt { iter.fold(BigInt::zero(), |acc, x| acc + x) }
+}
+
+impl<'a> Sum<&'a BigInt> for BigInt
+{
+    fn sum<I: Iterator<Item = &'a BigInt>>(iter: I) -> BigInt { iter.fold(BigInt::zero(), |acc, x| acc + x) }
+}
+
+impl Product for BigInt
+{
+    fn product<I: Iterator<Item = BigInt>>(iter: I) -> BigInt { iter.fold(BigInt::one(), |acc, x| acc * x) }
+}
+
+impl<'a> Product<&'a BigInt> for BigInt
+{
+    fn product<I: Iterator<Item = &'a BigInt>>(iter: I) -> BigInt { iter.fold(BigInt::one(), |acc, x| acc * x) }
+}
+
+impl Zero for BigInt
+{
+    fn zero() -> BigInt { BigInt::zero() }
+    fn is_zero(&self) -> bool { BigInt::is_zero(self) }
+}
+
+impl One for BigInt
+{
+    fn one() -> BigInt { BigInt::one() }
+}
+
+impl Signed for BigInt
+{
+    fn abs(&self) -> BigInt { BigInt::abs(self) }
+    fn signum(&self) -> BigInt { BigInt::from(BigInt::signum(self)) }
+    fn is_negative(&self) -> bool { self.negative }
+    fn is_positive(&self) -> bool { !self.negative && !self.mag.is_empty() }
+}
+
+impl Integer for BigInt
+{
+    fn div_floor(&self, other: &BigInt) -> BigInt
+    {
+        let (q, r) = self.div_rem(other);
+        if !r.is_zero() && r.negative != other.negative { q - BigInt::one() } else { q }
+    }
+
+    fn mod_floor(&self, other: &BigInt) -> BigInt
+    {
+        let r = self % other;
+        if !r.is_zero() && r.negative != other.negative { r + other } else { r }
+    }
+
+    fn gcd(&self, other: &BigInt) -> BigInt { BigInt::gcd(self, other) }
+
+    fn lcm(&self, other: &BigInt) -> BigInt
+    {
+        if self.is_zero() || other.is_zero() { return BigInt::zero(); }
+        (self / &BigInt::gcd(self, other) * other).abs()
+    }
+
+    fn is_even(&self) -> bool { BigInt::is_even(self) }
+}
+
+/////////////////////////////////////
+
+impl fmt::Display for BigInt
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        f.pad_integral(!self.negative, "", &self.abs().to_str_radix(10))
+    }
+}
+
+// the digits, not the limbs
+impl fmt::Debug for BigInt
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { fmt::Display::fmt(self, f) }
+}
+
+// unlike the primitives a negative number prints as - and its magnitude, there
+// is no two's complement to show. {:#x} adds the 0x prefix
+macro_rules! bigint_radix_fmt {
+    ($($imp:ident $radix:expr, $prefix:expr, $upper:expr);*) => {$(
+        impl fmt::$imp for BigInt
+        {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+            {
+                let digits = self.abs().to_str_radix($radix);
+                let digits = if $upper { digits.to_uppercase() } else { digits };
+                f.pad_integral(!self.negative, $prefix, &digits)
+            }
+        }
+    )*};
+}
+
+bigint_radix_fmt!(Binary 2, "0b", false; Octal 8, "0o", false; LowerHex 16, "0x", false; UpperHex 16, "0x", true);
+
+impl FromStr for BigInt
+{
+    type Err = ParseBigIntError;
+
+    fn from_str(s: &str) -> Result<BigInt, ParseBigIntError> { BigInt::from_str_radix(s, 10) }
+}
+
+impl CheckedInt for BigInt
+{
+    fn checked_add(&self, other: &BigInt) -> Option<BigInt> { Some(self + other) }
+    fn checked_sub(&self, other: &BigInt) -> Option<BigInt> { Some(self - other) }
+    fn checked_mul(&self, other: &BigInt) -> Option<BigInt> { Some(self * other) }
+    fn checked_div(&self, other: &BigInt) -> Option<BigInt> { if other.is_zero() { None } else { Some(self / other) } }
+    fn checked_neg(&self) -> Option<BigInt> { Some(-self) }
+    fn from_i64(x: i64) -> Option<BigInt> { Some(BigInt::from(x)) }
+    fn to_f64(&self) -> f64 { BigInt::to_f64(self) }
+}
+
+/////////////////////////////////////
+
+pub fn main()
+{
+    use crate::rational::Rational;
+    use crate::{Complex, SumExact, Summable};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    let big = |s: &str| s.parse::<BigInt>().unwrap();
+
+    assert_eq!(BigInt::from(2).pow(100), big("1267650600228229401496703205376"));
+    let factorial: BigInt = (1..=30).map(BigInt::from).product();
+    assert_eq!(factorial, big("265252859812191058636308480000000"));
+    println!("30! = {}, {:#x}", factorial, factorial);
+
+    // everything against i128 on values that fit, including the signs of / and %
+    let mut rng = StdRng::seed_from_u64(40);
+    for _ in 0..20_000 {
+        let a: i128 = rng.gen::<i64>() as i128 * if rng.gen_bool(0.5) { rng.gen::<i32>() as i128 } else { 1 };
+        let b: i128 = if rng.gen_bool(0.1) { rng.gen_range(-3..=3) } else { rng.gen::<i64>() as i128 };
+        let (x, y) = (BigInt::from(a), BigInt::from(b));
+        assert_eq!(&x + &y, BigInt::from(a + b));
+        assert_eq!(&x - &y, BigInt::from(a - b));
+        assert_eq!(x.cmp(&y), a.cmp(&b));
+        if let Some(p) = a.checked_mul(b) { assert_eq!(&x * &y, BigInt::from(p)); }
+        if b != 0 {
+            assert_eq!(&x / &y, BigInt::from(a / b), "{} / {}", a, b);
+            assert_eq!(&x % &y, BigInt::from(a % b), "{} % {}", a, b);
+        }
+    }
+
+    // big numbers: karatsuba against schoolbook, division undoes multiplication
+    let mut random = |limbs: usize| {
+        let mag: Vec<u64> = (0..limbs).map(|_| rng.gen()).collect();
+        BigInt::from_parts(rng.gen_bool(0.5), mag)
+    };
+    for (la, lb) in [(40, 40), (100, 37), (300, 250), (33, 700), (2, 90)] {
+        let (a, b) = (random(la), random(lb));
+        let product = &a * &b;
+        assert_eq!(product.mag, mul_schoolbook(&a.mag, &b.mag), "{}x{} limbs", la, lb);
+        let c = random(lb / 2 + 1);
+        let (q, r) = (&product + &c).div_rem(&b);
+        assert_eq!(&q * &b + &r, &product + &c);
+        assert!(cmp_mag(&r.mag, &b.mag) == Ordering::Less);
+    }
+
+    // every radix round trips
+    let x = random(9);
+    for radix in 2..=36 {
+        assert_eq!(BigInt::from_str_radix(&x.to_str_radix(radix), radix), Ok(x.clone()));
+    }
+    assert_eq!(BigInt::from_str_radix("-ff", 16), Ok(BigInt::from(-255)));
+    assert_eq!(format!("{:b}", BigInt::from(-5)), "-101");
+    assert_eq!(BigInt::from_str_radix("12z", 10).unwrap_err().position, 2);
+    assert_eq!("-".parse::<BigInt>().unwrap_err().kind, ParseErrorKind::Empty);
+
+    // modpow: fermat's little theorem for the mersenne prime 2^127 - 1
+    let p = &BigInt::from(2).pow(127) - &BigInt::one();
+    assert_eq!(BigInt::from(3).modpow(&(&p - &BigInt::one()), &p), BigInt::one());
+    assert_eq!(BigInt::from(4).modpow(&BigInt::from(13), &BigInt::from(497)), BigInt::from(445));
+    assert_eq!(BigInt::from(-3).modpow(&BigInt::from(3), &BigInt::from(5)), BigInt::from(3));
+    let g = (&BigInt::from(3) * &BigInt::from(2).pow(200)).gcd(&(&BigInt::from(9) * &BigInt::from(2).pow(100)));
+    assert_eq!(g, &BigInt::from(3) * &BigInt::from(2).pow(100));
+
+    // the sum that overflows Summable<i32>
+    let values: Vec<BigInt> = vec![BigInt::from(i32::MAX); 10];
+    let total = values.sum();
+    assert_eq!(total, BigInt::from(i32::MAX as i64 * 10));
+    // the same without converting first
+    assert_eq!(vec![i32::MAX; 10].sum_exact(), total);
+    assert_eq!(vec![i32::MIN, -1].sum_exact(), BigInt::from(i32::MIN as i64 - 1));
+    println!("10 x i32::MAX = {}", total);
+
+    // exact gaussian integers and fractions
+    let z = Complex::new(BigInt::from(3), BigInt::from(4));
+    let mut power = z.clone();
+    for _ in 1..40 { power *= z.clone(); }
+    println!("(3+4i)^40 = {}", power);
+    // |z^40| = 5^40 exactly
+    assert_eq!(&power.re * &power.re + &power.im * &power.im, BigInt::from(5).pow(80));
+
+    let harmonic: Rational<BigInt> = (1..=100).map(|k| Rational::new(BigInt::one(), BigInt::from(k))).sum();
+    println!("H(100) = {}", harmonic);
+    assert!((harmonic.to_f64() - 5.187377517639621).abs() < 1e-12);
+}
@@ -0,0 +1,451 @@
+// turn based fights between teams of creatures
+//
+// every round each creature rolls initiative (speed plus a d6) and acts in
+// that order: status effects tick first, then a stunned creature loses its
+// turn and everyone else attacks the weakest enemy still standing. a fight
+// ends when one side has nobody left, or after max_rounds as a draw
+//
+// damage is attack^2 / (attack + defense), so it never drops to 0 against a
+// high defense, times a roll in [0.85, 1.15], doubled on a critical hit
+//
+// all randomness comes from the rng passed in, a seeded StdRng (run_seeded)
+// makes a fight and its log come out the same every time
+
+use crate::lifecycle::{self, EventBus, EventKind, Snapshot};
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use std::fmt;
+
+pub const CRITICAL_CHANCE: f64 = 0.05;
+pub const DEFAULT_HP: i32 = 50;
+pub const DEFAULT_SPEED: i32 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Effect
+{
+    // damage at the start of each turn
+    Poison(i32),
+    // the turn is lost
+    Stun,
+    // attack halved
+    Weaken,
+    // defense raised by the amount
+    Shield(i32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Status
+{
+    pub effect: Effect,
+    // turns of the affected creature it lasts
+    pub turns: u32,
+}
+
+pub struct Creature
+{
+    pub name: String,
+    pub attack: i32,
+    pub defense: i32,
+    pub speed: i32,
+    pub max_hp: i32,
+    pub hp: i32,
+    pub statuses: Vec<Status>,
+    // what a hit does to the target besides damage, and how often
+    pub on_hit: Option<(Status, f64)>,
+    // where the lifecycle events go, see lifecycle.rs
+    tracker: Option<(EventBus, u64)>,
+}
+
+impl Creature
+{
+    pub fn new(name: &str, attack: i32, defense: i32) -> Creature
+    {
+        println!("{} has been created", name);
+        Creature {
+            name: name.to_string(),
+            attack,
+            defense,
+            speed: DEFAULT_SPEED,
+            max_hp: DEFAULT_HP,
+            hp: DEFAULT_HP,
+            statuses: vec![],
+            on_hit: None,
+            tracker: None,
+        }
+    }
+
+    pub fn with_hp(mut self, hp: i32) -> Creature
+    {
+        self.max_hp = hp;
+        self.hp = hp;
+        self
+    }
+
+    pub fn with_speed(mut self, speed: i32) -> Creature
+    {
+        self.speed = speed;
+        self
+    }
+
+    // panics unless chance is a probability, 0 to 1
+    pub fn with_on_hit(mut self, status: Status, chance: f64) -> Creature
+    {
+        assert!((0.0..=1.0).contains(&chance), "on hit chance {} is not between 0 and 1", chance);
+        self.on_hit = Some((status, chance));
+        self
+    }
+
+    pub fn is_alive(&self) -> bool { self.hp > 0 }
+
+    pub fn has(&self, effect: fn(&Effect) -> bool) -> bool { self.statuses.iter().any(|s| effect(&s.effect)) }
+
+    // attack and defense with the statuses applied
+    pub fn effective_attack(&self) -> i32
+    {
+        if self.has(|e| *e == Effect::Weaken) { self.attack / 2 } else { self.attack }
+    }
+
+    pub fn effective_defense(&self) -> i32
+    {
+        let shield: i32 = self.statuses.iter().map(|s| match s.effect { Effect::Shield(n) => n, _ => 0 }).sum();
+        self.defense + shield
+    }
+
+    // hp never goes below 0
+    pub fn take_damage(&mut self, damage: i32)
+    {
+        self.hp = (self.hp - damage).max(0);
+        self.changed();
+    }
+
+    // a new status of the same kind replaces the old one. one lasting 0
+    // turns is ignored
+    pub fn apply(&mut self, status: Status)
+    {
+        if status.turns == 0 { return; }
+        let kind = std::mem::discriminant(&status.effect);
+        self.statuses.retain(|s| std::mem::discriminant(&s.effect) != kind);
+        self.statuses.push(status);
+        self.changed();
+    }
+
+    // a created event now, modified and destroyed ones from here on. the id
+    // is the one the events carry
+    pub fn track(&mut self, bus: &EventBus) -> u64
+    {
+        let id = bus.new_id();
+        self.tracker = Some((bus.clone(), id));
+        self.report(EventKind::Created);
+        id
+    }
+
+    // the modified event, for changes made to the fields directly
+    pub fn changed(&self) { self.report(EventKind::Modified); }
+
+    fn report(&self, kind: EventKind)
+    {
+        if let Some((bus, id)) = &self.tracker {
+            bus.publish(lifecycle::Event { id: *id, kind, state: Snapshot::of(self) });
+        }
+    }
+}
+
+impl Drop for Creature
+{
+    fn drop(&mut self)
+    {
+        self.report(EventKind::Destroyed);
+        println!("{} has been destroyed", self.name);
+    }
+}
+
+/////////////////////////////////////
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hit
+{
+    pub damage: i32,
+    pub critical: bool,
+}
+
+pub fn damage<R: Rng + ?Sized>(attacker: &Creature, defender: &Creature, rng: &mut R) -> Hit
+{
+    let (a, d) = (attacker.effective_attack().max(0) as f64, defender.effective_defense().max(0) as f64);
+    if a == 0.0 { return Hit { damage: 0, critical: false }; }
+
+    let critical = rng.gen_bool(CRITICAL_CHANCE);
+    let base = a * a / (a + d) * rng.gen_range(0.85..=1.15);
+    let damage = (if critical { 2.0 * base } else { base }).round().max(1.0) as i32;
+    Hit { damage, critical }
+}
+
+// one line of the battle log, creatures go by name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event
+{
+    Round(u32),
+    Attack { attacker: String, target: String, damage: i32, critical: bool },
+    StatusApplied { target: String, status: Status },
+    StatusDamage { target: String, damage: i32 },
+    StatusExpired { target: String, effect: Effect },
+    Stunned(String),
+    Defeated(String),
+    Victory(usize),
+    Draw,
+}
+
+impl fmt::Display for Event
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self {
+            Event::Round(n) => write!(f, "round {}", n),
+            Event::Attack { attacker, target, damage, critical } => {
+                write!(f, "{} hits {} for {}{}", attacker, target, damage, if *critical { ", critical!" } else { "" })
+            }
+            Event::StatusApplied { target, status } => write!(f, "{} gets {:?} for {} turns", target, status.effect, status.turns),
+            Event::StatusDamage { target, damage } => write!(f, "{} takes {} from poison", target, damage),
+            Event::StatusExpired { target, effect } => write!(f, "{:?} wears off {}", effect, target),
+            Event::Stunned(name) => write!(f, "{} is stunned", name),
+            Event::Defeated(name) => write!(f, "{} is defeated", name),
+            Event::Victory(team) => write!(f, "team {} wins", team),
+            Event::Draw => write!(f, "draw"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome
+{
+    // the index of the winning team
+    Victory(usize),
+    Draw,
+}
+
+// two (or more) teams and what happened to them so far
+pub struct Battle
+{
+    pub teams: Vec<Vec<Creature>>,
+    pub log: Vec<Event>,
+    pub round: u32,
+}
+
+impl Battle
+{
+    pub fn new(teams: Vec<Vec<Creature>>) -> Battle { Battle { teams, log: vec![], round: 0 } }
+
+    fn standing(&self) -> Vec<usize>
+    {
+        (0..self.teams.len()).filter(|&t| self.teams[t].iter().any(Creature::is_alive)).collect()
+    }
+
+    // the outcome once the fight is over
+    pub fn outcome(&self) -> Option<Outcome>
+    {
+        match self.standing().as_slice() {
+            [] => Some(Outcome::Draw),
+            [team] => Some(Outcome::Victory(*team)),
+            _ => None,
+        }
+    }
+
+    // (team, index) of everyone alive, fastest first
+    fn initiative<R: Rng + ?Sized>(&self, rng: &mut R) -> Vec<(usize, usize)>
+    {
+        let mut order: Vec<(i32, usize, usize)> = vec![];
+        for (t, team) in self.teams.iter().enumerate() {
+            for (i, c) in team.iter().enumerate() {
+                if c.is_alive() { order.push((c.speed + rng.gen_range(1..=6), t, i)); }
+            }
+        }
+        // ties go to the earlier team and position, which keeps seeded fights stable
+        order.sort_by(|a, b| b.0.cmp(&a.0).then((a.1, a.2).cmp(&(b.1, b.2))));
+        order.into_iter().map(|(_, t, i)| (t, i)).collect()
+    }
+
+    // the enemy with the least hp left
+    fn target(&self, team: usize) -> Option<(usize, usize)>
+    {
+        self.teams
+            .iter()
+            .enumerate()
+            .filter(|&(t, _)| t != team)
+            .flat_map(|(t, members)| members.iter().enumerate().map(move |(i, c)| (t, i, c)))
+            .filter(|(_, _, c)| c.is_alive())
+            .min_by_key(|&(t, i, c)| (c.hp, t, i))
+            .map(|(t, i, _)| (t, i))
+    }
+
+    fn turn<R: Rng + ?Sized>(&mut self, (t, i): (usize, usize), rng: &mut R)
+    {
+        let me = &mut self.teams[t][i];
+        if !me.is_alive() { return; }
+
+        let poison: i32 = me.statuses.iter().map(|s| match s.effect { Effect::Poison(n) => n, _ => 0 }).sum();
+        if poison > 0 {
+            me.take_damage(poison);
+            self.log.push(Event::StatusDamage { target: me.name.clone(), damage: poison });
+            if !me.is_alive() {
+                self.log.push(Event::Defeated(me.name.clone()));
+                return;
+            }
+        }
+
+        let stunned = me.has(|e| *e == Effect::Stun);
+        if stunned {
+            self.log.push(Event::Stunned(me.name.clone()));
+        } else if let Some((tt, ti)) = self.target(t) {
+            let hit = damage(&self.teams[t][i], &self.teams[tt][ti], rng);
+            let on_hit = self.teams[t][i].on_hit;
+            let attacker = self.teams[t][i].name.clone();
+            let target = &mut self.teams[tt][ti];
+            target.take_damage(hit.damage);
+            self.log.push(Event::Attack { attacker, target: target.name.clone(), damage: hit.damage, critical: hit.critical });
+            if !target.is_alive() {
+                self.log.push(Event::Defeated(target.name.clone()));
+            } else if let Some((status, chance)) = on_hit {
+                if rng.gen_bool(chance) {
+                    target.apply(status);
+                    self.log.push(Event::StatusApplied { target: target.name.clone(), status });
+                }
+            }
+        }
+
+        // the statuses count down on their owner's turns
+        let me = &mut self.teams[t][i];
+        for s in &mut me.statuses { s.turns = s.turns.saturating_sub(1); }
+        for s in me.statuses.iter().filter(|s| s.turns == 0) {
+            self.log.push(Event::StatusExpired { target: me.name.clone(), effect: s.effect });
+        }
+        let before = me.statuses.len();
+        me.statuses.retain(|s| s.turns > 0);
+        if me.statuses.len() != before { me.changed(); }
+    }
+
+    // one round, false when the fight was already over
+    pub fn step<R: Rng + ?Sized>(&mut self, rng: &mut R) -> bool
+    {
+        if self.outcome().is_some() { return false; }
+        self.round += 1;
+        self.log.push(Event::Round(self.round));
+        for fighter in self.initiative(rng) {
+            self.turn(fighter, rng);
+            if self.outcome().is_some() { break; }
+        }
+        true
+    }
+
+    pub fn run<R: Rng + ?Sized>(&mut self, rng: &mut R, max_rounds: u32) -> Outcome
+    {
+        while self.round < max_rounds && self.step(rng) {}
+        let outcome = self.outcome().unwrap_or(Outcome::Draw);
+        self.log.push(match outcome {
+            Outcome::Victory(team) => Event::Victory(team),
+            Outcome::Draw => Event::Draw,
+        });
+        outcome
+    }
+
+    // the same seed, the same fight
+    pub fn run_seeded(&mut self, seed: u64, max_rounds: u32) -> Outcome
+    {
+        self.run(&mut StdRng::seed_from_u64(seed), max_rounds)
+    }
+}
+
+/////////////////////////////////////
+
+pub fn main()
+{
+    let heroes = || {
+        vec![
+            Creature::new("Knight", 14, 10).with_hp(100).with_speed(8),
+            Creature::new("Archer", 17, 4).with_speed(14),
+            Creature::new("Cleric", 6, 6).with_hp(40).with_on_hit(Status { effect: Effect::Stun, turns: 1 }, 0.3),
+        ]
+    };
+    let monsters = || {
+        vec![
+            Creature::new("Goblin", 10, 5),
+            Creature::new("Spider", 8, 3).with_on_hit(Status { effect: Effect::Poison(3), turns: 3 }, 0.5),
+            Creature::new("Troll", 18, 8).with_hp(90).with_speed(5),
+        ]
+    };
+
+    let mut battle = Battle::new(vec![heroes(), monsters()]);
+    let outcome = battle.run_seeded(48, 100);
+    for event in &battle.log { println!("{}", event); }
+    assert!(battle.outcome().is_some() && outcome != Outcome::Draw);
+
+    // reproducible: the same seed gives the same log
+    let mut again = Battle::new(vec![heroes(), monsters()]);
+    assert_eq!(again.run_seeded(48, 100), outcome);
+    assert_eq!(again.log, battle.log);
+
+    // the loser's side is all down, the winner has someone standing
+    let Outcome::Victory(winner) = outcome else { unreachable!() };
+    assert!(battle.teams[1 - winner].iter().all(|c| c.hp == 0));
+    assert!(battle.teams[winner].iter().any(|c| c.hp > 0));
+    let defeated = battle.log.iter().filter(|e| matches!(e, Event::Defeated(_))).count();
+    assert_eq!(defeated, battle.teams.iter().flatten().filter(|c| !c.is_alive()).count());
+
+    // a long series: each side wins sometimes, nothing runs away
+    let mut wins = [0; 2];
+    for seed in 0..200 {
+        let mut b = Battle::new(vec![heroes(), monsters()]);
+        if let Outcome::Victory(t) = b.run_seeded(seed, 100) { wins[t] += 1; }
+        assert!(b.round <= 100);
+    }
+    println!("heroes {} monsters {}", wins[0], wins[1]);
+    assert!(wins[0] > 0 && wins[1] > 0);
+
+    // the damage formula: defense helps but never blocks everything
+    let mut rng = StdRng::seed_from_u64(48);
+    let (troll, wall) = (Creature::new("Troll", 18, 8), Creature::new("Wall", 0, 1000));
+    let hits: Vec<Hit> = (0..1000).map(|_| damage(&troll, &wall, &mut rng)).collect();
+    assert!(hits.iter().all(|h| h.damage >= 1));
+    assert_eq!(damage(&wall, &troll, &mut rng).damage, 0);
+    let mean = (0..10_000).map(|_| damage(&troll, &troll, &mut rng).damage as f64).sum::<f64>() / 10_000.0;
+    // 18^2 / 26 = 12.5, plus 5% doubled
+    assert!((mean - 12.5 * 1.05).abs() < 0.3, "{}", mean);
+
+    // statuses: poison ticks and wears off, a stun costs a turn, two stuns don't stack
+    let mut solo = Battle::new(vec![vec![Creature::new("Slime", 0, 0).with_hp(10)], vec![Creature::new("Rock", 0, 100)]]);
+    solo.teams[0][0].apply(Status { effect: Effect::Poison(2), turns: 2 });
+    solo.teams[0][0].apply(Status { effect: Effect::Stun, turns: 1 });
+    solo.teams[0][0].apply(Status { effect: Effect::Stun, turns: 1 });
+    assert_eq!(solo.teams[0][0].statuses.len(), 2);
+    solo.run_seeded(48, 5);
+    assert_eq!(solo.teams[0][0].hp, 6);
+    assert!(solo.log.contains(&Event::Stunned("Slime".to_string())));
+    assert_eq!(solo.log.last(), Some(&Event::Draw));
+
+    // weaken halves the attack and a shield adds to the defense, the damage
+    // both ways shows it
+    let mut brute = Creature::new("Brute", 20, 3);
+    brute.apply(Status { effect: Effect::Weaken, turns: 2 });
+    brute.apply(Status { effect: Effect::Shield(5), turns: 3 });
+    assert_eq!((brute.effective_attack(), brute.effective_defense()), (10, 8));
+    let plain = Creature::new("Plain", 20, 3);
+    let mut mean = |a: &Creature, d: &Creature| (0..10_000).map(|_| damage(a, d, &mut rng).damage as f64).sum::<f64>() / 10_000.0;
+    // 10^2 / 13 instead of 20^2 / 23, and 20^2 / 28
+    let (dealt, taken) = (mean(&brute, &plain), mean(&plain, &brute));
+    assert!((dealt - 100.0 / 13.0 * 1.05).abs() < 0.3, "{}", dealt);
+    assert!((taken - 400.0 / 28.0 * 1.05).abs() < 0.3, "{}", taken);
+
+    // they wear off after their turns, a status of 0 turns does nothing
+    let mut worn = Battle::new(vec![vec![brute], vec![Creature::new("Rock", 0, 100)]]);
+    worn.run_seeded(48, 3);
+    let brute = &mut worn.teams[0][0];
+    assert!(brute.statuses.is_empty() && brute.effective_attack() == 20 && brute.effective_defense() == 3);
+    assert!(worn.log.contains(&Event::StatusExpired { target: "Brute".to_string(), effect: Effect::Weaken }));
+    assert!(worn.log.contains(&Event::StatusExpired { target: "Brute".to_string(), effect: Effect::Shield(5) }));
+    brute.apply(Status { effect: Effect::Stun, turns: 0 });
+    assert!(brute.statuses.is_empty());
+    // one pushed by hand expires on the next turn without wrapping around
+    brute.statuses.push(Status { effect: Effect::Weaken, turns: 0 });
+    worn.step(&mut rng);
+    assert!(worn.teams[0][0].statuses.is_empty());
+}
@@ -0,0 +1,139 @@
+// approximate comparison of floating point complex numbers
+//
+// == on Complex<f64> is exact: both parts must be bit for bit the same number
+// (except 0.0 == -0.0) and NAN never equals anything. after a few operations
+// rounding makes that too strict, so these compare with a tolerance instead:
+//   - absolute: the distance between the two numbers is at most eps,
+//     the right choice around zero where relative errors blow up
+//   - relative: the distance is at most a fraction of the larger modulus
+//   - ulps: each part is at most n representable floats away from the other,
+//     the relative check expressed in the float's own resolution
+
+use crate::Complex;
+
+macro_rules! complex_approx_impl {
+    ($t:ident, $int:ident, $ordered:ident) => {
+        // maps the float onto an integer line where neighbouring floats are
+        // neighbouring integers, -0.0 and 0.0 both land on 0
+        fn $ordered(x: $t) -> i128
+        {
+            let bits = x.to_bits() as $int;
+            if bits < 0 { ($int::MIN as i128) - (bits as i128) } else { bits as i128 }
+        }
+
+        impl Complex<$t>
+        {
+            pub fn is_nan(&self) -> bool { self.re.is_nan() || self.im.is_nan() }
+
+            pub fn is_finite(&self) -> bool { self.re.is_finite() && self.im.is_finite() }
+
+            // either part, the other may be anything but NAN
+            pub fn is_infinite(&self) -> bool { !self.is_nan() && (self.re.is_infinite() || self.im.is_infinite()) }
+
+            // like ==, except that a NAN part matches a NAN part. handy to check
+            // that two computations fail in the same way
+            pub fn nan_eq(&self, other: &Complex<$t>) -> bool
+            {
+                let part = |a: $t, b: $t| a == b || (a.is_nan() && b.is_nan());
+                part(self.re, other.re) && part(self.im, other.im)
+            }
+
+            fn distance(&self, other: &Complex<$t>) -> $t
+            {
+                (self.re - other.re).hypot(self.im - other.im)
+            }
+
+            pub fn approx_eq_abs(&self, other: &Complex<$t>, eps: $t) -> bool
+            {
+                self == other || self.distance(other) <= eps
+            }
+
+            // an infinity is only close to itself, relative to it anything would be
+            pub fn approx_eq_rel(&self, other: &Complex<$t>, max_relative: $t) -> bool
+            {
+                if self == other { return true; }
+                if self.is_infinite() || other.is_infinite() { return false; }
+                let largest = self.re.hypot(self.im).max(other.re.hypot(other.im));
+                self.distance(other) <= largest * max_relative
+            }
+
+            // how many floats apart the parts are, the larger of the two. None if
+            // there is a NAN. an infinity sits right after MAX on the float line,
+            // but it is no approximation of anything, so it is u128::MAX ulps
+            // from everything except itself
+            pub fn ulps_distance(&self, other: &Complex<$t>) -> Option<u128>
+            {
+                if self.is_nan() || other.is_nan() { return None; }
+                let part = |a: $t, b: $t| {
+                    if a != b && (a.is_infinite() || b.is_infinite()) { return u128::MAX; }
+                    ($ordered(a) - $ordered(b)).unsigned_abs()
+                };
+                Some(part(self.re, other.re).max(part(self.im, other.im)))
+            }
+
+            pub fn approx_eq_ulps(&self, other: &Complex<$t>, max_ulps: u32) -> bool
+            {
+                self.ulps_distance(other).map_or(false, |d| d <= max_ulps as u128)
+            }
+
+            // the usual combination: absolute near zero, ulps everywhere else
+            pub fn approx_eq(&self, other: &Complex<$t>, eps: $t, max_ulps: u32) -> bool
+            {
+                self.approx_eq_abs(other, eps) || self.approx_eq_ulps(other, max_ulps)
+            }
+        }
+    };
+}
+
+complex_approx_impl!(f32, i32, ordered_f32);
+complex_approx_impl!(f64, i64, ordered_f64);
+
+/////////////////////////////////////
+
+pub fn main()
+{
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    let a = Complex::new(0.1 + 0.2, 1.0_f64);
+    let b = Complex::new(0.3, 1.0);
+    println!("{:?} == {:?} -> {}", a, b, a == b);
+    println!("1 ulp apart -> {}", a.approx_eq_ulps(&b, 1));
+    println!("within 1e-12 -> {}", a.approx_eq_abs(&b, 1e-12));
+    assert!(a != b && a.approx_eq_ulps(&b, 1) && a.approx_eq_abs(&b, 1e-12));
+    assert_eq!(a.ulps_distance(&b), Some(1));
+
+    // infinities match only themselves, not the largest finite float
+    let (max, inf) = (Complex::new(f64::MAX, 0.0), Complex::new(f64::INFINITY, 0.0));
+    assert_eq!(inf.ulps_distance(&inf), Some(0));
+    assert_eq!(max.ulps_distance(&inf), Some(u128::MAX));
+    assert!(!max.approx_eq_ulps(&inf, u32::MAX) && !inf.approx_eq(&max, 1.0, 4));
+    assert!(!Complex::new(1.0_f64, 0.0).approx_eq_rel(&inf, 1e-12) && inf.approx_eq_rel(&inf, 1e-12));
+    assert_eq!(Complex::new(-0.0_f64, 1.0).ulps_distance(&Complex::new(0.0, 1.0)), Some(0));
+    assert_eq!(Complex::new(f64::NAN, 1.0_f64).ulps_distance(&Complex::new(f64::NAN, 1.0)), None);
+
+    let nan = Complex::new(f64::NAN, 0.0);
+    let copy = nan;
+    println!("nan == nan -> {}, nan != nan -> {}, nan_eq -> {}", nan == copy, nan != copy, nan.nan_eq(&copy));
+
+    // property check: for random pairs, including nans, infinities and signed
+    // zeros, != is always the opposite of == and == is symmetric, and nothing
+    // but an equal number is relatively close to an infinity
+    let specials = [f64::NAN, f64::INFINITY, f64::NEG_INFINITY, 0.0, -0.0, 1.0];
+    let mut rng = StdRng::seed_from_u64(7);
+    let part = |rng: &mut StdRng| {
+        if rng.gen_bool(0.3) { specials[rng.gen_range(0..specials.len())] } else { rng.gen_range(-2.0..2.0) }
+    };
+    for _ in 0..100_000 {
+        let x = Complex::new(part(&mut rng), part(&mut rng));
+        let y = if rng.gen_bool(0.2) { x } else { Complex::new(part(&mut rng), part(&mut rng)) };
+        assert_eq!(x != y, !(x == y), "{:?} {:?}", x, y);
+        assert_eq!(x.eq(&y), y.eq(&x), "{:?} {:?}", x, y);
+        let copy = x;
+        assert_eq!(x == copy, !x.is_nan(), "{:?}", x);
+        if x != y && (x.is_infinite() || y.is_infinite()) {
+            assert!(!x.approx_eq_rel(&y, 1e-12) && !y.approx_eq_rel(&x, 0.5), "{:?} {:?}", x, y);
+        }
+    }
+    println!("== and != agree on 100000 random pairs");
+}
@@ -0,0 +1,306 @@
+// text in and out of Complex<T>
+//
+// formatting: {} prints "3+4i", the format flags go to both parts, so {:+.3}
+// gives "+3.000+4.000i". {:#} prints the tuple form "(3,4)". {:e} uses
+// scientific notation for the parts. width and fill pad the whole number, and
+// so does the 0 flag: {:010.1} gives "003.0+4.0i", the zeros go after the
+// sign of the real part (and the parenthesis of the tuple form)
+//
+// parsing accepts
+//   3+4i  3 + 4i  -2.5i  i  -j  1e3-2j  7  (1,2)  ( 1 , -2 )
+// with i or j as the imaginary unit. errors say what went wrong and at which
+// byte of the input
+
+use crate::Complex;
+
+use std::fmt;
+use std::str::FromStr;
+
+// the flags of f, minus width and fill which apply to the whole number
+fn write_part<T, F>(out: &mut String, f: &fmt::Formatter, value: &T, force_sign: bool, show: F)
+where
+    F: Fn(&T, bool, Option<usize>) -> String,
+{
+    out.push_str(&show(value, force_sign || f.sign_plus(), f.precision()));
+}
+
+fn pad(f: &mut fmt::Formatter, s: &str) -> fmt::Result
+{
+    let len = s.chars().count();
+    let width = match f.width() {
+        Some(width) if width > len => width,
+        _ => return f.write_str(s),
+    };
+
+    let missing = width - len;
+    if f.sign_aware_zero_pad() {
+        let prefix = s.len() - s.trim_start_matches(['(', '+', '-']).len();
+        return write!(f, "{}{}{}", &s[..prefix], "0".repeat(missing), &s[prefix..]);
+    }
+    let fill = f.fill().to_string();
+    let (before, after) = match f.align() {
+        Some(fmt::Alignment::Left) => (0, missing),
+        Some(fmt::Alignment::Center) => (missing / 2, missing - missing / 2),
+        // numbers line up to the right by default
+        _ => (missing, 0),
+    };
+    write!(f, "{}{}{}", fill.repeat(before), s, fill.repeat(after))
+}
+
+fn format_complex<T, F>(c: &Complex<T>, f: &mut fmt::Formatter, show: F) -> fmt::Result
+where
+    F: Fn(&T, bool, Option<usize>) -> String,
+{
+    let mut s = String::new();
+    if f.alternate() {
+        s.push('(');
+        write_part(&mut s, f, &c.re, false, &show);
+        s.push(',');
+        write_part(&mut s, f, &c.im, false, &show);
+        s.push(')');
+    } else {
+        write_part(&mut s, f, &c.re, false, &show);
+        write_part(&mut s, f, &c.im, true, &show);
+        s.push('i');
+    }
+    pad(f, &s)
+}
+
+impl<T: fmt::Display> fmt::Display for Complex<T>
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        format_complex(self, f, |v, plus, precision| match (plus, precision) {
+            (true, Some(p)) => format!("{:+.*}", p, v),
+            (true, None) => format!("{:+}", v),
+            (false, Some(p)) => format!("{:.*}", p, v),
+            (false, None) => format!("{}", v),
+        })
+    }
+}
+
+impl<T: fmt::LowerExp> fmt::LowerExp for Complex<T>
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        format_complex(self, f, |v, plus, precision| match (plus, precision) {
+            (true, Some(p)) => format!("{:+.*e}", p, v),
+            (true, None) => format!("{:+e}", v),
+            (false, Some(p)) => format!("{:.*e}", p, v),
+            (false, None) => format!("{:e}", v),
+        })
+    }
+}
+
+/////////////////////////////////////
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseErrorKind
+{
+    Empty,
+    InvalidReal,
+    InvalidImaginary,
+    // "(1 2)" or "(1,2,3)"
+    ExpectedComma,
+    // "(1,2" or "1,2)"
+    UnbalancedParenthesis,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseComplexError
+{
+    pub kind: ParseErrorKind,
+    // byte offset into the input where the bad part starts
+    pub position: usize,
+}
+
+impl fmt::Display for ParseComplexError
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        let what = match self.kind {
+            ParseErrorKind::Empty => "empty string",
+            ParseErrorKind::InvalidReal => "invalid real part",
+            ParseErrorKind::InvalidImaginary => "invalid imaginary part",
+            ParseErrorKind::ExpectedComma => "expected exactly one comma",
+            ParseErrorKind::UnbalancedParenthesis => "unbalanced parenthesis",
+        };
+        write!(f, "cannot parse complex number: {} at position {}", what, self.position)
+    }
+}
+
+impl std::error::Error for ParseComplexError {}
+
+// parses s[start..end] trimmed, errors point at where the trimmed text begins
+fn parse_part<T: FromStr>(s: &str, start: usize, end: usize, kind: ParseErrorKind) -> Result<T, ParseComplexError>
+{
+    let raw = &s[start..end];
+    let trimmed = raw.trim();
+    let position = start + (raw.len() - raw.trim_start().len());
+    trimmed.parse().map_err(|_| ParseComplexError { kind, position })
+}
+
+// T has no zero or one we could name, but every number type parses them
+fn constant<T: FromStr>(text: &str, kind: ParseErrorKind, position: usize) -> Result<T, ParseComplexError>
+{
+    text.parse().map_err(|_| ParseComplexError { kind, position })
+}
+
+// the sign that starts the imaginary part: the last + or - that isn't the
+// sign of the whole number or of an exponent (the e in 1e-3)
+fn split_sign(s: &str) -> Option<usize>
+{
+    let bytes = s.as_bytes();
+    let first = bytes.iter().position(|b| !b.is_ascii_whitespace())?;
+    (first + 1..bytes.len()).rev().find(|&i| {
+        if bytes[i] != b'+' && bytes[i] != b'-' { return false; }
+        let exponent = matches!(bytes[i - 1], b'e' | b'E')
+            && i >= 2
+            && (bytes[i - 2].is_ascii_digit() || bytes[i - 2] == b'.');
+        !exponent
+    })
+}
+
+impl<T: FromStr> FromStr for Complex<T>
+{
+    type Err = ParseComplexError;
+
+    fn from_str(s: &str) -> Result<Complex<T>, ParseComplexError>
+    {
+        use ParseErrorKind::*;
+
+        let start = s.len() - s.trim_start().len();
+        let end = s.trim_end().len();
+        if start >= end { return Err(ParseComplexError { kind: Empty, position: 0 }); }
+        let text = &s[..end];
+
+        // tuple form (re, im)
+        if text.as_bytes()[start] == b'(' || text.as_bytes()[end - 1] == b')' {
+            if text.as_bytes()[start] != b'(' {
+                return Err(ParseComplexError { kind: UnbalancedParenthesis, position: end - 1 });
+            }
+            if text.as_bytes()[end - 1] != b')' || end - start < 2 {
+                return Err(ParseComplexError { kind: UnbalancedParenthesis, position: start });
+            }
+            let inner = start + 1..end - 1;
+            let commas: Vec<usize> = text[inner.clone()].match_indices(',').map(|(i, _)| inner.start + i).collect();
+            if commas.len() != 1 {
+                let position = commas.get(1).copied().unwrap_or(inner.start);
+                return Err(ParseComplexError { kind: ExpectedComma, position });
+            }
+            let re = parse_part(text, inner.start, commas[0], InvalidReal)?;
+            let im = parse_part(text, commas[0] + 1, inner.end, InvalidImaginary)?;
+            return Ok(Complex::new(re, im));
+        }
+
+        // no unit at the end: a plain real number
+        let last = text.as_bytes()[end - 1];
+        if last != b'i' && last != b'j' {
+            let re = parse_part(text, start, end, InvalidReal)?;
+            return Ok(Complex::new(re, constant("0", InvalidImaginary, end)?));
+        }
+
+        // the imaginary coefficient can be left out: i, +i, -i. there may be
+        // spaces between its sign and the digits: 75 - 3.3j
+        let coefficient = |from: usize| -> Result<T, ParseComplexError> {
+            let part = &text[from..end - 1];
+            let signed = part.trim_start();
+            let sign_at = from + part.len() - signed.len();
+            let (negative, unsigned) = match signed.as_bytes().first() {
+                Some(b'+') => (false, &signed[1..]),
+                Some(b'-') => (true, &signed[1..]),
+                _ => (false, signed),
+            };
+            let digits = unsigned.trim();
+            if digits.is_empty() {
+                return constant(if negative { "-1" } else { "1" }, InvalidImaginary, sign_at);
+            }
+            let digits_at = end - 1 - unsigned.trim_start().len();
+            let number = if negative { format!("-{}", digits) } else { digits.to_string() };
+            number.parse().map_err(|_| ParseComplexError { kind: InvalidImaginary, position: digits_at })
+        };
+
+        match split_sign(&text[..end - 1]) {
+            Some(sign) => {
+                let re = parse_part(text, start, sign, InvalidReal)?;
+                Ok(Complex::new(re, coefficient(sign)?))
+            }
+            None => Ok(Complex::new(constant("0", InvalidReal, start)?, coefficient(start)?)),
+        }
+    }
+}
+
+/////////////////////////////////////
+
+pub fn main()
+{
+    let z = Complex::new(3.0_f64, -4.25);
+    println!("{}", z);
+    println!("{:+.3}", z);
+    println!("[{:>16.1}]", z);
+    println!("{:#}", z);
+    println!("{:e}", Complex::new(1500.0_f64, 0.002));
+
+    // impedances as they appear in the config files
+    for text in ["50+10j", "75 - 3.3j", "-2.5i", "1e3-2j", "(1,2)", "j", "12"] {
+        let z: Result<Complex<f64>, _> = text.parse();
+        println!("{:>10} -> {:?}", text, z);
+    }
+
+    for text in ["3+xi", "(1 2)", "4+5", ""] {
+        match text.parse::<Complex<f64>>() {
+            Ok(z) => println!("{:>10} -> {}", text, z),
+            Err(e) => println!("{:>10} -> {}", text, e),
+        }
+    }
+
+    let round_trip: Complex<i32> = Complex::new(-7, 12).to_string().parse().unwrap();
+    println!("round trip: {}", round_trip);
+    assert_eq!(round_trip, Complex::new(-7, 12));
+
+    // what the flags print
+    assert_eq!(z.to_string(), "3-4.25i");
+    assert_eq!(format!("{:+.3}", z), "+3.000-4.250i");
+    assert_eq!(format!("[{:>16.1}]", z), "[        3.0-4.2i]");
+    assert_eq!(format!("[{:*<12}]", z), "[3-4.25i*****]");
+    assert_eq!(format!("{:#}", z), "(3,-4.25)");
+    assert_eq!(format!("{:e}", Complex::new(1500.0_f64, 0.002)), "1.5e3+2e-3i");
+    assert_eq!(format!("{:010.1}", Complex::new(3.0_f64, 4.0)), "003.0+4.0i");
+    assert_eq!(format!("{:011.1}", Complex::new(-3.0_f64, 4.0)), "-003.0+4.0i");
+    assert_eq!(format!("{:#08}", Complex::new(1, -2)), "(001,-2)");
+    assert_eq!(format!("{:04}", Complex::new(10, 20)), "10+20i");
+
+    // everything printed parses back to the same number
+    for z in [Complex::new(3.0_f64, -4.25), Complex::new(-0.5, 1e-7), Complex::new(1e300, -0.0)] {
+        assert_eq!(z.to_string().parse::<Complex<f64>>(), Ok(z));
+        assert_eq!(format!("{:#}", z).parse::<Complex<f64>>(), Ok(z));
+        assert_eq!(format!("{:e}", z).parse::<Complex<f64>>(), Ok(z));
+    }
+    let parsed: Vec<Complex<f64>> = ["50+10j", "75 - 3.3j", "-2.5i", "1e3-2j", "( 1 , -2 )", "-j", "12"]
+        .iter()
+        .map(|text| text.parse().unwrap())
+        .collect();
+    assert_eq!(parsed, [
+        Complex::new(50.0, 10.0),
+        Complex::new(75.0, -3.3),
+        Complex::new(0.0, -2.5),
+        Complex::new(1000.0, -2.0),
+        Complex::new(1.0, -2.0),
+        Complex::new(0.0, -1.0),
+        Complex::new(12.0, 0.0),
+    ]);
+
+    // and the errors point at the bad byte
+    let error = |text: &str| text.parse::<Complex<f64>>().unwrap_err();
+    use ParseErrorKind::*;
+    assert_eq!(error("3+xi"), ParseComplexError { kind: InvalidImaginary, position: 2 });
+    assert_eq!(error("(1 2)"), ParseComplexError { kind: ExpectedComma, position: 1 });
+    assert_eq!(error("(1,2,3)"), ParseComplexError { kind: ExpectedComma, position: 4 });
+    assert_eq!(error("  (1,2"), ParseComplexError { kind: UnbalancedParenthesis, position: 2 });
+    assert_eq!(error("1,2)"), ParseComplexError { kind: UnbalancedParenthesis, position: 3 });
+    assert_eq!(error("(x, 2)"), ParseComplexError { kind: InvalidReal, position: 1 });
+    assert_eq!(error("(1,  y)"), ParseComplexError { kind: InvalidImaginary, position: 5 });
+    assert_eq!(error("4+5"), ParseComplexError { kind: InvalidReal, position: 0 });
+    assert_eq!(error("   "), ParseComplexError { kind: Empty, position: 0 });
+    assert_eq!(error("3+xi").to_string(), "cannot parse complex number: invalid imaginary part at position 2");
+}
@@ -0,0 +1,245 @@
+// polar form and transcendental functions for Complex<f32> and Complex<f64>
+//
+// every multivalued function returns its principal value. the branch cuts
+// are the usual ones (the same as C99 and most libraries):
+//   ln, sqrt, powc, powf   along the negative real axis, arg in (-pi, pi]
+//   asin, acos, atanh      along the real axis outside of [-1, 1]
+//   acosh                  along the real axis left of 1
+//   atan, asinh            along the imaginary axis outside of [-i, i]
+// a number exactly on a cut takes the value from the side its signed zero
+// points to, like the real functions do
+
+use crate::Complex;
+
+macro_rules! complex_math_impl {
+    ($t:ident) => {
+        impl Complex<$t>
+        {
+            pub const I: Complex<$t> = Complex { re: 0.0, im: 1.0 };
+
+            pub fn from_polar(r: $t, theta: $t) -> Complex<$t>
+            {
+                Complex::new(r * theta.cos(), r * theta.sin())
+            }
+
+            // (modulus, argument)
+            pub fn to_polar(self) -> ($t, $t) { (self.abs(), self.arg()) }
+
+            // modulus, hypot avoids overflowing on the squares
+            pub fn abs(self) -> $t { self.re.hypot(self.im) }
+
+            pub fn norm_sqr(self) -> $t { self.re * self.re + self.im * self.im }
+
+            pub fn arg(self) -> $t { self.im.atan2(self.re) }
+
+            pub fn conj(self) -> Complex<$t> { Complex::new(self.re, -self.im) }
+
+            // 1 / z without going through the general division
+            pub fn inv(self) -> Complex<$t>
+            {
+                let n = self.norm_sqr();
+                Complex::new(self.re / n, -self.im / n)
+            }
+
+            /////////////////////////////////////
+
+            // e^(a + bi) = e^a (cos b + i sin b)
+            pub fn exp(self) -> Complex<$t> { Complex::<$t>::from_polar(self.re.exp(), self.im) }
+
+            pub fn ln(self) -> Complex<$t> { Complex::new(self.abs().ln(), self.arg()) }
+
+            pub fn log(self, base: $t) -> Complex<$t> { self.ln() / base.ln() }
+
+            pub fn sqrt(self) -> Complex<$t>
+            {
+                if self.re == 0.0 && self.im == 0.0 { return Complex::new(0.0, self.im); }
+
+                // work out the larger part first and get the other one by
+                // division, so nothing cancels
+                let t = ((self.abs() + self.re.abs()) / 2.0).sqrt();
+                if self.re >= 0.0 {
+                    Complex::new(t, self.im / (2.0 * t))
+                } else {
+                    Complex::new(self.im.abs() / (2.0 * t), t.copysign(self.im))
+                }
+            }
+
+            // z^w = e^(w ln z), 0^w is 0 for any w with a positive real part
+            pub fn powc(self, w: Complex<$t>) -> Complex<$t>
+            {
+                if self.re == 0.0 && self.im == 0.0 {
+                    if w.re == 0.0 && w.im == 0.0 { return Complex::new(1.0, 0.0); }
+                    if w.re > 0.0 { return Complex::new(0.0, 0.0); }
+                }
+                (w * self.ln()).exp()
+            }
+
+            // real exponents stay in polar form: r^x (cos x theta + i sin x theta)
+            pub fn powf(self, x: $t) -> Complex<$t>
+            {
+                let (r, theta) = self.to_polar();
+                Complex::<$t>::from_polar(r.powf(x), theta * x)
+            }
+
+            // integer powers by repeated squaring, exact for gaussian integers
+            // that fit in the mantissa
+            pub fn powi(self, n: i32) -> Complex<$t>
+            {
+                let mut base = if n < 0 { self.inv() } else { self };
+                let mut exp = n.unsigned_abs();
+                let mut result = Complex::new(1.0, 0.0);
+                while exp > 0 {
+                    if exp & 1 == 1 { result *= base; }
+                    base = base * base;
+                    exp >>= 1;
+                }
+                result
+            }
+
+            /////////////////////////////////////
+
+            pub fn sin(self) -> Complex<$t>
+            {
+                Complex::new(self.re.sin() * self.im.cosh(), self.re.cos() * self.im.sinh())
+            }
+
+            pub fn cos(self) -> Complex<$t>
+            {
+                Complex::new(self.re.cos() * self.im.cosh(), -self.re.sin() * self.im.sinh())
+            }
+
+            // tan(a + bi) = (sin 2a + i sinh 2b) / (cos 2a + cosh 2b), far from the
+            // real axis cosh overflows while the result has long settled on +-i
+            pub fn tan(self) -> Complex<$t>
+            {
+                if self.im.abs() > 20.0 { return Complex::new(0.0, (1.0 as $t).copysign(self.im)); }
+                let (a, b) = (2.0 * self.re, 2.0 * self.im);
+                let d = a.cos() + b.cosh();
+                Complex::new(a.sin() / d, b.sinh() / d)
+            }
+
+            pub fn sinh(self) -> Complex<$t>
+            {
+                Complex::new(self.re.sinh() * self.im.cos(), self.re.cosh() * self.im.sin())
+            }
+
+            pub fn cosh(self) -> Complex<$t>
+            {
+                Complex::new(self.re.cosh() * self.im.cos(), self.re.sinh() * self.im.sin())
+            }
+
+            // tanh(a + bi) = (sinh 2a + i sin 2b) / (cosh 2a + cos 2b)
+            pub fn tanh(self) -> Complex<$t>
+            {
+                if self.re.abs() > 20.0 { return Complex::new((1.0 as $t).copysign(self.re), 0.0); }
+                let (a, b) = (2.0 * self.re, 2.0 * self.im);
+                let d = a.cosh() + b.cos();
+                Complex::new(a.sinh() / d, b.sin() / d)
+            }
+
+            /////////////////////////////////////
+
+            // i z, exact: only swaps the parts and flips a sign
+            fn mul_i(self) -> Complex<$t> { Complex::new(-self.im, self.re) }
+
+            // -i z
+            fn mul_neg_i(self) -> Complex<$t> { Complex::new(self.im, -self.re) }
+
+            // the real constants below are added as scalars, not as complex
+            // numbers: 1 + 0i would turn an imaginary -0.0 into 0.0 and jump
+            // to the wrong side of a cut
+
+            // asinh z = ln(z + sqrt(z^2 + 1)). on the left half plane the sum
+            // cancels, asinh is odd so the right half plane does the work
+            pub fn asinh(self) -> Complex<$t>
+            {
+                if self.re < 0.0 { return -(-self).asinh(); }
+                (self + (self * self + 1.0).sqrt()).ln()
+            }
+
+            // acosh z = ln(z + sqrt(z + 1) sqrt(z - 1)), the two square roots
+            // (instead of sqrt(z^2 - 1)) put the cut where it belongs
+            pub fn acosh(self) -> Complex<$t>
+            {
+                (self + (self + 1.0).sqrt() * (self - 1.0).sqrt()).ln()
+            }
+
+            // atanh z = (ln(1 + z) - ln(1 - z)) / 2
+            pub fn atanh(self) -> Complex<$t>
+            {
+                ((self + 1.0).ln() - (-self + 1.0).ln()) / 2.0
+            }
+
+            // asin z = -i asinh(i z)
+            pub fn asin(self) -> Complex<$t> { self.mul_i().asinh().mul_neg_i() }
+
+            // acos z = -i ln(z + i sqrt(1 - z^2)), unlike pi/2 - asin z it stays
+            // accurate next to z = 1
+            pub fn acos(self) -> Complex<$t>
+            {
+                (self + (-(self * self) + 1.0).sqrt().mul_i()).ln().mul_neg_i()
+            }
+
+            // atan z = -i atanh(i z)
+            pub fn atan(self) -> Complex<$t> { self.mul_i().atanh().mul_neg_i() }
+        }
+    };
+}
+
+complex_math_impl!(f32);
+complex_math_impl!(f64);
+
+/////////////////////////////////////
+
+pub fn main()
+{
+    use std::f64::consts::{E, PI};
+
+    // euler: e^(i pi) + 1 = 0
+    let euler = (Complex::<f64>::I * PI).exp() + 1.0;
+    println!("e^(i pi) + 1 = {:?}", euler);
+
+    let z = Complex::new(3.0_f64, 4.0);
+    println!("|z| = {}, arg z = {}, conj z = {:?}", z.abs(), z.arg(), z.conj());
+    println!("sqrt(-4) = {:?}", Complex::new(-4.0_f64, 0.0).sqrt());
+    println!("i^i = {:?} (e^(-pi/2) = {})", Complex::<f64>::I.powc(Complex::<f64>::I), E.powf(-PI / 2.0));
+
+    // accuracy checks against identities on a grid of points around the origin,
+    // kept off the branch cuts where the inverse functions legitimately jump
+    let close = |a: Complex<f64>, b: Complex<f64>, what: &str, z: Complex<f64>| {
+        // relative near the poles of tan and tanh, where both sides are huge
+        let ok = a.approx_eq_abs(&b, 1e-12) || a.approx_eq_rel(&b, 1e-10);
+        assert!(ok, "{} failed at {:?}: {:?} vs {:?}", what, z, a, b);
+    };
+    let one = Complex::new(1.0, 0.0);
+    for i in -6..=6 {
+        for j in -6..=6 {
+            let z = Complex::new(i as f64 * 0.29 + 0.01, j as f64 * 0.31 + 0.02);
+
+            close(z.ln().exp(), z, "exp(ln z) = z", z);
+            close(z.sqrt() * z.sqrt(), z, "sqrt(z)^2 = z", z);
+            close(Complex::<f64>::from_polar(z.abs(), z.arg()), z, "from_polar(to_polar z) = z", z);
+            close(z.sin() * z.sin() + z.cos() * z.cos(), one, "sin^2 + cos^2 = 1", z);
+            close(z.cosh() * z.cosh() - z.sinh() * z.sinh(), one, "cosh^2 - sinh^2 = 1", z);
+            close(z.tan(), z.sin() / z.cos(), "tan = sin / cos", z);
+            close(z.tanh(), z.sinh() / z.cosh(), "tanh = sinh / cosh", z);
+            close(z.powf(2.5), (z.ln() * 2.5).exp(), "powf = exp(x ln z)", z);
+            close(z.powi(3), z * z * z, "powi(3) = z z z", z);
+
+            close(z.asin().sin(), z, "sin(asin z) = z", z);
+            close(z.acos().cos(), z, "cos(acos z) = z", z);
+            close(z.atan().tan(), z, "tan(atan z) = z", z);
+            close(z.asinh().sinh(), z, "sinh(asinh z) = z", z);
+            close(z.acosh().cosh(), z, "cosh(acosh z) = z", z);
+            close(z.atanh().tanh(), z, "tanh(atanh z) = z", z);
+        }
+    }
+
+    // principal values on either side of the cuts
+    let above = Complex::new(-2.0_f64, 0.0);
+    let below = Complex::new(-2.0_f64, -0.0);
+    assert!(above.ln().im == PI && below.ln().im == -PI);
+    assert!(above.sqrt().im > 0.0 && below.sqrt().im < 0.0);
+    assert!(Complex::new(2.0_f64, 0.0).asin().im > 0.0 && Complex::new(2.0_f64, -0.0).asin().im < 0.0);
+    println!("identities hold");
+}
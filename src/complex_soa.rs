@@ -0,0 +1,359 @@
+// complex samples as a structure of arrays: every real part in one vector and
+// every imaginary part in another, for DSP loops over millions of samples
+//
+// a Vec<Complex<f64>> interleaves re, im, re, im.. and a product needs both
+// halves of two neighbours, so the compiler has to shuffle lanes before it can
+// use simd. with the parts split each kernel is four plain streams of floats,
+// which llvm vectorizes on its own: the loops below are zips over slices of
+// one length (no bounds checks left to stop it) with nothing but + - * inside
+//
+// sums are the exception, float addition isn't associative so llvm won't
+// reorder a reduction. conj_dot keeps LANES partial sums by hand, which is the
+// reordering it would do itself, and adds them up at the end. the real and
+// imaginary sums take a pass each (in one loop llvm packs the re and im of a
+// sample into a register together and spends its time shuffling), block by
+// block so that memory is only read once
+
+use crate::num::Float;
+use crate::Complex;
+
+use std::iter::FromIterator;
+
+// independent accumulators in the reductions, enough for two avx registers of f64
+const LANES: usize = 8;
+
+// samples per block in conj_dot: the second pass finds the block still in l1
+const BLOCK: usize = 1024;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ComplexBuffer<T = f64>
+{
+    re: Vec<T>,
+    im: Vec<T>,
+}
+
+impl<T: Float> ComplexBuffer<T>
+{
+    pub fn new() -> ComplexBuffer<T> { ComplexBuffer { re: vec![], im: vec![] } }
+
+    pub fn zeros(n: usize) -> ComplexBuffer<T> { ComplexBuffer { re: vec![T::zero(); n], im: vec![T::zero(); n] } }
+
+    // panics when the two parts don't have the same length
+    pub fn from_parts(re: Vec<T>, im: Vec<T>) -> ComplexBuffer<T>
+    {
+        assert_eq!(re.len(), im.len(), "real and imaginary parts differ in length");
+        ComplexBuffer { re, im }
+    }
+
+    pub fn into_parts(self) -> (Vec<T>, Vec<T>) { (self.re, self.im) }
+
+    pub fn len(&self) -> usize { self.re.len() }
+
+    pub fn is_empty(&self) -> bool { self.re.is_empty() }
+
+    pub fn re(&self) -> &[T] { &self.re }
+
+    pub fn im(&self) -> &[T] { &self.im }
+
+    // both parts at once, they can't be borrowed mutably one after the other
+    pub fn parts_mut(&mut self) -> (&mut [T], &mut [T]) { (&mut self.re, &mut self.im) }
+
+    pub fn get(&self, i: usize) -> Option<Complex<T>>
+    {
+        if i < self.len() { Some(Complex::new(self.re[i], self.im[i])) } else { None }
+    }
+
+    pub fn set(&mut self, i: usize, z: Complex<T>)
+    {
+        self.re[i] = z.re;
+        self.im[i] = z.im;
+    }
+
+    pub fn push(&mut self, z: Complex<T>)
+    {
+        self.re.push(z.re);
+        self.im.push(z.im);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Complex<T>> + '_
+    {
+        self.re.iter().zip(&self.im).map(|(&re, &im)| Complex::new(re, im))
+    }
+
+    pub fn to_vec(&self) -> Vec<Complex<T>> { self.iter().collect() }
+
+    /////////////////////////////////////
+    // the kernels panic when the lengths differ, like copy_from_slice
+
+    // self[i] *= other[i]
+    pub fn mul_assign(&mut self, other: &ComplexBuffer<T>)
+    {
+        assert_eq!(self.len(), other.len(), "buffers differ in length");
+        let lhs = self.re.iter_mut().zip(self.im.iter_mut());
+        for ((ar, ai), (&br, &bi)) in lhs.zip(other.re.iter().zip(&other.im)) {
+            let (r, i) = (*ar * br - *ai * bi, *ar * bi + *ai * br);
+            *ar = r;
+            *ai = i;
+        }
+    }
+
+    // out[i] = self[i] * other[i], out is overwritten and keeps its allocation
+    pub fn mul_into(&self, other: &ComplexBuffer<T>, out: &mut ComplexBuffer<T>)
+    {
+        assert_eq!(self.len(), other.len(), "buffers differ in length");
+        out.re.resize(self.len(), T::zero());
+        out.im.resize(self.len(), T::zero());
+        let lhs = self.re.iter().zip(&self.im);
+        let rhs = other.re.iter().zip(&other.im);
+        let dst = out.re.iter_mut().zip(out.im.iter_mut());
+        for ((or, oi), ((&ar, &ai), (&br, &bi))) in dst.zip(lhs.zip(rhs)) {
+            *or = ar * br - ai * bi;
+            *oi = ar * bi + ai * br;
+        }
+    }
+
+    // self[i] += a[i] * b[i], multiply accumulate
+    pub fn mac(&mut self, a: &ComplexBuffer<T>, b: &ComplexBuffer<T>)
+    {
+        assert!(self.len() == a.len() && a.len() == b.len(), "buffers differ in length");
+        let acc = self.re.iter_mut().zip(self.im.iter_mut());
+        let ab = a.re.iter().zip(&a.im).zip(b.re.iter().zip(&b.im));
+        for ((cr, ci), ((&ar, &ai), (&br, &bi))) in acc.zip(ab) {
+            *cr = *cr + (ar * br - ai * bi);
+            *ci = *ci + (ar * bi + ai * br);
+        }
+    }
+
+    // out[i] = |self[i]|^2
+    pub fn norm_sqr_into(&self, out: &mut [T])
+    {
+        assert_eq!(self.len(), out.len(), "output differs in length");
+        for (o, (&r, &i)) in out.iter_mut().zip(self.re.iter().zip(&self.im)) {
+            *o = r * r + i * i;
+        }
+    }
+
+    pub fn norm_sqr(&self) -> Vec<T>
+    {
+        let mut out = vec![T::zero(); self.len()];
+        self.norm_sqr_into(&mut out);
+        out
+    }
+
+    // sum of conj(self[i]) other[i], the inner product that is linear in the
+    // second argument. the order of the additions differs from a plain loop,
+    // so the last bits can too
+    pub fn conj_dot(&self, other: &ComplexBuffer<T>) -> Complex<T>
+    {
+        assert_eq!(self.len(), other.len(), "buffers differ in length");
+        let (mut re, mut im) = (T::zero(), T::zero());
+        for start in (0..self.len()).step_by(BLOCK) {
+            let r = start..(start + BLOCK).min(self.len());
+            let (ar, ai, br, bi) = (&self.re[r.clone()], &self.im[r.clone()], &other.re[r.clone()], &other.im[r]);
+            // (ar - i ai)(br + i bi) = (ar br + ai bi) + (ar bi - ai br)i
+            re = re + lane_sum([ar, br, ai, bi], |a, b, c, d| a * b + c * d);
+            im = im + lane_sum([ar, bi, ai, br], |a, b, c, d| a * b - c * d);
+        }
+        Complex::new(re, im)
+    }
+}
+
+fn lanes<T>(chunk: &[T]) -> &[T; LANES] { chunk.try_into().unwrap() }
+
+// the sum of term(a[i], b[i], c[i], d[i]) over four slices of one length, in
+// LANES independent running sums. the arrays tell llvm the chunk length, with
+// plain slices it keeps a bounds check in the inner loop
+fn lane_sum<T: Float>([a, b, c, d]: [&[T]; 4], term: impl Fn(T, T, T, T) -> T) -> T
+{
+    let n = a.len() / LANES * LANES;
+    let mut sums = [T::zero(); LANES];
+    let chunks = a[..n].chunks_exact(LANES).zip(b[..n].chunks_exact(LANES));
+    let chunks = chunks.zip(c[..n].chunks_exact(LANES).zip(d[..n].chunks_exact(LANES)));
+    for ((a, b), (c, d)) in chunks {
+        let (a, b, c, d) = (lanes(a), lanes(b), lanes(c), lanes(d));
+        for (k, sum) in sums.iter_mut().enumerate() {
+            *sum = *sum + term(a[k], b[k], c[k], d[k]);
+        }
+    }
+
+    let tail = (n..a.len()).fold(T::zero(), |acc, k| acc + term(a[k], b[k], c[k], d[k]));
+    sums.iter().fold(T::zero(), |acc, &x| acc + x) + tail
+}
+
+impl<'a, T: Float> From<&'a [Complex<T>]> for ComplexBuffer<T>
+{
+    fn from(samples: &'a [Complex<T>]) -> ComplexBuffer<T> { samples.iter().copied().collect() }
+}
+
+impl<T: Float> FromIterator<Complex<T>> for ComplexBuffer<T>
+{
+    fn from_iter<I: IntoIterator<Item = Complex<T>>>(iter: I) -> ComplexBuffer<T>
+    {
+        let mut buffer = ComplexBuffer::new();
+        for z in iter { buffer.push(z); }
+        buffer
+    }
+}
+
+/////////////////////////////////////
+// the same kernels as plain loops over interleaved samples, what the
+// benchmark compares against
+
+pub fn mul_assign_aos(a: &mut [Complex<f64>], b: &[Complex<f64>])
+{
+    for (x, y) in a.iter_mut().zip(b) { *x *= *y; }
+}
+
+pub fn mac_aos(acc: &mut [Complex<f64>], a: &[Complex<f64>], b: &[Complex<f64>])
+{
+    for (c, (x, y)) in acc.iter_mut().zip(a.iter().zip(b)) { *c += *x * *y; }
+}
+
+pub fn norm_sqr_aos(a: &[Complex<f64>], out: &mut [f64])
+{
+    for (o, x) in out.iter_mut().zip(a) { *o = x.norm_sqr(); }
+}
+
+pub fn conj_dot_aos(a: &[Complex<f64>], b: &[Complex<f64>]) -> Complex<f64>
+{
+    a.iter().zip(b).map(|(x, y)| x.conj() * *y).sum()
+}
+
+fn random_samples(n: usize, rng: &mut rand::rngs::StdRng) -> Vec<Complex<f64>>
+{
+    use rand::Rng;
+    (0..n).map(|_| Complex::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0))).collect()
+}
+
+pub fn main()
+{
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    // the kernels are checked against the plain loops in the tests below,
+    // this only shows them at work
+    let mut rng = StdRng::seed_from_u64(42);
+    let (a, b) = (random_samples(4, &mut rng), random_samples(4, &mut rng));
+    let (sa, sb) = (ComplexBuffer::from(&a[..]), ComplexBuffer::from(&b[..]));
+    let mut product = ComplexBuffer::new();
+    sa.mul_into(&sb, &mut product);
+    println!("a * b = {:?}", product.to_vec());
+    println!("|a|^2 = {:?}", sa.norm_sqr());
+    println!("<a, b> = {:?}", sa.conj_dot(&sb));
+
+    // the parts are plain slices, for code that wants them one at a time
+    let (re, im) = product.clone().into_parts();
+    let mut rebuilt = ComplexBuffer::from_parts(re, im);
+    assert_eq!(rebuilt, product);
+    let (re, im) = rebuilt.parts_mut();
+    for (r, i) in re.iter_mut().zip(im.iter_mut()) { (*r, *i) = (-*i, *r); }
+    rebuilt.set(0, Complex::new(0.0, 0.0));
+    println!("times i, first zeroed: {:?} {:?}", rebuilt.re(), rebuilt.im());
+    assert_eq!(rebuilt.get(1), product.get(1).map(|z| Complex::new(-z.im, z.re)));
+    assert!(!rebuilt.is_empty() && rebuilt.get(4).is_none());
+}
+
+pub fn benchmark()
+{
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::hint::black_box;
+    use std::time::Instant;
+
+    let mut rng = StdRng::seed_from_u64(42);
+    let time = |label: &str, n: usize, run: &mut dyn FnMut()| -> f64 {
+        let runs = (1 << 24) / n + 1;
+        let start = Instant::now();
+        for _ in 0..runs { run(); }
+        let per_sample = start.elapsed().as_secs_f64() / (runs * n) as f64;
+        println!("{:>14} n = {:>8}: {:>7.3} ns/sample", label, n, per_sample * 1e9);
+        per_sample
+    };
+
+    // from fitting in l1 to well past the caches, where memory bandwidth decides
+    for n in [1 << 10, 1 << 14, 1 << 20] {
+        let (a, b) = (random_samples(n, &mut rng), random_samples(n, &mut rng));
+        let (sa, sb) = (ComplexBuffer::from(&a[..]), ComplexBuffer::from(&b[..]));
+
+        // multiplying in place over and over by anything but unit numbers
+        // would run into denormals (or infinities) and time those instead
+        let unit: Vec<Complex<f64>> = b.iter().map(|z| *z / z.abs()).collect();
+        let sunit = ComplexBuffer::from(&unit[..]);
+        let mut x = a.clone();
+        let aos = time("mul aos", n, &mut || mul_assign_aos(black_box(&mut x), &unit));
+        let mut sx = sa.clone();
+        let soa = time("mul soa", n, &mut || sx.mul_assign(black_box(&sunit)));
+        println!("{:>14} {:.2}x", "speedup", aos / soa);
+
+        let mut acc = vec![Complex::new(0.0, 0.0); n];
+        let aos = time("mac aos", n, &mut || mac_aos(black_box(&mut acc), &a, &b));
+        let mut sacc = ComplexBuffer::zeros(n);
+        let soa = time("mac soa", n, &mut || sacc.mac(&sa, black_box(&sb)));
+        println!("{:>14} {:.2}x", "speedup", aos / soa);
+
+        let mut out = vec![0.0; n];
+        let aos = time("norm_sqr aos", n, &mut || norm_sqr_aos(&a, black_box(&mut out)));
+        let soa = time("norm_sqr soa", n, &mut || sa.norm_sqr_into(black_box(&mut out)));
+        println!("{:>14} {:.2}x", "speedup", aos / soa);
+
+        let aos = time("conj_dot aos", n, &mut || { black_box(conj_dot_aos(black_box(&a), &b)); });
+        let soa = time("conj_dot soa", n, &mut || { black_box(black_box(&sa).conj_dot(&sb)); });
+        println!("{:>14} {:.2}x", "speedup", aos / soa);
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    // every kernel against the array of structs loop, on a length that isn't
+    // a multiple of LANES so the tail of conj_dot runs too
+    #[test]
+    fn kernels_match_the_plain_loops()
+    {
+        let mut rng = StdRng::seed_from_u64(42);
+        let n = 1003;
+        let (a, b, c) = (random_samples(n, &mut rng), random_samples(n, &mut rng), random_samples(n, &mut rng));
+        let (sa, sb) = (ComplexBuffer::from(&a[..]), ComplexBuffer::from(&b[..]));
+        let close = |x: Complex<f64>, y: Complex<f64>| x.approx_eq_abs(&y, 1e-12);
+
+        let mut product = sa.clone();
+        product.mul_assign(&sb);
+        let mut expected = a.clone();
+        mul_assign_aos(&mut expected, &b);
+        assert!(product.iter().zip(&expected).all(|(x, y)| close(x, *y)));
+
+        let mut out = ComplexBuffer::new();
+        sa.mul_into(&sb, &mut out);
+        assert_eq!(out, product);
+
+        let mut acc = ComplexBuffer::from(&c[..]);
+        acc.mac(&sa, &sb);
+        let mut expected = c.clone();
+        mac_aos(&mut expected, &a, &b);
+        assert!(acc.iter().zip(&expected).all(|(x, y)| close(x, *y)));
+
+        let mut expected = vec![0.0; n];
+        norm_sqr_aos(&a, &mut expected);
+        assert_eq!(sa.norm_sqr(), expected);
+
+        let dot = sa.conj_dot(&sb);
+        assert!(dot.approx_eq_abs(&conj_dot_aos(&a, &b), 1e-10));
+        // <a, a> = |a|^2, real
+        let self_dot = sa.conj_dot(&sa);
+        assert!(self_dot.im == 0.0 && (self_dot.re - expected.iter().sum::<f64>()).abs() < 1e-10);
+    }
+
+    // f32 buffers use the same kernels
+    #[test]
+    fn f32_buffers()
+    {
+        let small: ComplexBuffer<f32> = (0..10).map(|k| Complex::new(k as f32, 1.0)).collect();
+        assert_eq!(small.conj_dot(&small), Complex::new(295.0, 0.0));
+        assert_eq!(small.get(3), Some(Complex::new(3.0, 1.0)));
+        assert_eq!(small.get(10), None);
+    }
+}
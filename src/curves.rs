@@ -0,0 +1,912 @@
+// bezier curves, circular arcs and paths made out of them
+// they all work on the Point / Line / Rect types from main.rs
+
+use crate::{Line, Point, Rect, Shape};
+
+use std::f64::consts::{FRAC_PI_2, PI};
+
+// flattening and arc length subdivide at most this many times,
+// so a curve never turns into more than 2^16 pieces
+const MAX_DEPTH: u32 = 16;
+
+// parameters a bit outside of [0, 1] still count as on the curve
+const T_EPSILON: f64 = 1e-9;
+
+fn on_curve(t: f64) -> bool { (-T_EPSILON..=1.0 + T_EPSILON).contains(&t) }
+
+fn lerp(a: Point, b: Point, t: f64) -> Point
+{
+    Point { x: a.x + (b.x - a.x) * t, y: a.y + (b.y - a.y) * t }
+}
+
+fn distance(a: Point, b: Point) -> f64 { Line { start: a, end: b }.len() }
+
+fn sub(a: Point, b: Point) -> Point { Point { x: a.x - b.x, y: a.y - b.y } }
+
+fn cross(a: Point, b: Point) -> f64 { a.x * b.y - a.y * b.x }
+
+fn dot(a: Point, b: Point) -> f64 { a.x * b.x + a.y * b.y }
+
+// real roots of a*t^2 + b*t + c
+fn quadratic_roots(a: f64, b: f64, c: f64) -> Vec<f64>
+{
+    let scale = a.abs().max(b.abs()).max(c.abs());
+    if scale == 0.0 { return vec![]; }
+
+    if a.abs() <= 1e-12 * scale {
+        if b.abs() <= 1e-12 * scale { return vec![]; }
+        return vec![-c / b];
+    }
+
+    let disc = b * b - 4.0 * a * c;
+    if disc < 0.0 { return vec![]; }
+
+    // avoids the cancellation of the textbook formula
+    let q = -0.5 * (b + b.signum() * disc.sqrt());
+    if q == 0.0 { return vec![0.0]; }
+    vec![q / a, c / q]
+}
+
+// real roots of a*t^3 + b*t^2 + c*t + d
+fn cubic_roots(a: f64, b: f64, c: f64, d: f64) -> Vec<f64>
+{
+    let scale = a.abs().max(b.abs()).max(c.abs()).max(d.abs());
+    if a.abs() <= 1e-12 * scale { return quadratic_roots(b, c, d); }
+
+    let (b, c, d) = (b / a, c / a, d / a);
+
+    // substitute t = x - b/3 to get x^3 + p*x + q
+    let p = c - b * b / 3.0;
+    let q = 2.0 * b * b * b / 27.0 - b * c / 3.0 + d;
+    let disc = (q / 2.0) * (q / 2.0) + (p / 3.0) * (p / 3.0) * (p / 3.0);
+
+    let roots: Vec<f64> = if p.abs() < 1e-14 {
+        vec![(-q).cbrt()]
+    } else if disc > 0.0 {
+        let s = disc.sqrt();
+        vec![(-q / 2.0 + s).cbrt() + (-q / 2.0 - s).cbrt()]
+    } else {
+        let r = (-p / 3.0).sqrt();
+        let phi = (-q / (2.0 * r * r * r)).clamp(-1.0, 1.0).acos();
+        (0..3).map(|k| 2.0 * r * ((phi + 2.0 * PI * k as f64) / 3.0).cos()).collect()
+    };
+
+    // a couple of newton steps clean up the rounding of the closed form
+    roots.into_iter()
+        .map(|x| x - b / 3.0)
+        .map(|mut t| {
+            for _ in 0..2 {
+                let f = ((t + b) * t + c) * t + d;
+                let df = (3.0 * t + 2.0 * b) * t + c;
+                if df != 0.0 { t -= f / df; }
+            }
+            t
+        })
+        .collect()
+}
+
+// keeps the roots inside the curve's parameter range
+fn unit_roots(roots: Vec<f64>) -> Vec<f64>
+{
+    let mut ts: Vec<f64> = roots.into_iter()
+        .filter(|t| on_curve(*t))
+        .map(|t| t.clamp(0.0, 1.0))
+        .collect();
+    ts.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    ts.dedup_by(|a, b| (*a - *b).abs() < T_EPSILON);
+    ts
+}
+
+// where along `line` (0 at start, 1 at end) the point lies, if it is on the segment
+fn line_param(line: &Line, p: Point) -> Option<f64>
+{
+    let dir = sub(line.end, line.start);
+    let s = dot(sub(p, line.start), dir) / dot(dir, dir);
+    if on_curve(s) { Some(s) } else { None }
+}
+
+// the curve is a polynomial in t with these power basis coefficients (constant first),
+// the intersections are where its signed distance from the line is zero
+fn polynomial_line_intersections<C: Curve>(curve: &C, coeffs: &[Point], line: &Line) -> Vec<(f64, Point)>
+{
+    let dir = sub(line.end, line.start);
+    if dot(dir, dir) == 0.0 { return vec![]; }
+
+    let mut dist: Vec<f64> = coeffs.iter().map(|c| cross(dir, *c)).collect();
+    dist[0] = cross(dir, sub(coeffs[0], line.start));
+
+    let roots = match dist.len() {
+        3 => quadratic_roots(dist[2], dist[1], dist[0]),
+        4 => cubic_roots(dist[3], dist[2], dist[1], dist[0]),
+        _ => unreachable!("only quadratic and cubic curves"),
+    };
+
+    unit_roots(roots).into_iter()
+        .map(|t| (t, curve.eval(t)))
+        .filter(|(_, p)| line_param(line, *p).is_some())
+        .collect()
+}
+
+fn flatten_into<C: Curve>(curve: &C, tolerance: f64, flatness: fn(&C) -> f64, depth: u32, out: &mut Vec<Point>)
+{
+    if depth >= MAX_DEPTH || flatness(curve) <= tolerance {
+        out.push(curve.end());
+        return;
+    }
+    let (left, right) = curve.split(0.5);
+    flatten_into(&left, tolerance, flatness, depth + 1, out);
+    flatten_into(&right, tolerance, flatness, depth + 1, out);
+}
+
+// Gravesen's method: the true length is between the chord and the control polygon,
+// subdivide until the two are close enough and blend them
+fn bezier_length<C: Curve>(curve: &C, controls: fn(&C) -> Vec<Point>, tolerance: f64, depth: u32) -> f64
+{
+    let points = controls(curve);
+    let degree = (points.len() - 1) as f64;
+    let chord = distance(points[0], points[points.len() - 1]);
+    let polygon: f64 = points.windows(2).map(|w| distance(w[0], w[1])).sum();
+
+    if depth >= MAX_DEPTH || polygon - chord <= tolerance {
+        return (2.0 * chord + (degree - 1.0) * polygon) / (degree + 1.0);
+    }
+    let (left, right) = curve.split(0.5);
+    bezier_length(&left, controls, tolerance / 2.0, depth + 1)
+        + bezier_length(&right, controls, tolerance / 2.0, depth + 1)
+}
+
+// 3 point gauss-legendre on [0, 1], exact for polynomials up to degree 5
+const GAUSS_NODES: [(f64, f64); 3] = [
+    (0.112_701_665_379_258_31, 5.0 / 18.0),
+    (0.5, 8.0 / 18.0),
+    (0.887_298_334_620_741_7, 5.0 / 18.0),
+];
+
+// half of the integral of x dy - y dx along the curve, summing it over a closed
+// loop gives the signed area (green's theorem)
+fn green_integral(eval: impl Fn(f64) -> Point, derivative: impl Fn(f64) -> Point) -> f64
+{
+    GAUSS_NODES.iter()
+        .map(|(t, w)| w * cross(eval(*t), derivative(*t)))
+        .sum::<f64>() / 2.0
+}
+
+/////////////////////////////////////
+
+// anything that goes from start() to end() as t goes from 0 to 1
+pub trait Curve
+{
+    fn start(&self) -> Point;
+
+    fn end(&self) -> Point;
+
+    fn eval(&self, t: f64) -> Point;
+
+    // the part before t and the part after t
+    fn split(&self, t: f64) -> (Self, Self) where Self: Sized;
+
+    fn arc_length(&self, tolerance: f64) -> f64;
+
+    fn bounding_box(&self) -> Rect;
+
+    // polyline from start() to end() that never strays more than tolerance from the curve
+    fn flatten(&self, tolerance: f64) -> Vec<Point>;
+
+    // pairs of (t on the curve, point), ordered by t
+    fn intersect_line(&self, line: &Line) -> Vec<(f64, Point)>;
+}
+
+impl Curve for Line
+{
+    fn start(&self) -> Point { self.start }
+
+    fn end(&self) -> Point { self.end }
+
+    fn eval(&self, t: f64) -> Point { lerp(self.start, self.end, t) }
+
+    fn split(&self, t: f64) -> (Line, Line)
+    {
+        let mid = self.eval(t);
+        (Line { start: self.start, end: mid }, Line { start: mid, end: self.end })
+    }
+
+    fn arc_length(&self, _tolerance: f64) -> f64 { self.len() }
+
+    fn bounding_box(&self) -> Rect { Rect::new(self.start, self.end) }
+
+    fn flatten(&self, _tolerance: f64) -> Vec<Point> { vec![self.start, self.end] }
+
+    fn intersect_line(&self, line: &Line) -> Vec<(f64, Point)>
+    {
+        let d1 = sub(self.end, self.start);
+        let d2 = sub(line.end, line.start);
+        let denom = cross(d1, d2);
+        // parallel or degenerate, overlapping collinear segments don't count
+        if denom == 0.0 { return vec![]; }
+
+        let offset = sub(line.start, self.start);
+        let t = cross(offset, d2) / denom;
+        let s = cross(offset, d1) / denom;
+        if on_curve(t) && on_curve(s) {
+            let t = t.clamp(0.0, 1.0);
+            vec![(t, self.eval(t))]
+        } else {
+            vec![]
+        }
+    }
+}
+
+/////////////////////////////////////
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuadBezier
+{
+    pub p0: Point,
+    pub p1: Point,
+    pub p2: Point,
+}
+
+impl QuadBezier
+{
+    pub fn new(p0: Point, p1: Point, p2: Point) -> QuadBezier
+    {
+        QuadBezier { p0, p1, p2 }
+    }
+
+    fn derivative(&self, t: f64) -> Point
+    {
+        let d = lerp(sub(self.p1, self.p0), sub(self.p2, self.p1), t);
+        Point { x: 2.0 * d.x, y: 2.0 * d.y }
+    }
+
+    // max distance between the curve and its chord
+    fn flatness(&self) -> f64
+    {
+        let d = Point {
+            x: self.p0.x - 2.0 * self.p1.x + self.p2.x,
+            y: self.p0.y - 2.0 * self.p1.y + self.p2.y,
+        };
+        dot(d, d).sqrt() / 4.0
+    }
+
+    fn controls(&self) -> Vec<Point> { vec![self.p0, self.p1, self.p2] }
+}
+
+impl Curve for QuadBezier
+{
+    fn start(&self) -> Point { self.p0 }
+
+    fn end(&self) -> Point { self.p2 }
+
+    fn eval(&self, t: f64) -> Point
+    {
+        lerp(lerp(self.p0, self.p1, t), lerp(self.p1, self.p2, t), t)
+    }
+
+    fn split(&self, t: f64) -> (QuadBezier, QuadBezier)
+    {
+        let a = lerp(self.p0, self.p1, t);
+        let b = lerp(self.p1, self.p2, t);
+        let mid = lerp(a, b, t);
+        (QuadBezier::new(self.p0, a, mid), QuadBezier::new(mid, b, self.p2))
+    }
+
+    fn arc_length(&self, tolerance: f64) -> f64
+    {
+        bezier_length(self, QuadBezier::controls, tolerance, 0)
+    }
+
+    fn bounding_box(&self) -> Rect
+    {
+        // the derivative is linear, each axis has at most one extreme
+        let extreme = |a: f64, b: f64, c: f64| {
+            let denom = a - 2.0 * b + c;
+            if denom == 0.0 { None } else { Some((a - b) / denom) }
+        };
+        [extreme(self.p0.x, self.p1.x, self.p2.x), extreme(self.p0.y, self.p1.y, self.p2.y)]
+            .iter()
+            .flatten()
+            .filter(|t| **t > 0.0 && **t < 1.0)
+            .fold(Rect::new(self.p0, self.p2), |rect, t| rect.expand(self.eval(*t)))
+    }
+
+    fn flatten(&self, tolerance: f64) -> Vec<Point>
+    {
+        let mut points = vec![self.p0];
+        flatten_into(self, tolerance, QuadBezier::flatness, 0, &mut points);
+        points
+    }
+
+    fn intersect_line(&self, line: &Line) -> Vec<(f64, Point)>
+    {
+        let coeffs = [
+            self.p0,
+            Point { x: 2.0 * (self.p1.x - self.p0.x), y: 2.0 * (self.p1.y - self.p0.y) },
+            Point {
+                x: self.p0.x - 2.0 * self.p1.x + self.p2.x,
+                y: self.p0.y - 2.0 * self.p1.y + self.p2.y,
+            },
+        ];
+        polynomial_line_intersections(self, &coeffs, line)
+    }
+}
+
+/////////////////////////////////////
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CubicBezier
+{
+    pub p0: Point,
+    pub p1: Point,
+    pub p2: Point,
+    pub p3: Point,
+}
+
+impl CubicBezier
+{
+    pub fn new(p0: Point, p1: Point, p2: Point, p3: Point) -> CubicBezier
+    {
+        CubicBezier { p0, p1, p2, p3 }
+    }
+
+    fn derivative(&self, t: f64) -> Point
+    {
+        let d = QuadBezier::new(sub(self.p1, self.p0), sub(self.p2, self.p1), sub(self.p3, self.p2));
+        let p = d.eval(t);
+        Point { x: 3.0 * p.x, y: 3.0 * p.y }
+    }
+
+    // upper bound of the distance between the curve and its chord
+    fn flatness(&self) -> f64
+    {
+        let second = |a: Point, b: Point, c: Point| {
+            let d = Point { x: a.x - 2.0 * b.x + c.x, y: a.y - 2.0 * b.y + c.y };
+            dot(d, d).sqrt()
+        };
+        0.75 * second(self.p0, self.p1, self.p2).max(second(self.p1, self.p2, self.p3))
+    }
+
+    fn controls(&self) -> Vec<Point> { vec![self.p0, self.p1, self.p2, self.p3] }
+}
+
+impl Curve for CubicBezier
+{
+    fn start(&self) -> Point { self.p0 }
+
+    fn end(&self) -> Point { self.p3 }
+
+    fn eval(&self, t: f64) -> Point
+    {
+        let a = lerp(self.p0, self.p1, t);
+        let b = lerp(self.p1, self.p2, t);
+        let c = lerp(self.p2, self.p3, t);
+        lerp(lerp(a, b, t), lerp(b, c, t), t)
+    }
+
+    fn split(&self, t: f64) -> (CubicBezier, CubicBezier)
+    {
+        let a = lerp(self.p0, self.p1, t);
+        let b = lerp(self.p1, self.p2, t);
+        let c = lerp(self.p2, self.p3, t);
+        let ab = lerp(a, b, t);
+        let bc = lerp(b, c, t);
+        let mid = lerp(ab, bc, t);
+        (CubicBezier::new(self.p0, a, ab, mid), CubicBezier::new(mid, bc, c, self.p3))
+    }
+
+    fn arc_length(&self, tolerance: f64) -> f64
+    {
+        bezier_length(self, CubicBezier::controls, tolerance, 0)
+    }
+
+    fn bounding_box(&self) -> Rect
+    {
+        // the derivative is a quadratic, each axis has at most two extremes
+        let extremes = |a: f64, b: f64, c: f64, d: f64| {
+            quadratic_roots(
+                3.0 * (-a + 3.0 * b - 3.0 * c + d),
+                6.0 * (a - 2.0 * b + c),
+                3.0 * (b - a),
+            )
+        };
+        let mut ts = extremes(self.p0.x, self.p1.x, self.p2.x, self.p3.x);
+        ts.extend(extremes(self.p0.y, self.p1.y, self.p2.y, self.p3.y));
+        ts.into_iter()
+            .filter(|t| *t > 0.0 && *t < 1.0)
+            .fold(Rect::new(self.p0, self.p3), |rect, t| rect.expand(self.eval(t)))
+    }
+
+    fn flatten(&self, tolerance: f64) -> Vec<Point>
+    {
+        let mut points = vec![self.p0];
+        flatten_into(self, tolerance, CubicBezier::flatness, 0, &mut points);
+        points
+    }
+
+    fn intersect_line(&self, line: &Line) -> Vec<(f64, Point)>
+    {
+        let (p0, p1, p2, p3) = (self.p0, self.p1, self.p2, self.p3);
+        let coeffs = [
+            p0,
+            Point { x: 3.0 * (p1.x - p0.x), y: 3.0 * (p1.y - p0.y) },
+            Point { x: 3.0 * (p0.x - 2.0 * p1.x + p2.x), y: 3.0 * (p0.y - 2.0 * p1.y + p2.y) },
+            Point {
+                x: p3.x - 3.0 * p2.x + 3.0 * p1.x - p0.x,
+                y: p3.y - 3.0 * p2.y + 3.0 * p1.y - p0.y,
+            },
+        ];
+        polynomial_line_intersections(self, &coeffs, line)
+    }
+}
+
+/////////////////////////////////////
+
+// part of a circle, angles in radians, a positive sweep goes counter clockwise
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CircularArc
+{
+    pub center: Point,
+    pub radius: f64,
+    pub start_angle: f64,
+    pub sweep: f64,
+}
+
+impl CircularArc
+{
+    pub fn new(center: Point, radius: f64, start_angle: f64, sweep: f64) -> CircularArc
+    {
+        CircularArc { center, radius, start_angle, sweep }
+    }
+
+    fn at_angle(&self, angle: f64) -> Point
+    {
+        Point {
+            x: self.center.x + self.radius * angle.cos(),
+            y: self.center.y + self.radius * angle.sin(),
+        }
+    }
+
+    // t of the point of the circle at this angle, None if the arc doesn't reach it
+    fn param_of_angle(&self, angle: f64) -> Option<f64>
+    {
+        if self.sweep == 0.0 { return None; }
+        let mut delta = (angle - self.start_angle).rem_euclid(2.0 * PI);
+        if self.sweep < 0.0 && delta != 0.0 { delta -= 2.0 * PI; }
+        let t = delta / self.sweep;
+        if t <= 1.0 + T_EPSILON { Some(t.min(1.0)) } else { None }
+    }
+
+    fn green(&self) -> f64
+    {
+        let (a0, a1) = (self.start_angle, self.start_angle + self.sweep);
+        let (c, r) = (self.center, self.radius);
+        (r * (c.x * (a1.sin() - a0.sin()) - c.y * (a1.cos() - a0.cos())) + r * r * self.sweep) / 2.0
+    }
+}
+
+impl Curve for CircularArc
+{
+    fn start(&self) -> Point { self.at_angle(self.start_angle) }
+
+    fn end(&self) -> Point { self.at_angle(self.start_angle + self.sweep) }
+
+    fn eval(&self, t: f64) -> Point { self.at_angle(self.start_angle + self.sweep * t) }
+
+    fn split(&self, t: f64) -> (CircularArc, CircularArc)
+    {
+        let first = self.sweep * t;
+        (
+            CircularArc::new(self.center, self.radius, self.start_angle, first),
+            CircularArc::new(self.center, self.radius, self.start_angle + first, self.sweep - first),
+        )
+    }
+
+    fn arc_length(&self, _tolerance: f64) -> f64 { self.radius * self.sweep.abs() }
+
+    fn bounding_box(&self) -> Rect
+    {
+        // besides the end points the box touches the circle only at multiples of 90 degrees
+        let a0 = self.start_angle;
+        let a1 = self.start_angle + self.sweep;
+        let (lo, hi) = (a0.min(a1), a0.max(a1));
+        let first = (lo / FRAC_PI_2).ceil() as i64;
+        let last = (hi / FRAC_PI_2).floor() as i64;
+        (first..=last)
+            .map(|k| self.at_angle(k as f64 * FRAC_PI_2))
+            .fold(Rect::new(self.start(), self.end()), |rect, p| rect.expand(p))
+    }
+
+    fn flatten(&self, tolerance: f64) -> Vec<Point>
+    {
+        // a chord spanning the angle step sags by radius * (1 - cos(step / 2))
+        let ratio = (tolerance / self.radius).min(1.0);
+        let step = (2.0 * (1.0 - ratio).acos()).clamp(1e-6, FRAC_PI_2);
+        let pieces = (self.sweep.abs() / step).ceil().max(1.0) as usize;
+        (0..=pieces).map(|i| self.eval(i as f64 / pieces as f64)).collect()
+    }
+
+    fn intersect_line(&self, line: &Line) -> Vec<(f64, Point)>
+    {
+        let dir = sub(line.end, line.start);
+        let offset = sub(line.start, self.center);
+        let roots = quadratic_roots(
+            dot(dir, dir),
+            2.0 * dot(dir, offset),
+            dot(offset, offset) - self.radius * self.radius,
+        );
+
+        let mut hits: Vec<(f64, Point)> = unit_roots(roots).into_iter()
+            .filter_map(|s| {
+                let p = line.eval(s);
+                let angle = (p.y - self.center.y).atan2(p.x - self.center.x);
+                self.param_of_angle(angle).map(|t| (t, p))
+            })
+            .collect();
+        hits.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        hits
+    }
+}
+
+/////////////////////////////////////
+
+// one piece of a path
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Segment
+{
+    Line(Line),
+    Quad(QuadBezier),
+    Cubic(CubicBezier),
+    Arc(CircularArc),
+}
+
+impl Segment
+{
+    fn green(&self) -> f64
+    {
+        match self {
+            Segment::Line(l) => cross(l.start, l.end) / 2.0,
+            Segment::Quad(q) => green_integral(|t| q.eval(t), |t| q.derivative(t)),
+            Segment::Cubic(c) => green_integral(|t| c.eval(t), |t| c.derivative(t)),
+            Segment::Arc(a) => a.green(),
+        }
+    }
+}
+
+impl Curve for Segment
+{
+    fn start(&self) -> Point
+    {
+        match self {
+            Segment::Line(l) => l.start(),
+            Segment::Quad(q) => q.start(),
+            Segment::Cubic(c) => c.start(),
+            Segment::Arc(a) => a.start(),
+        }
+    }
+
+    fn end(&self) -> Point
+    {
+        match self {
+            Segment::Line(l) => l.end(),
+            Segment::Quad(q) => q.end(),
+            Segment::Cubic(c) => c.end(),
+            Segment::Arc(a) => a.end(),
+        }
+    }
+
+    fn eval(&self, t: f64) -> Point
+    {
+        match self {
+            Segment::Line(l) => l.eval(t),
+            Segment::Quad(q) => q.eval(t),
+            Segment::Cubic(c) => c.eval(t),
+            Segment::Arc(a) => a.eval(t),
+        }
+    }
+
+    fn split(&self, t: f64) -> (Segment, Segment)
+    {
+        match self {
+            Segment::Line(l) => { let (a, b) = l.split(t); (Segment::Line(a), Segment::Line(b)) }
+            Segment::Quad(q) => { let (a, b) = q.split(t); (Segment::Quad(a), Segment::Quad(b)) }
+            Segment::Cubic(c) => { let (a, b) = c.split(t); (Segment::Cubic(a), Segment::Cubic(b)) }
+            Segment::Arc(r) => { let (a, b) = r.split(t); (Segment::Arc(a), Segment::Arc(b)) }
+        }
+    }
+
+    fn arc_length(&self, tolerance: f64) -> f64
+    {
+        match self {
+            Segment::Line(l) => l.arc_length(tolerance),
+            Segment::Quad(q) => q.arc_length(tolerance),
+            Segment::Cubic(c) => c.arc_length(tolerance),
+            Segment::Arc(a) => a.arc_length(tolerance),
+        }
+    }
+
+    fn bounding_box(&self) -> Rect
+    {
+        match self {
+            Segment::Line(l) => l.bounding_box(),
+            Segment::Quad(q) => q.bounding_box(),
+            Segment::Cubic(c) => c.bounding_box(),
+            Segment::Arc(a) => a.bounding_box(),
+        }
+    }
+
+    fn flatten(&self, tolerance: f64) -> Vec<Point>
+    {
+        match self {
+            Segment::Line(l) => l.flatten(tolerance),
+            Segment::Quad(q) => q.flatten(tolerance),
+            Segment::Cubic(c) => c.flatten(tolerance),
+            Segment::Arc(a) => a.flatten(tolerance),
+        }
+    }
+
+    fn intersect_line(&self, line: &Line) -> Vec<(f64, Point)>
+    {
+        match self {
+            Segment::Line(l) => l.intersect_line(line),
+            Segment::Quad(q) => q.intersect_line(line),
+            Segment::Cubic(c) => c.intersect_line(line),
+            Segment::Arc(a) => a.intersect_line(line),
+        }
+    }
+}
+
+/////////////////////////////////////
+
+// connected segments, each one starts where the previous one ended
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path
+{
+    start: Point,
+    segments: Vec<Segment>,
+}
+
+impl Path
+{
+    pub fn new(start: Point) -> Path
+    {
+        Path { start, segments: Vec::new() }
+    }
+
+    // where the next segment will start
+    pub fn current(&self) -> Point
+    {
+        self.segments.last().map_or(self.start, |s| s.end())
+    }
+
+    pub fn line_to(mut self, to: Point) -> Path
+    {
+        self.segments.push(Segment::Line(Line { start: self.current(), end: to }));
+        self
+    }
+
+    pub fn quad_to(mut self, control: Point, to: Point) -> Path
+    {
+        self.segments.push(Segment::Quad(QuadBezier::new(self.current(), control, to)));
+        self
+    }
+
+    pub fn cubic_to(mut self, control1: Point, control2: Point, to: Point) -> Path
+    {
+        self.segments.push(Segment::Cubic(CubicBezier::new(self.current(), control1, control2, to)));
+        self
+    }
+
+    // turns around center by sweep radians, keeping the current distance from it
+    pub fn arc_around(mut self, center: Point, sweep: f64) -> Path
+    {
+        let from = self.current();
+        let radius = distance(center, from);
+        let start_angle = (from.y - center.y).atan2(from.x - center.x);
+        self.segments.push(Segment::Arc(CircularArc::new(center, radius, start_angle, sweep)));
+        self
+    }
+
+    pub fn segments(&self) -> &[Segment] { &self.segments }
+
+    pub fn arc_length(&self, tolerance: f64) -> f64
+    {
+        let per_segment = tolerance / self.segments.len().max(1) as f64;
+        self.segments.iter().map(|s| s.arc_length(per_segment)).sum()
+    }
+
+    pub fn bounding_box(&self) -> Rect
+    {
+        self.segments.iter()
+            .fold(Rect::new(self.start, self.start), |rect, s| rect.union(s.bounding_box()))
+    }
+
+    pub fn flatten(&self, tolerance: f64) -> Vec<Point>
+    {
+        let mut points = vec![self.start];
+        for segment in &self.segments {
+            points.extend(segment.flatten(tolerance).into_iter().skip(1));
+        }
+        points
+    }
+
+    // pairs of (index of the segment, point). a line through the point where
+    // two segments join hits the end of one and the start of the next, only
+    // the segment ending there reports it. the last segment joins the first
+    // when the path ends where it starts
+    pub fn intersect_line(&self, line: &Line) -> Vec<(usize, Point)>
+    {
+        let hits: Vec<Vec<(f64, Point)>> = self.segments.iter().map(|s| s.intersect_line(line)).collect();
+        let bbox = self.bounding_box();
+        let close = T_EPSILON * bbox.width().max(bbox.height()).max(1.0);
+        let closed = distance(self.current(), self.start) <= close;
+        let ends_at = |i: usize, p: Point| {
+            hits[i].iter().any(|&(t, q)| t >= 1.0 - T_EPSILON && distance(p, q) <= close)
+        };
+
+        let mut points = Vec::new();
+        for (i, segment_hits) in hits.iter().enumerate() {
+            let previous = if i > 0 { Some(i - 1) } else if closed { Some(hits.len() - 1) } else { None };
+            for &(t, p) in segment_hits {
+                if t <= T_EPSILON && previous.is_some_and(|j| ends_at(j, p)) { continue; }
+                points.push((i, p));
+            }
+        }
+        points
+    }
+
+    // joins the end back to the start with a straight line if they don't already meet
+    pub fn close(self) -> ClosedPath
+    {
+        let end = self.current();
+        let path = if end == self.start || self.segments.is_empty() {
+            self
+        } else {
+            let start = self.start;
+            self.line_to(start)
+        };
+        // the outline is only used for inside / outside tests, a thousandth
+        // of the path size is far below what anyone can see
+        let bbox = path.bounding_box();
+        let tolerance = 1e-3 * bbox.width().max(bbox.height()).max(f64::MIN_POSITIVE);
+        let outline = path.flatten(tolerance);
+        ClosedPath { path, outline }
+    }
+}
+
+// how many times the polygon winds around p, counter clockwise turns count as positive
+pub fn winding_number(polygon: &[Point], p: Point) -> i32
+{
+    let mut winding = 0;
+    for (i, a) in polygon.iter().enumerate() {
+        let b = polygon[(i + 1) % polygon.len()];
+        let side = cross(sub(b, *a), sub(p, *a));
+        if a.y <= p.y && b.y > p.y && side > 0.0 {
+            winding += 1;
+        } else if a.y > p.y && b.y <= p.y && side < 0.0 {
+            winding -= 1;
+        }
+    }
+    winding
+}
+
+// a path that ends where it starts, so it encloses an area
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClosedPath
+{
+    path: Path,
+    outline: Vec<Point>,
+}
+
+impl ClosedPath
+{
+    pub fn path(&self) -> &Path { &self.path }
+
+    // the path flattened into a polygon
+    pub fn outline(&self) -> &[Point] { &self.outline }
+
+    // positive when the outline goes counter clockwise
+    pub fn signed_area(&self) -> f64
+    {
+        self.path.segments.iter().map(Segment::green).sum()
+    }
+}
+
+impl Shape for ClosedPath
+{
+    fn area(&self) -> f64 { self.signed_area().abs() }
+
+    fn bounding_box(&self) -> Rect { self.path.bounding_box() }
+
+    // nonzero rule, so self intersecting outlines count overlaps once
+    fn contains(&self, p: Point) -> bool { winding_number(&self.outline, p) != 0 }
+}
+
+/////////////////////////////////////
+
+pub fn main()
+{
+    let curve = CubicBezier::new(
+        Point { x: 0.0, y: 0.0 },
+        Point { x: 1.0, y: 2.0 },
+        Point { x: 3.0, y: 2.0 },
+        Point { x: 4.0, y: 0.0 },
+    );
+    println!("middle of the curve: {:?}", curve.eval(0.5));
+    println!("length of the curve: {}", curve.arc_length(1e-6));
+    println!("bounding box: {:?}", curve.bounding_box());
+    println!("flattened into {} points", curve.flatten(0.01).len());
+
+    let horizon = Line { start: Point { x: -1.0, y: 1.0 }, end: Point { x: 5.0, y: 1.0 } };
+    println!("crossings with y = 1: {:?}", curve.intersect_line(&horizon));
+
+    // a unit circle made of a single arc, the area should be pi
+    let circle = Path::new(Point { x: 1.0, y: 0.0 })
+        .arc_around(Point { x: 0.0, y: 0.0 }, 2.0 * PI)
+        .close();
+    println!("circle area: {}", circle.area());
+
+    // a half disc: the arc over the top and the diameter closing it
+    let half = Path::new(Point { x: 1.0, y: 0.0 })
+        .arc_around(Point { x: 0.0, y: 0.0 }, PI)
+        .close();
+    println!("half disc area: {}", half.area());
+
+    let near = |a: Point, b: Point| distance(a, b) < 1e-9;
+
+    // the middle of a quadratic is halfway between the middle control point
+    // and the middle of the ends
+    let arch = QuadBezier::new(Point { x: 0.0, y: 0.0 }, Point { x: 1.0, y: 2.0 }, Point { x: 2.0, y: 0.0 });
+    assert!(near(arch.eval(0.5), Point { x: 1.0, y: 1.0 }));
+    assert!(near(curve.eval(0.5), Point { x: 2.0, y: 1.5 }));
+
+    // the halves of a split meet at the split point and retrace the curve
+    let (left, right) = curve.split(0.3);
+    assert!(near(left.start(), curve.start()) && near(right.end(), curve.end()));
+    assert!(near(left.end(), curve.eval(0.3)) && near(right.start(), curve.eval(0.3)));
+    assert!(near(left.eval(0.5), curve.eval(0.15)) && near(right.eval(0.5), curve.eval(0.65)));
+    let halves = left.arc_length(1e-9) + right.arc_length(1e-9);
+    assert!((halves - curve.arc_length(1e-9)).abs() < 1e-6);
+
+    // a quarter circle is pi r / 2 long, also when it is a cubic approximation
+    let quarter = CircularArc::new(Point { x: 0.0, y: 0.0 }, 2.0, 0.0, FRAC_PI_2);
+    assert!((quarter.arc_length(1e-9) - PI).abs() < 1e-12);
+    let k = 4.0 / 3.0 * (2.0f64.sqrt() - 1.0) * 2.0;
+    let cubic_quarter = CubicBezier::new(
+        Point { x: 2.0, y: 0.0 },
+        Point { x: 2.0, y: k },
+        Point { x: k, y: 2.0 },
+        Point { x: 0.0, y: 2.0 },
+    );
+    assert!((cubic_quarter.arc_length(1e-9) - PI).abs() < 1e-3);
+    // and a path adds up the lengths of its segments
+    let round_trip = Path::new(Point { x: 2.0, y: 0.0 })
+        .cubic_to(Point { x: 2.0, y: k }, Point { x: k, y: 2.0 }, Point { x: 0.0, y: 2.0 })
+        .arc_around(Point { x: 0.0, y: 0.0 }, -FRAC_PI_2);
+    assert_eq!(round_trip.segments()[0], Segment::Cubic(cubic_quarter));
+    assert!((round_trip.arc_length(1e-9) - 2.0 * PI).abs() < 1e-3);
+
+    // areas
+    let square = Path::new(Point { x: 0.0, y: 0.0 })
+        .line_to(Point { x: 1.0, y: 0.0 })
+        .line_to(Point { x: 1.0, y: 1.0 })
+        .line_to(Point { x: 0.0, y: 1.0 })
+        .close();
+    assert_eq!(square.signed_area(), 1.0);
+    assert!((circle.area() - PI).abs() < 1e-9);
+    assert!((half.area() - PI / 2.0).abs() < 1e-9);
+    assert!(circle.contains(Point { x: 0.5, y: 0.5 }) && !circle.contains(Point { x: 0.8, y: 0.8 }));
+
+    // intersections, a crossing at a corner counts once
+    assert_eq!(curve.intersect_line(&horizon).len(), 2);
+    assert_eq!(arch.intersect_line(&Line { start: Point { x: 0.0, y: 2.0 }, end: Point { x: 2.0, y: 2.0 } }).len(), 0);
+    let across = Line { start: Point { x: -1.0, y: 0.5 }, end: Point { x: 2.0, y: 0.5 } };
+    let diagonal = Line { start: Point { x: -1.0, y: -1.0 }, end: Point { x: 2.0, y: 2.0 } };
+    let corner = Line { start: Point { x: -1.0, y: 1.0 }, end: Point { x: 1.0, y: -1.0 } };
+    assert_eq!(square.path().intersect_line(&across).len(), 2);
+    let hits = square.path().intersect_line(&diagonal);
+    assert_eq!(hits.len(), 2, "{:?}", hits);
+    assert!(near(hits[0].1, Point { x: 1.0, y: 1.0 }) && near(hits[1].1, Point { x: 0.0, y: 0.0 }));
+    assert_eq!(square.path().intersect_line(&corner).len(), 1);
+    let through_center = Line { start: Point { x: -2.0, y: 0.0 }, end: Point { x: 2.0, y: 0.0 } };
+    assert_eq!(circle.path().intersect_line(&through_center).len(), 2);
+}
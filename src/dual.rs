@@ -0,0 +1,494 @@
+// dual numbers a + a'e with e^2 = 0, forward mode automatic differentiation
+//
+// f(a + e) = f(a) + f'(a)e, so pushing a + 1e through a function brings its
+// derivative out exactly, not approximated like a finite difference. the
+// arithmetic is generic over the parts like Complex<T>, the functions are
+// written against the Real trait, which Dual<T> implements too: Dual<Dual<f64>>
+// carries second derivatives
+//
+// code to be differentiated is written once for any T: Real and runs on plain
+// f64 as well:
+//   fn cost<T: Real>(x: T) -> T { x * x.sin() + T::constant(3.0) }
+//   let (value, slope) = derivative(cost, 2.0);
+
+use crate::num::{Num, One, Zero};
+
+use std::cmp::Ordering;
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Dual<T>
+{
+    pub value: T,
+    pub deriv: T,
+}
+
+impl<T> Dual<T>
+{
+    pub fn new(value: T, deriv: T) -> Dual<T>
+    {
+        Dual { value, deriv }
+    }
+}
+
+// equal means both parts equal, like is_zero, so a Polynomial<Dual<f64>>
+// doesn't trim away a coefficient whose value is 0 but whose slope isn't.
+// the order looks at the value only, so branches in the differentiated code
+// (if x <= 0, max, clamp) go the same way they would on plain numbers. that
+// makes the two disagree at a tie: 0 + 1e <= 0 but 0 + 1e != 0, branch with
+// <= and >= rather than ==
+impl<T: PartialEq> PartialEq for Dual<T>
+{
+    fn eq(&self, rhs: &Self) -> bool { self.value == rhs.value && self.deriv == rhs.deriv }
+}
+
+impl<T: PartialOrd> PartialOrd for Dual<T>
+{
+    fn partial_cmp(&self, rhs: &Self) -> Option<Ordering> { self.value.partial_cmp(&rhs.value) }
+}
+
+impl<T> Add for Dual<T>
+where T: Add<Output = T>
+{
+    type Output = Dual<T>;
+
+    fn add(self, rhs: Self) -> Self::Output
+    {
+        Dual { value: self.value + rhs.value, deriv: self.deriv + rhs.deriv }
+    }
+}
+
+impl<T> Sub for Dual<T>
+where T: Sub<Output = T>
+{
+    type Output = Dual<T>;
+
+    fn sub(self, rhs: Self) -> Self::Output
+    {
+        Dual { value: self.value - rhs.value, deriv: self.deriv - rhs.deriv }
+    }
+}
+
+impl<T> Neg for Dual<T>
+where T: Neg<Output = T>
+{
+    type Output = Dual<T>;
+
+    fn neg(self) -> Self::Output
+    {
+        Dual { value: -self.value, deriv: -self.deriv }
+    }
+}
+
+// product rule: (a + a'e)(b + b'e) = ab + (a'b + ab')e
+impl<T> Mul for Dual<T>
+where T: Clone + Add<Output = T> + Mul<Output = T>
+{
+    type Output = Dual<T>;
+
+    fn mul(self, rhs: Self) -> Self::Output
+    {
+        let deriv = self.deriv * rhs.value.clone() + self.value.clone() * rhs.deriv;
+        Dual { value: self.value * rhs.value, deriv }
+    }
+}
+
+// quotient rule: (a/b)' = (a'b - ab') / b^2
+impl<T> Div for Dual<T>
+where T: Clone + Sub<Output = T> + Mul<Output = T> + Div<Output = T>
+{
+    type Output = Dual<T>;
+
+    fn div(self, rhs: Self) -> Self::Output
+    {
+        let deriv = (self.deriv * rhs.value.clone() - self.value.clone() * rhs.deriv) / (rhs.value.clone() * rhs.value.clone());
+        Dual { value: self.value / rhs.value, deriv }
+    }
+}
+
+impl<T> AddAssign for Dual<T>
+where T: AddAssign
+{
+    fn add_assign(&mut self, rhs: Self)
+    {
+        self.value += rhs.value;
+        self.deriv += rhs.deriv;
+    }
+}
+
+impl<T> SubAssign for Dual<T>
+where T: SubAssign
+{
+    fn sub_assign(&mut self, rhs: Self)
+    {
+        self.value -= rhs.value;
+        self.deriv -= rhs.deriv;
+    }
+}
+
+impl<T> MulAssign for Dual<T>
+where T: Clone + Add<Output = T> + Mul<Output = T>
+{
+    fn mul_assign(&mut self, rhs: Self)
+    {
+        *self = self.clone() * rhs;
+    }
+}
+
+impl<T> DivAssign for Dual<T>
+where T: Clone + Sub<Output = T> + Mul<Output = T> + Div<Output = T>
+{
+    fn div_assign(&mut self, rhs: Self)
+    {
+        *self = self.clone() / rhs;
+    }
+}
+
+// dual op scalar, the scalar is a constant with no derivative
+
+impl<T> Add<T> for Dual<T>
+where T: Add<Output = T>
+{
+    type Output = Dual<T>;
+
+    fn add(self, rhs: T) -> Self::Output { Dual { value: self.value + rhs, deriv: self.deriv } }
+}
+
+impl<T> Sub<T> for Dual<T>
+where T: Sub<Output = T>
+{
+    type Output = Dual<T>;
+
+    fn sub(self, rhs: T) -> Self::Output { Dual { value: self.value - rhs, deriv: self.deriv } }
+}
+
+impl<T> Mul<T> for Dual<T>
+where T: Clone + Mul<Output = T>
+{
+    type Output = Dual<T>;
+
+    fn mul(self, rhs: T) -> Self::Output { Dual { value: self.value * rhs.clone(), deriv: self.deriv * rhs } }
+}
+
+impl<T> Div<T> for Dual<T>
+where T: Clone + Div<Output = T>
+{
+    type Output = Dual<T>;
+
+    fn div(self, rhs: T) -> Self::Output { Dual { value: self.value / rhs.clone(), deriv: self.deriv / rhs } }
+}
+
+// scalar op dual, spelled out per float type for the same reason as for Complex
+macro_rules! dual_primitive_ops {
+    ($($t:ty),*) => {$(
+        impl Add<Dual<$t>> for $t
+        {
+            type Output = Dual<$t>;
+
+            fn add(self, rhs: Dual<$t>) -> Dual<$t> { rhs + self }
+        }
+
+        impl Sub<Dual<$t>> for $t
+        {
+            type Output = Dual<$t>;
+
+            fn sub(self, rhs: Dual<$t>) -> Dual<$t> { -rhs + self }
+        }
+
+        impl Mul<Dual<$t>> for $t
+        {
+            type Output = Dual<$t>;
+
+            fn mul(self, rhs: Dual<$t>) -> Dual<$t> { rhs * self }
+        }
+
+        impl Div<Dual<$t>> for $t
+        {
+            type Output = Dual<$t>;
+
+            fn div(self, rhs: Dual<$t>) -> Dual<$t> { Dual::new(self, 0.0) / rhs }
+        }
+    )*};
+}
+
+dual_primitive_ops!(f32, f64);
+
+// zero is zero with no slope, the identities are constants
+impl<T: Num> Zero for Dual<T>
+{
+    fn zero() -> Dual<T> { Dual::new(T::zero(), T::zero()) }
+
+    fn is_zero(&self) -> bool { self.value.is_zero() && self.deriv.is_zero() }
+}
+
+impl<T: Num> One for Dual<T>
+{
+    fn one() -> Dual<T> { Dual::new(T::one(), T::zero()) }
+}
+
+/////////////////////////////////////
+
+// the numbers differentiable code is written against: f32, f64 and duals of
+// them. narrower than num::Float, so that Dual<T> can implement it
+pub trait Real: Num + Copy + PartialOrd + Neg<Output = Self>
+{
+    // a constant, its derivative is 0
+    fn constant(x: f64) -> Self;
+
+    fn exp(self) -> Self;
+    fn ln(self) -> Self;
+    fn sqrt(self) -> Self;
+    fn powf(self, n: f64) -> Self;
+    fn powi(self, n: i32) -> Self;
+    fn abs(self) -> Self;
+    fn sin(self) -> Self;
+    fn cos(self) -> Self;
+    fn tan(self) -> Self;
+    fn asin(self) -> Self;
+    fn acos(self) -> Self;
+    fn atan(self) -> Self;
+    fn sinh(self) -> Self;
+    fn cosh(self) -> Self;
+    fn tanh(self) -> Self;
+}
+
+macro_rules! real_float_impl {
+    ($($t:ident),*) => {$(
+        impl Real for $t
+        {
+            fn constant(x: f64) -> $t { x as $t }
+
+            fn exp(self) -> $t { $t::exp(self) }
+            fn ln(self) -> $t { $t::ln(self) }
+            fn sqrt(self) -> $t { $t::sqrt(self) }
+            fn powf(self, n: f64) -> $t { $t::powf(self, n as $t) }
+            fn powi(self, n: i32) -> $t { $t::powi(self, n) }
+            fn abs(self) -> $t { $t::abs(self) }
+            fn sin(self) -> $t { $t::sin(self) }
+            fn cos(self) -> $t { $t::cos(self) }
+            fn tan(self) -> $t { $t::tan(self) }
+            fn asin(self) -> $t { $t::asin(self) }
+            fn acos(self) -> $t { $t::acos(self) }
+            fn atan(self) -> $t { $t::atan(self) }
+            fn sinh(self) -> $t { $t::sinh(self) }
+            fn cosh(self) -> $t { $t::cosh(self) }
+            fn tanh(self) -> $t { $t::tanh(self) }
+        }
+    )*};
+}
+
+real_float_impl!(f32, f64);
+
+// chain rule throughout: f(a + a'e) = f(a) + f'(a) a'e
+impl<T: Real> Real for Dual<T>
+{
+    fn constant(x: f64) -> Dual<T> { Dual::new(T::constant(x), T::constant(0.0)) }
+
+    fn exp(self) -> Dual<T>
+    {
+        let e = self.value.exp();
+        Dual::new(e, self.deriv * e)
+    }
+
+    fn ln(self) -> Dual<T> { Dual::new(self.value.ln(), self.deriv / self.value) }
+
+    fn sqrt(self) -> Dual<T>
+    {
+        let root = self.value.sqrt();
+        Dual::new(root, self.deriv / (root * T::constant(2.0)))
+    }
+
+    // n a^(n - 1) a'
+    fn powf(self, n: f64) -> Dual<T>
+    {
+        Dual::new(self.value.powf(n), self.deriv * self.value.powf(n - 1.0) * T::constant(n))
+    }
+
+    fn powi(self, n: i32) -> Dual<T>
+    {
+        if n == 0 { return Dual::constant(1.0); }
+        Dual::new(self.value.powi(n), self.deriv * self.value.powi(n - 1) * T::constant(n as f64))
+    }
+
+    // the kink at 0 gets the slope from the right
+    fn abs(self) -> Dual<T>
+    {
+        if self.value < T::constant(0.0) { -self } else { self }
+    }
+
+    fn sin(self) -> Dual<T> { Dual::new(self.value.sin(), self.deriv * self.value.cos()) }
+
+    fn cos(self) -> Dual<T> { Dual::new(self.value.cos(), -(self.deriv * self.value.sin())) }
+
+    // tan' = 1 + tan^2, which needs no cos that could be 0
+    fn tan(self) -> Dual<T>
+    {
+        let t = self.value.tan();
+        Dual::new(t, self.deriv * (t * t + T::constant(1.0)))
+    }
+
+    fn asin(self) -> Dual<T>
+    {
+        let one = T::constant(1.0);
+        Dual::new(self.value.asin(), self.deriv / (one - self.value * self.value).sqrt())
+    }
+
+    fn acos(self) -> Dual<T>
+    {
+        let one = T::constant(1.0);
+        Dual::new(self.value.acos(), -(self.deriv / (one - self.value * self.value).sqrt()))
+    }
+
+    fn atan(self) -> Dual<T>
+    {
+        let one = T::constant(1.0);
+        Dual::new(self.value.atan(), self.deriv / (one + self.value * self.value))
+    }
+
+    fn sinh(self) -> Dual<T> { Dual::new(self.value.sinh(), self.deriv * self.value.cosh()) }
+
+    fn cosh(self) -> Dual<T> { Dual::new(self.value.cosh(), self.deriv * self.value.sinh()) }
+
+    fn tanh(self) -> Dual<T>
+    {
+        let t = self.value.tanh();
+        Dual::new(t, self.deriv * (T::constant(1.0) - t * t))
+    }
+}
+
+impl<T: Real> Dual<T>
+{
+    // x with dx/dx = 1, the input a derivative is taken with respect to
+    pub fn variable(x: T) -> Dual<T> { Dual::new(x, T::constant(1.0)) }
+
+    // a^b for a dual exponent too: e^(b ln a)
+    pub fn pow(self, b: Dual<T>) -> Dual<T> { (b * self.ln()).exp() }
+}
+
+/////////////////////////////////////
+
+// (f(x), f'(x))
+pub fn derivative<F>(f: F, x: f64) -> (f64, f64)
+where F: Fn(Dual<f64>) -> Dual<f64>
+{
+    let y = f(Dual::variable(x));
+    (y.value, y.deriv)
+}
+
+// (f(x), f'(x), f''(x)): the inner dual differentiates once, the outer one
+// differentiates that again
+pub fn second_derivative<F>(f: F, x: f64) -> (f64, f64, f64)
+where F: Fn(Dual<Dual<f64>>) -> Dual<Dual<f64>>
+{
+    let y = f(Dual::new(Dual::variable(x), Dual::constant(1.0)));
+    (y.value.value, y.value.deriv, y.deriv.deriv)
+}
+
+// (f(x), gradient of f at x), one pass through f per variable: every pass
+// seeds a different input with derivative 1
+pub fn gradient<F>(f: F, x: &[f64]) -> (f64, Vec<f64>)
+where F: Fn(&[Dual<f64>]) -> Dual<f64>
+{
+    let mut value = 0.0;
+    let mut inputs: Vec<Dual<f64>> = x.iter().map(|&v| Dual::constant(v)).collect();
+    let grad = (0..x.len())
+        .map(|i| {
+            inputs[i].deriv = 1.0;
+            let y = f(&inputs);
+            inputs[i].deriv = 0.0;
+            value = y.value;
+            y.deriv
+        })
+        .collect();
+    if x.is_empty() { value = f(&inputs).value; }
+    (value, grad)
+}
+
+/////////////////////////////////////
+
+// a cost function written once for any Real
+fn rosenbrock<T: Real>(p: &[T]) -> T
+{
+    let (a, b) = (T::constant(1.0), T::constant(100.0));
+    let (x, y) = (p[0], p[1]);
+    (a - x) * (a - x) + b * (y - x * x) * (y - x * x)
+}
+
+fn bell<T: Real>(x: T) -> T
+{
+    (-(x * x) / T::constant(2.0)).exp() / T::constant((2.0 * std::f64::consts::PI).sqrt())
+}
+
+pub fn main()
+{
+    // d/dx x^2 sin x = 2x sin x + x^2 cos x
+    let (y, dy) = derivative(|x| x * x * x.sin(), 2.0);
+    println!("f(2) = {}, f'(2) = {}", y, dy);
+    assert!((dy - (4.0 * 2f64.sin() + 4.0 * 2f64.cos())).abs() < 1e-14);
+
+    // the same generic function on plain numbers and on duals
+    let (y, dy) = derivative(bell, 1.0);
+    assert!((y - bell(1.0)).abs() < 1e-16 && (dy + bell(1.0)).abs() < 1e-15);
+
+    // against central differences for every function
+    type Case = (&'static str, fn(Dual<f64>) -> Dual<f64>, fn(f64) -> f64);
+    let functions: Vec<Case> = vec![
+        ("exp", |x| x.exp(), f64::exp),
+        ("ln", |x| x.ln(), f64::ln),
+        ("sqrt", |x| x.sqrt(), f64::sqrt),
+        ("powf", |x| x.powf(2.7), |x| x.powf(2.7)),
+        ("powi", |x| x.powi(-3), |x| x.powi(-3)),
+        ("pow", |x| x.pow(x), |x| x.powf(x)),
+        ("sin", |x| x.sin(), f64::sin),
+        ("cos", |x| x.cos(), f64::cos),
+        ("tan", |x| x.tan(), f64::tan),
+        ("asin", |x| (x / 2.0).asin(), |x| (x / 2.0).asin()),
+        ("acos", |x| (x / 2.0).acos(), |x| (x / 2.0).acos()),
+        ("atan", |x| x.atan(), f64::atan),
+        ("tanh", |x| x.tanh(), f64::tanh),
+        ("quotient", |x| (x.sinh() + 1.0) / (x.cosh() * x - 2.0), |x| (x.sinh() + 1.0) / (x.cosh() * x - 2.0)),
+    ];
+    for (name, f, plain) in functions.iter() {
+        for x in [0.3, 0.9, 1.7] {
+            let (y, dy) = derivative(f, x);
+            let h = 1e-6;
+            let numeric = (plain(x + h) - plain(x - h)) / (2.0 * h);
+            assert!((y - plain(x)).abs() < 1e-12, "{} value at {}", name, x);
+            assert!((dy - numeric).abs() < 1e-6 * (1.0 + numeric.abs()), "{}'({}) = {} vs {}", name, x, dy, numeric);
+        }
+    }
+
+    // == and is_zero look at both parts, < at the value
+    let slope = Dual::new(0.0, 1.0);
+    assert!(slope != Dual::zero() && !slope.is_zero() && Dual::new(0.0, 0.0) == Dual::zero());
+    assert!(slope < Dual::new(0.5, -3.0) && slope > Dual::new(-0.5, 3.0));
+    assert_eq!(slope.partial_cmp(&Dual::new(0.0, 2.0)), Some(Ordering::Equal));
+    assert!(slope <= Dual::zero() && slope >= Dual::zero());
+    // so a branch on a tie goes the plain number's way
+    fn step<T: Real>(x: T) -> T { if x <= T::constant(0.0) { T::constant(0.0) } else { T::constant(1.0) } }
+    assert_eq!(derivative(step, 0.0).0, step(0.0));
+    assert_eq!(derivative(step, 1e-9), (1.0, 0.0));
+    assert_eq!(derivative(|x| x.abs(), 0.0), (0.0, 1.0));
+    // so a coefficient that is 0 now but moves with x stays in a polynomial
+    let p = crate::polynomial::Polynomial::new(vec![Dual::constant(1.0), slope]);
+    assert_eq!(p.degree(), Some(1));
+
+    // sin'' = -sin
+    let (_, _, d2) = second_derivative(|x| x.sin(), 1.0);
+    assert!((d2 + 1f64.sin()).abs() < 1e-15);
+
+    // the rosenbrock valley has its minimum at (1, 1)
+    let (value, grad) = gradient(rosenbrock, &[1.0, 1.0]);
+    assert!(value == 0.0 && grad == vec![0.0, 0.0]);
+    let (_, grad) = gradient(rosenbrock, &[0.0, 0.0]);
+    assert_eq!(grad, vec![-2.0, 0.0]);
+
+    // newton's method with the derivative for free: the root of x^3 - 2x - 5
+    let mut x = 2.0;
+    for _ in 0..6 {
+        let (y, dy) = derivative(|x| x.powi(3) - x * 2.0 - 5.0, x);
+        x -= y / dy;
+    }
+    println!("root of x^3 - 2x - 5: {}", x);
+    assert!((x * x * x - 2.0 * x - 5.0).abs() < 1e-12);
+}
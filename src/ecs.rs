@@ -0,0 +1,368 @@
+// a small entity component system
+//
+// an Entity is an index plus a generation. despawning bumps the generation of
+// the index, so a handle kept around after its entity died stops matching
+// anything, even once the index is reused
+//
+// components are plain values, one column per type: Vec<Option<T>> indexed by
+// the entity index. a query names the components it wants as a tuple,
+//
+//   world.each::<(&mut Position, &Velocity)>(|entity, (p, v)| ...)
+//
+// and runs for every entity that has all of them. Option<&T> matches entities
+// with or without a T. each column sits in a RefCell, asking for the same type
+// twice in one query (&mut T and &T) panics like a double borrow does
+//
+// a Schedule holds the systems, closures over the world, and runs them in the
+// order they were added, once per tick
+
+use std::any::{Any, TypeId};
+use std::cell::{Ref, RefCell, RefMut};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Entity
+{
+    pub index: u32,
+    pub generation: u32,
+}
+
+// the storage of one component type, it shows up in Query's types but is
+// only read through the world
+pub struct Column<T>
+{
+    slots: Vec<Option<T>>,
+}
+
+// what the world needs from a column without knowing its type
+trait AnyColumn
+{
+    fn remove(&mut self, index: usize);
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: 'static> AnyColumn for Column<T>
+{
+    fn remove(&mut self, index: usize)
+    {
+        if let Some(slot) = self.slots.get_mut(index) { *slot = None; }
+    }
+
+    fn as_any(&self) -> &dyn Any { self }
+    fn as_any_mut(&mut self) -> &mut dyn Any { self }
+}
+
+#[derive(Default)]
+pub struct World
+{
+    generations: Vec<u32>,
+    alive: Vec<bool>,
+    free: Vec<u32>,
+    columns: HashMap<TypeId, RefCell<Box<dyn AnyColumn>>>,
+}
+
+impl World
+{
+    pub fn new() -> World { World::default() }
+
+    pub fn spawn(&mut self) -> Entity
+    {
+        match self.free.pop() {
+            Some(index) => {
+                self.alive[index as usize] = true;
+                Entity { index, generation: self.generations[index as usize] }
+            }
+            None => {
+                let index = u32::try_from(self.generations.len()).expect("too many entities");
+                self.generations.push(0);
+                self.alive.push(true);
+                Entity { index, generation: 0 }
+            }
+        }
+    }
+
+    pub fn is_alive(&self, entity: Entity) -> bool
+    {
+        let i = entity.index as usize;
+        i < self.alive.len() && self.alive[i] && self.generations[i] == entity.generation
+    }
+
+    // drops all components of the entity, false if it was already gone
+    pub fn despawn(&mut self, entity: Entity) -> bool
+    {
+        if !self.is_alive(entity) { return false; }
+        let i = entity.index as usize;
+        for column in self.columns.values_mut() { column.get_mut().remove(i); }
+        self.alive[i] = false;
+        self.generations[i] += 1;
+        self.free.push(entity.index);
+        true
+    }
+
+    // every live entity, by index
+    pub fn entities(&self) -> impl Iterator<Item = Entity> + '_
+    {
+        (0..self.alive.len())
+            .filter(|&i| self.alive[i])
+            .map(|i| Entity { index: i as u32, generation: self.generations[i] })
+    }
+
+    fn column<T: 'static>(&self) -> Option<&RefCell<Box<dyn AnyColumn>>> { self.columns.get(&TypeId::of::<T>()) }
+
+    fn column_mut<T: 'static>(&mut self) -> &mut Column<T>
+    {
+        let column = self
+            .columns
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| RefCell::new(Box::new(Column::<T> { slots: vec![] })));
+        column.get_mut().as_any_mut().downcast_mut().unwrap()
+    }
+
+    // the old component of the type, if there was one. panics on a dead entity
+    pub fn insert<T: 'static>(&mut self, entity: Entity, component: T) -> Option<T>
+    {
+        assert!(self.is_alive(entity), "{:?} doesn't exist", entity);
+        let slots = &mut self.column_mut::<T>().slots;
+        let i = entity.index as usize;
+        if slots.len() <= i { slots.resize_with(i + 1, || None); }
+        slots[i].replace(component)
+    }
+
+    pub fn remove<T: 'static>(&mut self, entity: Entity) -> Option<T>
+    {
+        if !self.is_alive(entity) { return None; }
+        self.column_mut::<T>().slots.get_mut(entity.index as usize)?.take()
+    }
+
+    pub fn get<T: 'static>(&self, entity: Entity) -> Option<Ref<'_, T>>
+    {
+        if !self.is_alive(entity) { return None; }
+        let column = <&T as Query>::borrow(self)?;
+        Ref::filter_map(column, |c| c.slots.get(entity.index as usize)?.as_ref()).ok()
+    }
+
+    pub fn get_mut<T: 'static>(&mut self, entity: Entity) -> Option<&mut T>
+    {
+        if !self.is_alive(entity) { return None; }
+        self.column_mut::<T>().slots.get_mut(entity.index as usize)?.as_mut()
+    }
+
+    pub fn has<T: 'static>(&self, entity: Entity) -> bool { self.get::<T>(entity).is_some() }
+
+    // f for every live entity with all the components of Q
+    pub fn each<Q: Query>(&self, mut f: impl FnMut(Entity, Q::Item<'_>))
+    {
+        let mut guard = match Q::borrow(self) {
+            Some(guard) => guard,
+            // some column nobody ever inserted into, nothing can match
+            None => return,
+        };
+        for entity in self.entities() {
+            if let Some(item) = Q::fetch(&mut guard, entity.index as usize) { f(entity, item); }
+        }
+    }
+
+    // the entities the query matches, for changes each can't make (despawns)
+    pub fn matching<Q: Query>(&self) -> Vec<Entity>
+    {
+        let mut found = vec![];
+        self.each::<Q>(|entity, _| found.push(entity));
+        found
+    }
+}
+
+/////////////////////////////////////
+
+// what can appear in a query: &T, &mut T, Option<&T> and tuples of those
+pub trait Query
+{
+    // the borrowed columns, held for the whole query
+    type Guard<'w>;
+    type Item<'g>;
+
+    // None when a column the query needs doesn't exist
+    fn borrow(world: &World) -> Option<Self::Guard<'_>>;
+    fn fetch<'g>(guard: &'g mut Self::Guard<'_>, index: usize) -> Option<Self::Item<'g>>;
+}
+
+impl<T: 'static> Query for &T
+{
+    type Guard<'w> = Ref<'w, Column<T>>;
+    type Item<'g> = &'g T;
+
+    fn borrow(world: &World) -> Option<Ref<'_, Column<T>>>
+    {
+        let column = world.column::<T>()?;
+        Some(Ref::map(column.borrow(), |c| c.as_any().downcast_ref().unwrap()))
+    }
+
+    fn fetch<'g>(guard: &'g mut Ref<'_, Column<T>>, index: usize) -> Option<&'g T>
+    {
+        guard.slots.get(index)?.as_ref()
+    }
+}
+
+impl<T: 'static> Query for &mut T
+{
+    type Guard<'w> = RefMut<'w, Column<T>>;
+    type Item<'g> = &'g mut T;
+
+    fn borrow(world: &World) -> Option<RefMut<'_, Column<T>>>
+    {
+        let column = world.column::<T>()?;
+        Some(RefMut::map(column.borrow_mut(), |c| c.as_any_mut().downcast_mut().unwrap()))
+    }
+
+    fn fetch<'g>(guard: &'g mut RefMut<'_, Column<T>>, index: usize) -> Option<&'g mut T>
+    {
+        guard.slots.get_mut(index)?.as_mut()
+    }
+}
+
+impl<T: 'static> Query for Option<&T>
+{
+    type Guard<'w> = Option<Ref<'w, Column<T>>>;
+    type Item<'g> = Option<&'g T>;
+
+    // never None, a missing column is the same as no entity having a T
+    fn borrow(world: &World) -> Option<Option<Ref<'_, Column<T>>>> { Some(<&T as Query>::borrow(world)) }
+
+    fn fetch<'g>(guard: &'g mut Option<Ref<'_, Column<T>>>, index: usize) -> Option<Option<&'g T>>
+    {
+        Some(guard.as_ref().and_then(|c| c.slots.get(index)?.as_ref()))
+    }
+}
+
+macro_rules! query_tuple {
+    ($($q:ident $i:tt),*) => {
+        impl<$($q: Query),*> Query for ($($q,)*)
+        {
+            type Guard<'w> = ($($q::Guard<'w>,)*);
+            type Item<'g> = ($($q::Item<'g>,)*);
+
+            fn borrow(world: &World) -> Option<Self::Guard<'_>> { Some(($($q::borrow(world)?,)*)) }
+
+            fn fetch<'g>(guard: &'g mut Self::Guard<'_>, index: usize) -> Option<Self::Item<'g>>
+            {
+                Some(($($q::fetch(&mut guard.$i, index)?,)*))
+            }
+        }
+    };
+}
+
+query_tuple!(A 0);
+query_tuple!(A 0, B 1);
+query_tuple!(A 0, B 1, C 2);
+query_tuple!(A 0, B 1, C 2, D 3);
+
+/////////////////////////////////////
+
+pub type System = Box<dyn FnMut(&mut World)>;
+
+#[derive(Default)]
+pub struct Schedule
+{
+    systems: Vec<(&'static str, System)>,
+    pub ticks: u64,
+}
+
+impl Schedule
+{
+    pub fn new() -> Schedule { Schedule::default() }
+
+    // runs after the ones added before it
+    pub fn add(&mut self, name: &'static str, system: impl FnMut(&mut World) + 'static) -> &mut Schedule
+    {
+        self.systems.push((name, Box::new(system)));
+        self
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ { self.systems.iter().map(|&(name, _)| name) }
+
+    pub fn tick(&mut self, world: &mut World)
+    {
+        for (_, system) in &mut self.systems { system(world); }
+        self.ticks += 1;
+    }
+}
+
+/////////////////////////////////////
+
+pub fn main()
+{
+    use crate::combat::Creature;
+    use std::rc::Rc;
+
+    struct Name(&'static str);
+    // what the animal says, the ones without it can't talk
+    struct Speech(&'static str);
+    struct Health(i32);
+    struct Poison(i32);
+
+    // the talk demo: species are just which components an entity has
+    let mut world = World::new();
+    for (name, speech) in [("John", Some("hello")), ("Misty", Some("meow")), ("Rock", None)] {
+        let e = world.spawn();
+        world.insert(e, Name(name));
+        if let Some(speech) = speech { world.insert(e, Speech(speech)); }
+    }
+    let said = Rc::new(RefCell::new(vec![]));
+    let mut schedule = Schedule::new();
+    let log = said.clone();
+    schedule.add("talk", move |world| {
+        world.each::<(&Name, Option<&Speech>)>(|_, (name, speech)| {
+            let line = match speech {
+                Some(Speech(s)) => format!("{} says {}", name.0, s),
+                None => format!("{} cannot talk", name.0),
+            };
+            println!("{}", line);
+            log.borrow_mut().push(line);
+        });
+    });
+    schedule.tick(&mut world);
+    assert_eq!(*said.borrow(), ["John says hello", "Misty says meow", "Rock cannot talk"]);
+
+    // the drop demo: a creature is a component, despawning its entity drops it
+    let mut world = World::new();
+    let goblin = world.spawn();
+    world.insert(goblin, Creature::new("Goblin", 10, 5));
+    world.insert(goblin, Health(9));
+    world.insert(goblin, Poison(4));
+    let troll = world.spawn();
+    world.insert(troll, Creature::new("Troll", 18, 8));
+    world.insert(troll, Health(30));
+
+    let mut schedule = Schedule::new();
+    schedule
+        .add("poison", |world| world.each::<(&mut Health, &Poison)>(|_, (h, p)| h.0 -= p.0))
+        .add("death", |world| {
+            let dead: Vec<Entity> = world.matching::<(&Health,)>().into_iter().filter(|&e| world.get::<Health>(e).unwrap().0 <= 0).collect();
+            for e in dead { world.despawn(e); }
+        });
+    assert_eq!(schedule.names().collect::<Vec<_>>(), ["poison", "death"]);
+
+    // 9 - 4 - 4 still standing, the third tick kills the goblin
+    schedule.tick(&mut world);
+    schedule.tick(&mut world);
+    assert!(world.is_alive(goblin) && world.get::<Health>(goblin).unwrap().0 == 1);
+    schedule.tick(&mut world);
+    assert!(!world.is_alive(goblin) && world.get::<Creature>(goblin).is_none());
+    assert_eq!(world.get::<Creature>(troll).unwrap().name, "Troll");
+
+    // the goblin's index comes back with a new generation, the old handle stays dead
+    let imp = world.spawn();
+    assert_eq!((imp.index, imp.generation), (goblin.index, goblin.generation + 1));
+    assert!(world.get::<Health>(imp).is_none() && !world.despawn(goblin));
+    world.insert(imp, Health(3));
+    assert!(world.get::<Health>(goblin).is_none());
+
+    // changes through get_mut, remove gives the component back
+    world.get_mut::<Health>(troll).unwrap().0 += 10;
+    assert_eq!(world.remove::<Health>(troll).map(|h| h.0), Some(40));
+    assert!(!world.has::<Health>(troll) && world.has::<Creature>(troll));
+    let mut count = 0;
+    world.each::<(&Health, Option<&Creature>)>(|e, (_, creature)| { assert!(e == imp && creature.is_none()); count += 1; });
+    assert_eq!(count, 1);
+    world.each::<(&Speech,)>(|_, _| unreachable!());
+}
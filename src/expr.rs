@@ -0,0 +1,629 @@
+// formulas over complex numbers at runtime: "sqrt(-4) * (2+3i) / x"
+//
+// text -> tokens -> tree -> value. the parser is a pratt parser, each infix
+// operator has a left and a right binding power, loosest first:
+//
+//   + -      left associative
+//   * /      left associative
+//   - +      prefix, so -x * y is (-x) * y
+//   ^        right associative and tighter than prefix minus: -2^2 = -4
+//
+// numbers may carry an i or j suffix to be imaginary (3i, 2.5e-3j), i alone
+// is the imaginary unit. names are variables looked up in an Environment,
+// names followed by ( are calls of the built in functions below. there is no
+// implicit multiplication, 2x is an error
+//
+// every error carries the span of the input it's about, render() underlines
+// it for a terminal. a line "name = formula" assigns, that's what repl() runs
+
+use crate::Complex;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+
+type C64 = Complex<f64>;
+
+// byte offsets start..end into the source
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span
+{
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span
+{
+    // from the start of self to the end of other
+    fn to(self, other: Span) -> Span { Span { start: self.start, end: other.end } }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ErrorKind
+{
+    UnexpectedCharacter(char),
+    InvalidNumber,
+    ExpectedExpression,
+    ExpectedOperator,
+    UnbalancedParenthesis,
+    UnknownVariable(String),
+    UnknownFunction(String),
+    WrongArgumentCount { function: String, expected: usize, found: usize },
+    DivisionByZero,
+    // more than MAX_DEPTH parentheses, signs or powers inside each other
+    TooDeep,
+}
+
+impl fmt::Display for ErrorKind
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self {
+            ErrorKind::UnexpectedCharacter(c) => write!(f, "unexpected character {:?}", c),
+            ErrorKind::InvalidNumber => write!(f, "invalid number"),
+            ErrorKind::ExpectedExpression => write!(f, "expected an expression"),
+            ErrorKind::ExpectedOperator => write!(f, "expected an operator"),
+            ErrorKind::UnbalancedParenthesis => write!(f, "unbalanced parenthesis"),
+            ErrorKind::UnknownVariable(name) => write!(f, "unknown variable {}", name),
+            ErrorKind::UnknownFunction(name) => write!(f, "unknown function {}", name),
+            ErrorKind::WrongArgumentCount { function, expected, found } => {
+                write!(f, "{} takes {} argument{}, got {}", function, expected, if *expected == 1 { "" } else { "s" }, found)
+            }
+            ErrorKind::DivisionByZero => write!(f, "division by zero"),
+            ErrorKind::TooDeep => write!(f, "nested more than {} deep", MAX_DEPTH),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExprError
+{
+    pub kind: ErrorKind,
+    pub span: Span,
+}
+
+impl ExprError
+{
+    // the source with a row of carets under the span and the message:
+    //
+    //   1 + y
+    //       ^ unknown variable y
+    pub fn render(&self, source: &str) -> String
+    {
+        // spans are bytes, the terminal wants characters
+        let pad = source[..self.span.start].chars().count();
+        let width = source[self.span.start..self.span.end].chars().count().max(1);
+        format!("{}\n{}{} {}", source, " ".repeat(pad), "^".repeat(width), self.kind)
+    }
+}
+
+impl fmt::Display for ExprError
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        write!(f, "{} at {}..{}", self.kind, self.span.start, self.span.end)
+    }
+}
+
+impl std::error::Error for ExprError {}
+
+fn error<T>(kind: ErrorKind, span: Span) -> Result<T, ExprError> { Err(ExprError { kind, span }) }
+
+/////////////////////////////////////
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token
+{
+    Number(C64),
+    Name(String),
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Caret,
+    Open,
+    Close,
+    Comma,
+    Equals,
+    End,
+}
+
+fn is_name_start(c: char) -> bool { c.is_alphabetic() || c == '_' }
+
+fn is_name_char(c: char) -> bool { c.is_alphanumeric() || c == '_' }
+
+// the tokens with their spans, always ending with End
+fn tokenize(source: &str) -> Result<Vec<(Token, Span)>, ExprError>
+{
+    let bytes = source.as_bytes();
+    let mut tokens = vec![];
+    let mut chars = source.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        let single = match c {
+            '+' => Some(Token::Plus),
+            '-' => Some(Token::Minus),
+            '*' => Some(Token::Star),
+            '/' => Some(Token::Slash),
+            '^' => Some(Token::Caret),
+            '(' => Some(Token::Open),
+            ')' => Some(Token::Close),
+            ',' => Some(Token::Comma),
+            '=' => Some(Token::Equals),
+            _ => None,
+        };
+        if let Some(token) = single {
+            tokens.push((token, Span { start, end: start + 1 }));
+            continue;
+        }
+        if c.is_whitespace() { continue; }
+
+        if c.is_ascii_digit() || c == '.' {
+            // digits, a fraction, an exponent if digits follow the e
+            let mut end = start;
+            while end < bytes.len() && (bytes[end].is_ascii_digit() || bytes[end] == b'.') { end += 1; }
+            if end < bytes.len() && (bytes[end] == b'e' || bytes[end] == b'E') {
+                let mut e = end + 1;
+                if e < bytes.len() && (bytes[e] == b'+' || bytes[e] == b'-') { e += 1; }
+                if e < bytes.len() && bytes[e].is_ascii_digit() {
+                    while e < bytes.len() && bytes[e].is_ascii_digit() { e += 1; }
+                    end = e;
+                }
+            }
+            let value: f64 = match source[start..end].parse() {
+                Ok(value) => value,
+                Err(_) => return error(ErrorKind::InvalidNumber, Span { start, end }),
+            };
+
+            // 3i is imaginary, 3in is 3 followed by the name in
+            let suffix = end < bytes.len() && (bytes[end] == b'i' || bytes[end] == b'j');
+            let imaginary = suffix && !source[end + 1..].chars().next().is_some_and(is_name_char);
+            let number = if imaginary { end += 1; Complex::new(0.0, value) } else { Complex::new(value, 0.0) };
+
+            while chars.peek().is_some_and(|&(i, _)| i < end) { chars.next(); }
+            tokens.push((Token::Number(number), Span { start, end }));
+            continue;
+        }
+
+        if is_name_start(c) {
+            let mut end = start + c.len_utf8();
+            while let Some(&(i, c)) = chars.peek() {
+                if !is_name_char(c) { break; }
+                end = i + c.len_utf8();
+                chars.next();
+            }
+            let name = &source[start..end];
+            let token = match name {
+                "i" | "j" => Token::Number(C64::I),
+                _ => Token::Name(name.to_string()),
+            };
+            tokens.push((token, Span { start, end }));
+            continue;
+        }
+
+        return error(ErrorKind::UnexpectedCharacter(c), Span { start, end: start + c.len_utf8() });
+    }
+
+    tokens.push((Token::End, Span { start: source.len(), end: source.len() }));
+    Ok(tokens)
+}
+
+/////////////////////////////////////
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp
+{
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExprKind
+{
+    Number(C64),
+    Variable(String),
+    Negate(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+// a node of the tree and the part of the source it came from
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr
+{
+    pub kind: ExprKind,
+    pub span: Span,
+}
+
+// (left, right) binding powers. left < right is left associative
+fn binding_power(op: BinaryOp) -> (u8, u8)
+{
+    match op {
+        BinaryOp::Add | BinaryOp::Sub => (1, 2),
+        BinaryOp::Mul | BinaryOp::Div => (3, 4),
+        BinaryOp::Pow => (8, 7),
+    }
+}
+
+// between * and ^
+const PREFIX_POWER: u8 = 5;
+
+// the parser recurses once per level, a formula typed in must not be able to
+// run it out of stack
+pub const MAX_DEPTH: usize = 256;
+
+struct Parser
+{
+    tokens: Vec<(Token, Span)>,
+    at: usize,
+    depth: usize,
+}
+
+impl Parser
+{
+    fn peek(&self) -> &(Token, Span) { &self.tokens[self.at] }
+
+    fn next(&mut self) -> (Token, Span)
+    {
+        let token = self.tokens[self.at].clone();
+        // End stays put
+        if self.at + 1 < self.tokens.len() { self.at += 1; }
+        token
+    }
+
+    fn expression(&mut self, min_power: u8) -> Result<Expr, ExprError>
+    {
+        if self.depth == MAX_DEPTH { return error(ErrorKind::TooDeep, self.peek().1); }
+        self.depth += 1;
+        let expr = self.operators(min_power);
+        self.depth -= 1;
+        expr
+    }
+
+    fn operators(&mut self, min_power: u8) -> Result<Expr, ExprError>
+    {
+        let mut lhs = self.prefix()?;
+        loop {
+            let op = match self.peek().0 {
+                Token::Plus => BinaryOp::Add,
+                Token::Minus => BinaryOp::Sub,
+                Token::Star => BinaryOp::Mul,
+                Token::Slash => BinaryOp::Div,
+                Token::Caret => BinaryOp::Pow,
+                _ => break,
+            };
+            let (left, right) = binding_power(op);
+            if left < min_power { break; }
+            self.next();
+            let rhs = self.expression(right)?;
+            let span = lhs.span.to(rhs.span);
+            lhs = Expr { kind: ExprKind::Binary(op, Box::new(lhs), Box::new(rhs)), span };
+        }
+        Ok(lhs)
+    }
+
+    fn prefix(&mut self) -> Result<Expr, ExprError>
+    {
+        let (token, span) = self.next();
+        match token {
+            Token::Number(z) => Ok(Expr { kind: ExprKind::Number(z), span }),
+            Token::Minus | Token::Plus => {
+                let operand = self.expression(PREFIX_POWER)?;
+                let span = span.to(operand.span);
+                if token == Token::Plus { return Ok(Expr { span, ..operand }); }
+                Ok(Expr { kind: ExprKind::Negate(Box::new(operand)), span })
+            }
+            Token::Open => {
+                let inner = self.expression(0)?;
+                match self.next() {
+                    (Token::Close, close) => Ok(Expr { span: span.to(close), ..inner }),
+                    // the ( that never closes
+                    (Token::End, _) => error(ErrorKind::UnbalancedParenthesis, span),
+                    (_, other) => error(ErrorKind::ExpectedOperator, other),
+                }
+            }
+            Token::Name(name) if self.peek().0 == Token::Open => {
+                let open = self.next().1;
+                let mut args = vec![];
+                if self.peek().0 != Token::Close {
+                    loop {
+                        args.push(self.expression(0)?);
+                        if self.peek().0 != Token::Comma { break; }
+                        self.next();
+                    }
+                }
+                match self.next() {
+                    (Token::Close, close) => Ok(Expr { kind: ExprKind::Call(name, args), span: span.to(close) }),
+                    (Token::End, _) => error(ErrorKind::UnbalancedParenthesis, open),
+                    (_, other) => error(ErrorKind::ExpectedOperator, other),
+                }
+            }
+            Token::Name(name) => Ok(Expr { kind: ExprKind::Variable(name), span }),
+            Token::Close => error(ErrorKind::UnbalancedParenthesis, span),
+            _ => error(ErrorKind::ExpectedExpression, span),
+        }
+    }
+
+    // after a complete expression only the end may follow
+    fn finish(&mut self) -> Result<(), ExprError>
+    {
+        match self.next() {
+            (Token::End, _) => Ok(()),
+            (Token::Close, span) => error(ErrorKind::UnbalancedParenthesis, span),
+            (_, span) => error(ErrorKind::ExpectedOperator, span),
+        }
+    }
+}
+
+pub fn parse(source: &str) -> Result<Expr, ExprError>
+{
+    let mut parser = Parser { tokens: tokenize(source)?, at: 0, depth: 0 };
+    let expr = parser.expression(0)?;
+    parser.finish()?;
+    Ok(expr)
+}
+
+// "name = formula" or just a formula
+fn parse_line(source: &str) -> Result<(Option<String>, Expr), ExprError>
+{
+    let tokens = tokenize(source)?;
+    let target = match (&tokens[0].0, tokens.get(1).map(|t| &t.0)) {
+        (Token::Name(name), Some(Token::Equals)) => Some(name.clone()),
+        _ => None,
+    };
+    let at = if target.is_some() { 2 } else { 0 };
+    let mut parser = Parser { tokens, at, depth: 0 };
+    let expr = parser.expression(0)?;
+    parser.finish()?;
+    Ok((target, expr))
+}
+
+/////////////////////////////////////
+
+#[derive(Clone, Copy)]
+enum Builtin
+{
+    Unary(fn(C64) -> C64),
+    Binary(fn(C64, C64) -> C64),
+}
+
+const FUNCTIONS: &[(&str, Builtin)] = &[
+    ("sqrt", Builtin::Unary(C64::sqrt)),
+    ("exp", Builtin::Unary(C64::exp)),
+    ("ln", Builtin::Unary(C64::ln)),
+    ("sin", Builtin::Unary(C64::sin)),
+    ("cos", Builtin::Unary(C64::cos)),
+    ("tan", Builtin::Unary(C64::tan)),
+    ("asin", Builtin::Unary(C64::asin)),
+    ("acos", Builtin::Unary(C64::acos)),
+    ("atan", Builtin::Unary(C64::atan)),
+    ("sinh", Builtin::Unary(C64::sinh)),
+    ("cosh", Builtin::Unary(C64::cosh)),
+    ("tanh", Builtin::Unary(C64::tanh)),
+    ("asinh", Builtin::Unary(C64::asinh)),
+    ("acosh", Builtin::Unary(C64::acosh)),
+    ("atanh", Builtin::Unary(C64::atanh)),
+    ("conj", Builtin::Unary(C64::conj)),
+    ("inv", Builtin::Unary(C64::inv)),
+    // the real valued ones come back as complex numbers on the real axis
+    ("abs", Builtin::Unary(|z| Complex::new(z.abs(), 0.0))),
+    ("arg", Builtin::Unary(|z| Complex::new(z.arg(), 0.0))),
+    ("re", Builtin::Unary(|z| Complex::new(z.re, 0.0))),
+    ("im", Builtin::Unary(|z| Complex::new(z.im, 0.0))),
+    ("pow", Builtin::Binary(power)),
+    // log(z, b), the log of z to the base b
+    ("log", Builtin::Binary(|z, b| z.ln() / b.ln())),
+    // polar(r, theta) with the real parts of both
+    ("polar", Builtin::Binary(|r, theta| C64::from_polar(r.re, theta.re))),
+];
+
+// the names repl users can call
+pub fn functions() -> impl Iterator<Item = &'static str> { FUNCTIONS.iter().map(|&(name, _)| name) }
+
+// whole real exponents multiply, so (1+i)^2 is exactly 2i
+fn power(z: C64, w: C64) -> C64
+{
+    let n = w.re;
+    if w.im == 0.0 && n.fract() == 0.0 && n.abs() <= i32::MAX as f64 { z.powi(n as i32) } else { z.powc(w) }
+}
+
+// the variables a formula sees. new() starts out with pi and e, which can be
+// reassigned like any other name
+#[derive(Debug, Clone, Default)]
+pub struct Environment
+{
+    variables: HashMap<String, C64>,
+}
+
+impl Environment
+{
+    pub fn new() -> Environment
+    {
+        let mut env = Environment::default();
+        env.set("pi", Complex::new(std::f64::consts::PI, 0.0));
+        env.set("e", Complex::new(std::f64::consts::E, 0.0));
+        env
+    }
+
+    pub fn set(&mut self, name: &str, value: C64) { self.variables.insert(name.to_string(), value); }
+
+    pub fn get(&self, name: &str) -> Option<C64> { self.variables.get(name).copied() }
+
+    pub fn eval(&self, source: &str) -> Result<C64, ExprError> { parse(source)?.eval(self) }
+
+    // one line of a session: evaluates, assigns for "name = formula", and
+    // keeps the result in ans either way
+    pub fn run(&mut self, line: &str) -> Result<C64, ExprError>
+    {
+        let (target, expr) = parse_line(line)?;
+        let value = expr.eval(self)?;
+        if let Some(name) = target { self.set(&name, value); }
+        self.set("ans", value);
+        Ok(value)
+    }
+}
+
+impl Expr
+{
+    pub fn eval(&self, env: &Environment) -> Result<C64, ExprError>
+    {
+        match &self.kind {
+            ExprKind::Number(z) => Ok(*z),
+            ExprKind::Variable(name) => match env.get(name) {
+                Some(z) => Ok(z),
+                None => error(ErrorKind::UnknownVariable(name.clone()), self.span),
+            },
+            // 0 - z rather than -z: negating the 0 imaginary part of -4 would
+            // make it -0 and put sqrt(-4) on the other side of the branch cut
+            ExprKind::Negate(operand) => Ok(Complex::new(0.0, 0.0) - operand.eval(env)?),
+            ExprKind::Binary(op, lhs, rhs) => {
+                let (a, b) = (lhs.eval(env)?, rhs.eval(env)?);
+                Ok(match op {
+                    BinaryOp::Add => a + b,
+                    BinaryOp::Sub => a - b,
+                    BinaryOp::Mul => a * b,
+                    BinaryOp::Div => {
+                        // instead of the NaN the division would give
+                        if b.re == 0.0 && b.im == 0.0 { return error(ErrorKind::DivisionByZero, rhs.span); }
+                        a / b
+                    }
+                    BinaryOp::Pow => power(a, b),
+                })
+            }
+            ExprKind::Call(name, args) => {
+                let builtin = match FUNCTIONS.iter().find(|(f, _)| f == name) {
+                    Some(&(_, builtin)) => builtin,
+                    // the name only, the arguments are fine
+                    None => {
+                        let span = Span { start: self.span.start, end: self.span.start + name.len() };
+                        return error(ErrorKind::UnknownFunction(name.clone()), span);
+                    }
+                };
+                let expected = match builtin { Builtin::Unary(_) => 1, Builtin::Binary(_) => 2 };
+                if args.len() != expected {
+                    let kind = ErrorKind::WrongArgumentCount { function: name.clone(), expected, found: args.len() };
+                    return error(kind, self.span);
+                }
+                let values = args.iter().map(|arg| arg.eval(env)).collect::<Result<Vec<_>, _>>()?;
+                Ok(match builtin {
+                    Builtin::Unary(f) => f(values[0]),
+                    Builtin::Binary(f) => f(values[0], values[1]),
+                })
+            }
+        }
+    }
+
+    // the variables the formula reads, each once, in order of appearance
+    pub fn variables(&self) -> Vec<&str>
+    {
+        fn walk<'a>(expr: &'a Expr, out: &mut Vec<&'a str>)
+        {
+            match &expr.kind {
+                ExprKind::Number(_) => {}
+                ExprKind::Variable(name) => if !out.contains(&name.as_str()) { out.push(name) },
+                ExprKind::Negate(operand) => walk(operand, out),
+                ExprKind::Binary(_, lhs, rhs) => { walk(lhs, out); walk(rhs, out); }
+                ExprKind::Call(_, args) => for arg in args { walk(arg, out) },
+            }
+        }
+        let mut out = vec![];
+        walk(self, &mut out);
+        out
+    }
+}
+
+/////////////////////////////////////
+
+// reads formulas line by line until the input ends, writes each result or
+// the rendered error. calculator() runs it on stdin and stdout
+pub fn repl<R: BufRead, W: Write>(input: R, mut output: W) -> io::Result<()>
+{
+    let mut env = Environment::new();
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() { continue; }
+        match env.run(&line) {
+            Ok(value) => writeln!(output, "{}", value)?,
+            Err(e) => writeln!(output, "{}", e.render(&line))?,
+        }
+    }
+    Ok(())
+}
+
+pub fn calculator()
+{
+    println!("complex calculator, functions: {}", functions().collect::<Vec<_>>().join(" "));
+    repl(io::stdin().lock(), io::stdout()).expect("stdin or stdout failed");
+}
+
+pub fn main()
+{
+    let close = |a: C64, b: C64| a.approx_eq_abs(&b, 1e-12);
+    let z = |re, im| Complex::<f64>::new(re, im);
+
+    // sqrt(-4) = 2i, 2i (2 + 3i) = -6 + 4i, over 2
+    let mut env = Environment::new();
+    env.set("x", z(2.0, 0.0));
+    assert!(close(env.eval("sqrt(-4) * (2+3i) / x").unwrap(), z(-3.0, 2.0)));
+
+    // precedence and associativity
+    assert_eq!(env.eval("1 + 2 * 3").unwrap(), z(7.0, 0.0));
+    assert_eq!(env.eval("-2^2").unwrap(), z(-4.0, 0.0));
+    assert_eq!(env.eval("2^3^2").unwrap(), z(512.0, 0.0));
+    assert_eq!(env.eval("2^-1").unwrap(), z(0.5, 0.0));
+    assert_eq!(env.eval("8 / 4 / 2").unwrap(), z(1.0, 0.0));
+    assert_eq!(env.eval("(1+i)^2").unwrap(), z(0.0, 2.0));
+    assert_eq!(env.eval("1.5e1 - 2j").unwrap(), z(15.0, -2.0));
+    assert!(close(env.eval("exp(i*pi)").unwrap(), z(-1.0, 0.0)));
+    assert!(close(env.eval("i^i").unwrap(), z((-std::f64::consts::FRAC_PI_2).exp(), 0.0)));
+    assert!(close(env.eval("log(8, 2) + abs(3+4i) + polar(2, pi/2)").unwrap(), z(8.0, 2.0)));
+
+    // a formula parsed once and evaluated for many x
+    let f = parse("x^2 + c").unwrap();
+    assert_eq!(f.variables(), ["x", "c"]);
+    env.set("c", z(-1.0, 0.0));
+    for k in 0..4 {
+        env.set("x", z(k as f64, 1.0));
+        assert_eq!(f.eval(&env).unwrap(), z(k as f64 * k as f64 - 2.0, 2.0 * k as f64));
+    }
+
+    // errors point at their part of the input
+    let fails = |source: &str| env.eval(source).unwrap_err();
+    assert_eq!(fails("1 + y"), ExprError { kind: ErrorKind::UnknownVariable("y".into()), span: Span { start: 4, end: 5 } });
+    assert_eq!(fails("2 * (3 + 4").span, Span { start: 4, end: 5 });
+    assert_eq!(fails("2 * (3 + 4").kind, ErrorKind::UnbalancedParenthesis);
+    assert_eq!(fails("3 + 4)").kind, ErrorKind::UnbalancedParenthesis);
+    assert_eq!(fails("1 +").kind, ErrorKind::ExpectedExpression);
+    assert_eq!(fails("2x").span, Span { start: 1, end: 2 });
+    assert_eq!(fails("exp(i pi)").kind, ErrorKind::ExpectedOperator);
+    assert_eq!(fails("1 # 2").kind, ErrorKind::UnexpectedCharacter('#'));
+    assert_eq!(fails("1..2").kind, ErrorKind::InvalidNumber);
+    assert_eq!(fails("1 / (x - x)").span, Span { start: 4, end: 11 });
+    assert_eq!(fails("foo(1)").span, Span { start: 0, end: 3 });
+    let arity = fails("sqrt(1, 2)");
+    assert_eq!(arity.to_string(), "sqrt takes 1 argument, got 2 at 0..10");
+    println!("{}", fails("sqrt(-4) * (2+3i) / y").render("sqrt(-4) * (2+3i) / y"));
+
+    // nesting is limited instead of running out of stack
+    let deep = fails(&format!("{}1", "-".repeat(10_000)));
+    assert_eq!(deep, ExprError { kind: ErrorKind::TooDeep, span: Span { start: MAX_DEPTH, end: MAX_DEPTH + 1 } });
+    assert_eq!(fails(&format!("{}1{}", "(".repeat(100_000), ")".repeat(100_000))).kind, ErrorKind::TooDeep);
+    assert_eq!(fails(&format!("1{}", "^1".repeat(10_000))).kind, ErrorKind::TooDeep);
+    let nested = format!("{}1{}", "(".repeat(MAX_DEPTH - 1), ")".repeat(MAX_DEPTH - 1));
+    assert_eq!(env.eval(&nested).unwrap(), z(1.0, 0.0));
+
+    // a session: assignments, ans, errors don't end it
+    let input = "a = 3 + 4i\nabs(a)\nans * 2\nb + 1\nconj(a) * a\n";
+    let mut output = vec![];
+    repl(input.as_bytes(), &mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    print!("{}", output);
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines[0], "3+4i");
+    assert_eq!(lines[1], "5+0i");
+    assert_eq!(lines[2], "10+0i");
+    assert_eq!(lines[3..6], ["b + 1", "^ unknown variable b", "25+0i"]);
+}
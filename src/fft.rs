@@ -0,0 +1,361 @@
+// fast fourier transform over Complex<f64>
+//
+// forward:  X[k] = sum_j x[j] e^(-2 pi i jk / n)
+// inverse:  x[j] = 1/n sum_k X[k] e^(2 pi i jk / n)
+//
+// power of two lengths go through the in-place radix-2 transform, any other
+// length through bluestein's algorithm, which turns the transform into a
+// convolution that radix-2 can do
+
+use crate::Complex;
+
+use std::f64::consts::PI;
+
+type C64 = Complex<f64>;
+
+const ZERO: C64 = Complex { re: 0.0, im: 0.0 };
+
+// iterative cooley-tukey, panics if the length isn't a power of two.
+// the inverse is not scaled, ifft divides by n
+pub fn fft_radix2(data: &mut [C64], inverse: bool)
+{
+    let n = data.len();
+    assert!(n == 0 || n.is_power_of_two(), "radix-2 fft needs a power of two length, got {}", n);
+    if n <= 1 { return; }
+
+    // put every element at its bit reversed index, then the butterflies can
+    // work on neighbouring blocks
+    let bits = n.trailing_zeros();
+    for i in 0..n {
+        let j = i.reverse_bits() >> (usize::BITS - bits);
+        if i < j { data.swap(i, j); }
+    }
+
+    // every twiddle straight from sin / cos, multiplying up a single root
+    // would pile up rounding errors over a million points
+    let sign = if inverse { 1.0 } else { -1.0 };
+    let twiddles: Vec<C64> = (0..n / 2)
+        .map(|k| Complex::<f64>::from_polar(1.0, sign * 2.0 * PI * k as f64 / n as f64))
+        .collect();
+
+    let mut len = 2;
+    while len <= n {
+        let half = len / 2;
+        let stride = n / len;
+        for block in data.chunks_exact_mut(len) {
+            let (lo, hi) = block.split_at_mut(half);
+            for k in 0..half {
+                let a = lo[k];
+                let b = hi[k] * twiddles[k * stride];
+                lo[k] = a + b;
+                hi[k] = a - b;
+            }
+        }
+        len *= 2;
+    }
+}
+
+// chirp[k] = e^(-+ pi i k^2 / n). k^2 is reduced mod 2n first, the angle
+// would lose its precision for large k otherwise
+fn chirp(n: usize, inverse: bool) -> Vec<C64>
+{
+    let sign = if inverse { 1.0 } else { -1.0 };
+    (0..n)
+        .map(|k| {
+            let k2 = (k as u128 * k as u128 % (2 * n) as u128) as f64;
+            Complex::<f64>::from_polar(1.0, sign * PI * k2 / n as f64)
+        })
+        .collect()
+}
+
+// bluestein: jk = (j^2 + k^2 - (k - j)^2) / 2, so
+// X[k] = chirp[k] * sum_j (x[j] chirp[j]) conj(chirp[k - j])
+// and the sum is a convolution, padded to a power of two. unscaled like radix-2
+fn bluestein(data: &mut [C64], inverse: bool)
+{
+    let n = data.len();
+    let m = (2 * n - 1).next_power_of_two();
+    let w = chirp(n, inverse);
+
+    let mut a = vec![ZERO; m];
+    for k in 0..n { a[k] = data[k] * w[k]; }
+
+    // the filter is symmetric: conj(chirp) at k and at -k (wrapped to m - k)
+    let mut b = vec![ZERO; m];
+    b[0] = w[0].conj();
+    for k in 1..n {
+        b[k] = w[k].conj();
+        b[m - k] = w[k].conj();
+    }
+
+    fft_radix2(&mut a, false);
+    fft_radix2(&mut b, false);
+    for (x, y) in a.iter_mut().zip(&b) { *x *= *y; }
+    fft_radix2(&mut a, true);
+
+    for k in 0..n { data[k] = a[k] * w[k] / m as f64; }
+}
+
+fn transform(data: &mut [C64], inverse: bool)
+{
+    if data.len() <= 1 { return; }
+    if data.len().is_power_of_two() {
+        fft_radix2(data, inverse);
+    } else {
+        bluestein(data, inverse);
+    }
+}
+
+// in place, any length
+pub fn fft(data: &mut [C64])
+{
+    transform(data, false);
+}
+
+// in place, any length, ifft(fft(x)) == x
+pub fn ifft(data: &mut [C64])
+{
+    transform(data, true);
+    let scale = data.len() as f64;
+    for x in data.iter_mut() { *x /= scale; }
+}
+
+// O(n^2) straight from the definition, the reference the fast ones are checked against
+pub fn dft(input: &[C64]) -> Vec<C64>
+{
+    let n = input.len();
+    (0..n)
+        .map(|k| {
+            input.iter()
+                .enumerate()
+                .map(|(j, x)| *x * Complex::<f64>::from_polar(1.0, -2.0 * PI * ((j * k) % n) as f64 / n as f64))
+                .sum()
+        })
+        .collect()
+}
+
+/////////////////////////////////////
+
+// spectrum of a real signal. the other half is the mirror image
+// (X[n - k] = conj X[k]), so only bins 0..=n/2 come back.
+// for even n the signal is packed into a complex one of half the length:
+// z[k] = x[2k] + i x[2k + 1], one fft of that gives both halves
+pub fn rfft(input: &[f64]) -> Vec<C64>
+{
+    let n = input.len();
+    if n == 0 { return vec![]; }
+
+    if n % 2 == 1 {
+        let mut full: Vec<C64> = input.iter().map(|x| Complex::new(*x, 0.0)).collect();
+        fft(&mut full);
+        full.truncate(n / 2 + 1);
+        return full;
+    }
+
+    let h = n / 2;
+    let mut z: Vec<C64> = input.chunks_exact(2).map(|p| Complex::new(p[0], p[1])).collect();
+    fft(&mut z);
+
+    (0..=h)
+        .map(|k| {
+            let zk = z[k % h];
+            let zc = z[(h - k) % h].conj();
+            let even = (zk + zc) * 0.5;
+            // (zk - zc) / 2i
+            let odd = (zk - zc) * Complex::new(0.0, -0.5);
+            even + Complex::<f64>::from_polar(1.0, -2.0 * PI * k as f64 / n as f64) * odd
+        })
+        .collect()
+}
+
+// back from the n/2 + 1 bins of rfft to the n real samples
+pub fn irfft(spectrum: &[C64], n: usize) -> Vec<f64>
+{
+    assert_eq!(spectrum.len(), n / 2 + 1, "a real signal of length {} has {} bins", n, n / 2 + 1);
+    if n == 0 { return vec![]; }
+
+    let mut full = vec![ZERO; n];
+    full[..spectrum.len()].copy_from_slice(spectrum);
+    for k in 1..n.div_ceil(2) { full[n - k] = spectrum[k].conj(); }
+    ifft(&mut full);
+    full.into_iter().map(|x| x.re).collect()
+}
+
+/////////////////////////////////////
+
+// linear convolution, a.len() + b.len() - 1 values
+pub fn convolve(a: &[C64], b: &[C64]) -> Vec<C64>
+{
+    if a.is_empty() || b.is_empty() { return vec![]; }
+    let len = a.len() + b.len() - 1;
+    let m = len.next_power_of_two();
+
+    let mut fa = a.to_vec();
+    fa.resize(m, ZERO);
+    let mut fb = b.to_vec();
+    fb.resize(m, ZERO);
+
+    fft_radix2(&mut fa, false);
+    fft_radix2(&mut fb, false);
+    for (x, y) in fa.iter_mut().zip(&fb) { *x *= *y; }
+    fft_radix2(&mut fa, true);
+
+    fa.truncate(len);
+    for x in fa.iter_mut() { *x /= m as f64; }
+    fa
+}
+
+// real inputs share a single forward transform: c = a + ib, and the spectra
+// of a and b come back out of C through the mirror symmetry
+pub fn convolve_real(a: &[f64], b: &[f64]) -> Vec<f64>
+{
+    if a.is_empty() || b.is_empty() { return vec![]; }
+    let len = a.len() + b.len() - 1;
+    let m = len.next_power_of_two();
+
+    let mut c = vec![ZERO; m];
+    for (i, x) in a.iter().enumerate() { c[i].re = *x; }
+    for (i, y) in b.iter().enumerate() { c[i].im = *y; }
+    fft_radix2(&mut c, false);
+
+    let mut product: Vec<C64> = (0..m)
+        .map(|k| {
+            let ck = c[k];
+            let cc = c[(m - k) % m].conj();
+            let fa = (ck + cc) * 0.5;
+            let fb = (ck - cc) * Complex::new(0.0, -0.5);
+            fa * fb
+        })
+        .collect();
+    fft_radix2(&mut product, true);
+
+    product.into_iter().take(len).map(|x| x.re / m as f64).collect()
+}
+
+/////////////////////////////////////
+
+fn random_signal(n: usize, rng: &mut impl rand::Rng) -> Vec<C64>
+{
+    (0..n).map(|_| Complex::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0))).collect()
+}
+
+pub fn main()
+{
+    // a cosine with 3 periods in 16 samples lands in bins 3 and 13
+    let n = 16;
+    let wave: Vec<f64> = (0..n).map(|j| (2.0 * PI * 3.0 * j as f64 / n as f64).cos()).collect();
+    let mut spectrum: Vec<C64> = wave.iter().map(|x| Complex::new(*x, 0.0)).collect();
+    fft(&mut spectrum);
+    let peaks: Vec<usize> = (0..n).filter(|k| spectrum[*k].abs() > 1e-9).collect();
+    println!("peaks of a 3 cycle cosine: {:?}, height {:.3}", peaks, spectrum[3].abs());
+    assert_eq!(peaks, vec![3, 13]);
+
+    // the real transform keeps only the first half
+    let half = rfft(&wave);
+    println!("rfft keeps {} of {} bins, back to {:.3?}", half.len(), n, &irfft(&half, n)[..4]);
+
+    // 7 samples go through bluestein, the naive dft gives the same
+    let ramp: Vec<C64> = (0..7).map(|k| Complex::new(k as f64, 0.0)).collect();
+    let mut odd = ramp.clone();
+    fft(&mut odd);
+    println!("fft of 0..7: {:.3?}", odd);
+    assert!(odd.iter().zip(dft(&ramp)).all(|(x, y)| (*x - y).abs() < 1e-9));
+
+    // (1 + ix)(1 - ix) = 1 + x^2
+    let i = Complex::new(0.0, 1.0);
+    println!("(1 + ix)(1 - ix) = {:.3?}", convolve(&[Complex::new(1.0, 0.0), i], &[Complex::new(1.0, 0.0), -i]));
+
+    // (1 + 2x)(3 + x + x^2) = 3 + 7x + 3x^2 + 2x^3
+    let product = convolve_real(&[1.0, 2.0], &[3.0, 1.0, 1.0]);
+    println!("(1 + 2x)(3 + x + x^2) = {:.3?}", product);
+}
+
+// timings from 2^10 to 2^20 points, plus a few lengths that need bluestein
+pub fn benchmark()
+{
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::time::Instant;
+
+    let mut rng = StdRng::seed_from_u64(2);
+    let time = |label: &str, n: usize, run: &mut dyn FnMut()| {
+        let runs = (1 << 22) / n.max(1) + 1;
+        let start = Instant::now();
+        for _ in 0..runs { run(); }
+        let per_run = start.elapsed().as_secs_f64() / runs as f64;
+        println!("{:>10} n = {:>8}: {:>10.3} ms", label, n, per_run * 1e3);
+    };
+
+    for bits in (10..=20).step_by(2) {
+        let n = 1 << bits;
+        let signal = random_signal(n, &mut rng);
+        let real: Vec<f64> = signal.iter().map(|x| x.re).collect();
+
+        // the copy keeps the input the same every run, it costs little next to the fft
+        let mut data = signal.clone();
+        time("fft", n, &mut || { data.copy_from_slice(&signal); fft(&mut data); });
+        time("rfft", n, &mut || { rfft(&real); });
+    }
+
+    for n in [1000, 65_537, 1_000_000] {
+        let signal = random_signal(n, &mut rng);
+        let mut data = signal.clone();
+        time("bluestein", n, &mut || { data.copy_from_slice(&signal); fft(&mut data); });
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn max_error(a: &[C64], b: &[C64]) -> f64
+    {
+        a.iter().zip(b).map(|(x, y)| (*x - *y).abs()).fold(0.0, f64::max)
+    }
+
+    // every transform against the naive dft and naive convolution
+    #[test]
+    fn transforms_match_the_naive_versions()
+    {
+        let mut rng = StdRng::seed_from_u64(1);
+
+        for n in (1..=70).chain([97, 128, 255, 256, 500, 1009]) {
+            let signal = random_signal(n, &mut rng);
+            let expected = dft(&signal);
+
+            let mut fast = signal.clone();
+            fft(&mut fast);
+            let error = max_error(&fast, &expected);
+            assert!(error < 1e-9 * n as f64, "fft of length {} is off by {}", n, error);
+
+            ifft(&mut fast);
+            assert!(max_error(&fast, &signal) < 1e-12 * n as f64, "ifft of length {} doesn't round trip", n);
+
+            let real: Vec<f64> = signal.iter().map(|x| x.re).collect();
+            let real_expected = dft(&real.iter().map(|x| Complex::new(*x, 0.0)).collect::<Vec<_>>());
+            let spectrum = rfft(&real);
+            assert!(max_error(&spectrum, &real_expected[..n / 2 + 1]) < 1e-9 * n as f64, "rfft of length {}", n);
+            let back = irfft(&spectrum, n);
+            assert!(back.iter().zip(&real).all(|(x, y)| (x - y).abs() < 1e-12 * n as f64), "irfft of length {}", n);
+        }
+
+        let a = random_signal(37, &mut rng);
+        let b = random_signal(11, &mut rng);
+        let mut naive = vec![ZERO; a.len() + b.len() - 1];
+        for (i, x) in a.iter().enumerate() {
+            for (j, y) in b.iter().enumerate() { naive[i + j] += *x * *y; }
+        }
+        assert!(max_error(&convolve(&a, &b), &naive) < 1e-12);
+
+        let ra: Vec<f64> = a.iter().map(|x| x.re).collect();
+        let rb: Vec<f64> = b.iter().map(|x| x.re).collect();
+        let mut naive_real = vec![0.0; ra.len() + rb.len() - 1];
+        for (i, x) in ra.iter().enumerate() {
+            for (j, y) in rb.iter().enumerate() { naive_real[i + j] += x * y; }
+        }
+        assert!(convolve_real(&ra, &rb).iter().zip(&naive_real).all(|(x, y)| (x - y).abs() < 1e-12));
+    }
+}
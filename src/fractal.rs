@@ -0,0 +1,276 @@
+// mandelbrot and julia sets: every pixel is a point of the complex plane,
+// iterated through z -> z^2 + c until it escapes or runs out of iterations.
+// mandelbrot starts at z = 0 with c the pixel, julia starts at the pixel with
+// a fixed c
+//
+// escaped points are colored by the smooth iteration count
+//   mu = n + 1 - log2(ln |z_n|)
+// which, unlike n itself, changes continuously across the picture, so there
+// are no bands. rows are handed to the worker threads one at a time, the rows
+// through the middle of the set cost far more than the ones at the edges
+
+use crate::raster::{Canvas, Color};
+use crate::Complex;
+
+use std::sync::Mutex;
+use std::thread;
+
+type C64 = Complex<f64>;
+
+// past 2 a point is known to escape, carrying on to 256 makes mu accurate
+const BAILOUT: f64 = 256.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Fractal
+{
+    Mandelbrot,
+    Julia(C64),
+}
+
+// the main cardioid and the period 2 bulb never escape, and they are the most
+// expensive points to find that out for
+fn in_main_bulbs(c: C64) -> bool
+{
+    let y2 = c.im * c.im;
+    let q = (c.re - 0.25) * (c.re - 0.25) + y2;
+    q * (q + c.re - 0.25) <= 0.25 * y2 || (c.re + 1.0) * (c.re + 1.0) + y2 <= 1.0 / 16.0
+}
+
+impl Fractal
+{
+    // smooth escape count of the point, None if it hasn't escaped after max_iterations
+    pub fn escape(&self, point: C64, max_iterations: u32) -> Option<f64>
+    {
+        let (mut z, c) = match *self {
+            Fractal::Mandelbrot => {
+                if in_main_bulbs(point) { return None; }
+                (Complex::new(0.0, 0.0), point)
+            }
+            Fractal::Julia(c) => (point, c),
+        };
+
+        for n in 1..=max_iterations {
+            z = z * z + c;
+            let r2 = z.norm_sqr();
+            if r2 > BAILOUT * BAILOUT {
+                // ln |z| = ln(|z|^2) / 2
+                return Some((n as f64 + 1.0 - (0.5 * r2.ln()).log2()).max(0.0));
+            }
+        }
+        None
+    }
+}
+
+/////////////////////////////////////
+
+// blue to white to orange and back, cycled along mu
+const PALETTE: [Color; 5] = [
+    Color { r: 0, g: 7, b: 100 },
+    Color { r: 32, g: 107, b: 203 },
+    Color { r: 237, g: 255, b: 255 },
+    Color { r: 255, g: 170, b: 0 },
+    Color { r: 0, g: 2, b: 0 },
+];
+
+// iterations per trip around the palette
+const PALETTE_PERIOD: f64 = 24.0;
+
+// black inside the set, a smooth gradient outside
+pub fn smooth_color(mu: Option<f64>) -> Color
+{
+    let mu = match mu {
+        Some(mu) => mu,
+        None => return Color::BLACK,
+    };
+    let t = (mu / PALETTE_PERIOD).fract() * PALETTE.len() as f64;
+    let i = t as usize % PALETTE.len();
+    PALETTE[i].mix(PALETTE[(i + 1) % PALETTE.len()], t.fract())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Renderer
+{
+    fractal: Fractal,
+    width: usize,
+    height: usize,
+    center: C64,
+    zoom: f64,
+    max_iterations: u32,
+    threads: usize,
+}
+
+impl Renderer
+{
+    // the whole set in view, one thread per core
+    pub fn new(fractal: Fractal, width: usize, height: usize) -> Renderer
+    {
+        let center = match fractal {
+            Fractal::Mandelbrot => Complex::new(-0.5, 0.0),
+            Fractal::Julia(_) => Complex::new(0.0, 0.0),
+        };
+        Renderer {
+            fractal,
+            width,
+            height,
+            center,
+            zoom: 1.0,
+            max_iterations: 500,
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+        }
+    }
+
+    pub fn center(mut self, center: C64) -> Renderer
+    {
+        self.center = center;
+        self
+    }
+
+    // zoom 1 fits 3 units of the plane into the shorter side of the image,
+    // zoom 10 fits 0.3
+    pub fn zoom(mut self, zoom: f64) -> Renderer
+    {
+        self.zoom = zoom;
+        self
+    }
+
+    // deeper zooms need more, the boundary gets too dark otherwise
+    pub fn max_iterations(mut self, max_iterations: u32) -> Renderer
+    {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    pub fn threads(mut self, threads: usize) -> Renderer
+    {
+        self.threads = threads.max(1);
+        self
+    }
+
+    // the point under pixel coordinates (x, y), y grows downwards like on the canvas
+    pub fn pixel_to_point(&self, x: f64, y: f64) -> C64
+    {
+        let unit = 3.0 / (self.zoom * self.width.min(self.height).max(1) as f64);
+        Complex::new(
+            self.center.re + (x - self.width as f64 / 2.0) * unit,
+            self.center.im - (y - self.height as f64 / 2.0) * unit,
+        )
+    }
+
+    // smooth escape counts of the pixel centers, row after row
+    pub fn escape_times(&self) -> Vec<Option<f64>>
+    {
+        let mut values = vec![None; self.width * self.height];
+        if values.is_empty() { return values; }
+
+        // whichever worker is free takes the next row
+        let rows = Mutex::new(values.chunks_mut(self.width).enumerate());
+        thread::scope(|s| {
+            for _ in 0..self.threads {
+                s.spawn(|| loop {
+                    let next = rows.lock().unwrap().next();
+                    let (y, row) = match next {
+                        Some(next) => next,
+                        None => break,
+                    };
+                    for (x, value) in row.iter_mut().enumerate() {
+                        let point = self.pixel_to_point(x as f64 + 0.5, y as f64 + 0.5);
+                        *value = self.fractal.escape(point, self.max_iterations);
+                    }
+                });
+            }
+        });
+        values
+    }
+
+    pub fn render(&self) -> Canvas
+    {
+        let mut canvas = Canvas::new(self.width, self.height, Color::BLACK);
+        for (i, mu) in self.escape_times().into_iter().enumerate() {
+            canvas.set((i % self.width) as i64, (i / self.width) as i64, smooth_color(mu));
+        }
+        canvas
+    }
+}
+
+/////////////////////////////////////
+
+pub fn main()
+{
+    let c = |re: f64, im: f64| Complex::new(re, im);
+
+    // points whose fate is known
+    let mandelbrot = Fractal::Mandelbrot;
+    assert_eq!(mandelbrot.escape(c(0.0, 0.0), 1000), None);
+    assert_eq!(mandelbrot.escape(c(-1.0, 0.0), 1000), None);
+    // c = i lands on the cycle -1 + i, -i, -1 + i, ...
+    assert_eq!(mandelbrot.escape(c(0.0, 1.0), 1000), None);
+    assert!(mandelbrot.escape(c(0.5, 0.0), 1000).is_some());
+    assert!(mandelbrot.escape(c(-2.1, 0.0), 1000).is_some());
+
+    // with c = 0 the julia set is the unit circle
+    let circle = Fractal::Julia(c(0.0, 0.0));
+    assert_eq!(circle.escape(c(0.6, 0.7), 1000), None);
+    assert!(circle.escape(c(0.8, 0.7), 1000).is_some());
+
+    // the count is smooth: neighbouring points outside the set differ by a little
+    let a = mandelbrot.escape(c(-0.75, 0.1), 5000).unwrap();
+    let b = mandelbrot.escape(c(-0.75, 0.1 + 1e-6), 5000).unwrap();
+    assert!((a - b).abs() < 0.1, "{} vs {}", a, b);
+
+    // splitting the rows between threads doesn't change a single pixel
+    let small = Renderer::new(Fractal::Mandelbrot, 120, 80);
+    assert_eq!(small.clone().threads(1).render(), small.threads(7).render());
+
+    let renders = [
+        ("mandelbrot.ppm", Renderer::new(Fractal::Mandelbrot, 900, 600)),
+        (
+            "seahorses.ppm",
+            Renderer::new(Fractal::Mandelbrot, 900, 600)
+                .center(c(-0.743643887037151, 0.131825904205330))
+                .zoom(2000.0)
+                .max_iterations(3000),
+        ),
+        ("julia.ppm", Renderer::new(Fractal::Julia(c(-0.8, 0.156)), 900, 600).max_iterations(1000)),
+    ];
+    // into the temp directory, not wherever the demo happens to run from
+    for (file, renderer) in renders.iter() {
+        let path = std::env::temp_dir().join(file);
+        match renderer.render().save_ppm(&path.to_string_lossy()) {
+            Ok(()) => println!("wrote {}", path.display()),
+            Err(e) => println!("could not write {}: {}", path.display(), e),
+        }
+    }
+}
+
+// pixels per second of a zoomed in view on 1, 2, 4, ... threads
+pub fn benchmark()
+{
+    use std::time::Instant;
+
+    let cores = thread::available_parallelism().map_or(1, |n| n.get());
+    let view = Renderer::new(Fractal::Mandelbrot, 1024, 768)
+        .center(Complex::new(-0.7453, 0.1127))
+        .zoom(300.0)
+        .max_iterations(2000);
+    let pixels = 1024.0 * 768.0;
+
+    let mut single = None;
+    let mut threads = 1;
+    while threads <= cores.max(1) {
+        let start = Instant::now();
+        let values = view.clone().threads(threads).escape_times();
+        let seconds = start.elapsed().as_secs_f64();
+        let single_seconds = *single.get_or_insert(seconds);
+        let inside = values.iter().filter(|v| v.is_none()).count();
+        println!(
+            "{:>3} threads: {:>8.1} ms, {:>7.2} Mpixel/s, speedup {:.2} ({} pixels inside)",
+            threads,
+            seconds * 1e3,
+            pixels / seconds / 1e6,
+            single_seconds / seconds,
+            inside
+        );
+        if threads == cores { break; }
+        threads = (threads * 2).min(cores);
+    }
+}
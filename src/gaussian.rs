@@ -0,0 +1,182 @@
+// gaussian integers: Complex<i64>, the a + bi with whole a and b
+//
+// they divide with a remainder like the integers do: round the exact quotient
+// to the nearest gaussian integer and the remainder has at most half the norm
+// of the divisor, so euclid's gcd works. the gcd is only defined up to a unit
+// (1, i, -1, -i), the one returned is moved into the first quadrant
+//
+// the products go through i128 and the norm is a u128, which holds even
+// the 2^127 of i64::MIN + i64::MIN i. the operations panic only when a
+// result itself doesn't fit in i64 parts
+
+use crate::number_theory::{is_prime, is_prime_u128};
+use crate::Complex;
+
+// x + y as a sign and a magnitude, which has room for the 2^127 that two
+// products of i64 parts can add up to
+fn signed_sum(x: i128, y: i128) -> (bool, u128)
+{
+    match x.checked_add(y) {
+        Some(sum) => (sum < 0, sum.unsigned_abs()),
+        // only when both have the same sign
+        None => (x < 0, x.unsigned_abs() + y.unsigned_abs()),
+    }
+}
+
+// (-1)^negative x / n rounded to the nearest integer, halves away from
+// -infinity. n > 0
+fn div_round((negative, x): (bool, u128), n: u128) -> i64
+{
+    let (q, r) = (x / n, x % n);
+    let up = if negative { r > n - r } else { r >= n - r };
+    let q = i128::try_from(q + up as u128).expect("gaussian integer overflow");
+    fit(if negative { -q } else { q })
+}
+
+fn fit(x: i128) -> i64 { i64::try_from(x).expect("gaussian integer overflow") }
+
+impl Complex<i64>
+{
+    // a^2 + b^2, which needs the extra bits, all 128 of them for i64::MIN
+    pub fn norm(self) -> u128
+    {
+        let (a, b) = (self.re.unsigned_abs() as u128, self.im.unsigned_abs() as u128);
+        a * a + b * b
+    }
+
+    pub fn is_unit(self) -> bool { self.norm() == 1 }
+
+    // (quotient, remainder) with self = q d + r and norm(r) <= norm(d) / 2.
+    // panics when d is 0
+    pub fn div_rem(self, d: Complex<i64>) -> (Complex<i64>, Complex<i64>)
+    {
+        let n = d.norm();
+        assert!(n != 0, "attempt to divide by zero");
+
+        // self / d = self conj(d) / norm(d), the parts of the numerator can
+        // need the 128th bit for parts near i64::MIN
+        let (a, b, c, e) = (self.re as i128, self.im as i128, d.re as i128, d.im as i128);
+        let (qr, qi) = (div_round(signed_sum(a * c, b * e), n), div_round(signed_sum(b * c, -(a * e)), n));
+
+        // q d is within norm(d) of self, so these differences are small
+        let (qr, qi) = (qr as i128, qi as i128);
+        let r = Complex::new(fit(a - (qr * c - qi * e)), fit(b - (qr * e + qi * c)));
+        (Complex::new(qr as i64, qi as i64), r)
+    }
+
+    pub fn divides(self, other: Complex<i64>) -> bool
+    {
+        if self.re == 0 && self.im == 0 { return other.re == 0 && other.im == 0; }
+        let r = other.div_rem(self).1;
+        r.re == 0 && r.im == 0
+    }
+
+    // the associate (self times a unit) with re > 0 and im >= 0, 0 stays 0
+    pub fn normalize(self) -> Complex<i64>
+    {
+        let mut z = self;
+        for _ in 0..4 {
+            if z.re > 0 && z.im >= 0 { break; }
+            // times i, -i64::MIN has no i64
+            z = Complex::new(z.im.checked_neg().expect("gaussian integer overflow"), z.re);
+        }
+        z
+    }
+
+    pub fn gcd(self, other: Complex<i64>) -> Complex<i64>
+    {
+        let (mut a, mut b) = (self, other);
+        while b.re != 0 || b.im != 0 {
+            let r = a.div_rem(b).1;
+            a = b;
+            b = r;
+        }
+        a.normalize()
+    }
+
+    // a + bi is a gaussian prime when its norm is a prime, or when it is a
+    // prime integer 3 mod 4 (times a unit), those stay prime in Z[i]
+    pub fn is_gaussian_prime(self) -> bool
+    {
+        let (a, b) = (self.re.unsigned_abs(), self.im.unsigned_abs());
+        match (a, b) {
+            (0, p) | (p, 0) => p % 4 == 3 && is_prime(p),
+            _ => is_prime_u128(self.norm()),
+        }
+    }
+}
+
+/////////////////////////////////////
+
+pub fn main()
+{
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    let z = |re, im| Complex::<i64>::new(re, im);
+
+    // 5 splits, 3 doesn't, 2 ramifies: 2 = -i (1 + i)^2
+    assert_eq!(z(2, 1) * z(2, -1), z(5, 0));
+    assert!(!z(5, 0).is_gaussian_prime() && z(2, 1).is_gaussian_prime());
+    assert!(z(3, 0).is_gaussian_prime() && z(0, -7).is_gaussian_prime());
+    assert!(!z(2, 0).is_gaussian_prime() && z(1, 1).is_gaussian_prime());
+    assert_eq!(z(0, -1) * z(1, 1) * z(1, 1), z(2, 0));
+
+    // 3 + i = (1 + i)(2 - i) and 5 = (2 + i)(2 - i), they share 2 - i = -i (1 + 2i)
+    assert_eq!(z(5, 0).gcd(z(3, 1)), z(1, 2));
+    assert!(z(1, 2).divides(z(5, 0)) && z(1, 2).divides(z(3, 1)));
+    assert_eq!(z(7, 0).gcd(z(5, 0)), z(1, 0));
+    assert_eq!(z(-4, 0).gcd(z(0, 0)), z(4, 0));
+
+    // the remainder is small, whatever the signs
+    let mut rng = StdRng::seed_from_u64(43);
+    for _ in 0..10_000 {
+        let a = z(rng.gen_range(-1 << 40..1 << 40), rng.gen_range(-1 << 40..1 << 40));
+        let d = z(rng.gen_range(-1000..1000), rng.gen_range(-1000..1000));
+        if d.norm() == 0 { continue; }
+        let (q, r) = a.div_rem(d);
+        assert_eq!(q * d + r, a);
+        assert!(2 * r.norm() <= d.norm(), "{:?} / {:?}", a, d);
+    }
+    // and near the ends of i64, where q d alone would overflow
+    let (_, r) = z(i64::MIN, i64::MAX).div_rem(z(3, -2));
+    assert!(2 * r.norm() <= z(3, -2).norm());
+    let corner = z(i64::MIN, i64::MIN);
+    assert_eq!(corner.norm(), 1 << 127);
+    assert_eq!(corner.div_rem(corner), (z(1, 0), z(0, 0)));
+    assert_eq!(corner.div_rem(z(1, 1)), (z(i64::MIN, 0), z(0, 0)));
+    assert_eq!(corner.div_rem(z(i64::MAX, i64::MAX)), (z(-1, 0), z(-1, -1)));
+    assert!(!corner.is_gaussian_prime() && !corner.is_unit());
+    // the associates of -i64::MAX still fit
+    assert_eq!(z(-i64::MAX, 0).normalize(), z(i64::MAX, 0));
+    assert_eq!(z(0, -i64::MAX).gcd(z(0, 0)), z(i64::MAX, 0));
+
+    // the gaussian primes of small norm, one per associate class
+    let primes: Vec<Complex<i64>> = (0..6)
+        .flat_map(|a| (0..6).map(move |b| z(a, b)))
+        .filter(|p| p.is_gaussian_prime() && p.normalize() == *p)
+        .collect();
+    println!("gaussian primes: {:?}", primes);
+    assert_eq!(primes.len(), 12);
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    // every associate of i64::MIN has a part of 2^63, which i64 can't hold
+    #[test]
+    #[should_panic(expected = "gaussian integer overflow")]
+    fn normalize_min()
+    {
+        Complex::<i64>::new(i64::MIN, 0).normalize();
+    }
+
+    #[test]
+    #[should_panic(expected = "gaussian integer overflow")]
+    fn gcd_of_the_corner_and_zero()
+    {
+        Complex::<i64>::new(i64::MIN, i64::MIN).gcd(Complex::new(0, 0));
+    }
+}
@@ -0,0 +1,190 @@
+// lifecycle events of creatures: created, modified, destroyed
+//
+// a creature tracked on an EventBus (Creature::track) reports itself when
+// tracking starts, after every change made through its methods (take_damage,
+// apply, or changed() after writing the fields directly) and from its Drop,
+// each time with its whole state at that moment. the destroyed event carries the
+// final state, so nobody has to poll a creature to see it die
+//
+// subscribers come in two kinds:
+//   listeners   closures called right away, on the thread that changed the
+//               creature. they run outside the bus's lock and may publish
+//   channels    tokio unbounded channels, for async code. unbounded because
+//               Drop can't wait for room. a channel whose receiver is gone is
+//               dropped from the bus on the next event
+//
+// the bus is a handle, clones share the subscribers. a channel's stream ends
+// once every clone of the bus (the tracked creatures hold one) is gone
+
+use crate::combat::{Creature, Status};
+
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind
+{
+    Created,
+    Modified,
+    Destroyed,
+}
+
+// what a creature looked like when the event happened
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot
+{
+    pub name: String,
+    pub attack: i32,
+    pub defense: i32,
+    pub hp: i32,
+    pub max_hp: i32,
+    pub statuses: Vec<Status>,
+}
+
+impl Snapshot
+{
+    pub fn of(creature: &Creature) -> Snapshot
+    {
+        Snapshot {
+            name: creature.name.clone(),
+            attack: creature.attack,
+            defense: creature.defense,
+            hp: creature.hp,
+            max_hp: creature.max_hp,
+            statuses: creature.statuses.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event
+{
+    // which creature, given out by Creature::track
+    pub id: u64,
+    pub kind: EventKind,
+    pub state: Snapshot,
+}
+
+pub type Listener = Arc<dyn Fn(&Event) + Send + Sync>;
+
+#[derive(Default)]
+struct Subscribers
+{
+    next_id: u64,
+    listeners: Vec<(u64, Listener)>,
+    channels: Vec<UnboundedSender<Event>>,
+}
+
+#[derive(Clone, Default)]
+pub struct EventBus
+{
+    inner: Arc<Mutex<Subscribers>>,
+}
+
+impl EventBus
+{
+    pub fn new() -> EventBus { EventBus::default() }
+
+    // the id is for unsubscribe
+    pub fn subscribe(&self, listener: impl Fn(&Event) + Send + Sync + 'static) -> u64
+    {
+        let id = self.new_id();
+        self.inner.lock().unwrap().listeners.push((id, Arc::new(listener)));
+        id
+    }
+
+    // false if there was no such listener
+    pub fn unsubscribe(&self, id: u64) -> bool
+    {
+        let mut inner = self.inner.lock().unwrap();
+        let before = inner.listeners.len();
+        inner.listeners.retain(|(i, _)| *i != id);
+        inner.listeners.len() != before
+    }
+
+    // the events from now on, for a tokio task
+    pub fn channel(&self) -> UnboundedReceiver<Event>
+    {
+        let (sender, receiver) = unbounded_channel();
+        self.inner.lock().unwrap().channels.push(sender);
+        receiver
+    }
+
+    pub fn publish(&self, event: Event)
+    {
+        let listeners: Vec<Listener> = {
+            let mut inner = self.inner.lock().unwrap();
+            inner.channels.retain(|channel| channel.send(event.clone()).is_ok());
+            inner.listeners.iter().map(|(_, l)| l.clone()).collect()
+        };
+        for listener in listeners { listener(&event); }
+    }
+
+    // unique on this bus, for listeners and tracked creatures alike
+    pub fn new_id(&self) -> u64
+    {
+        let mut inner = self.inner.lock().unwrap();
+        inner.next_id += 1;
+        inner.next_id
+    }
+}
+
+/////////////////////////////////////
+
+pub fn main()
+{
+    use crate::combat::{Battle, Effect, Outcome};
+
+    let bus = EventBus::new();
+
+    // the logging system listens synchronously
+    let log = Arc::new(Mutex::new(vec![]));
+    let lines = log.clone();
+    let logger = bus.subscribe(move |e| lines.lock().unwrap().push(format!("{:?} {} hp {}", e.kind, e.state.name, e.state.hp)));
+
+    // the ui is async and wants deaths, not every scratch
+    let mut events = bus.channel();
+    let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    let ui = runtime.spawn(async move {
+        let mut deaths = vec![];
+        while let Some(event) = events.recv().await {
+            if event.kind == EventKind::Destroyed { deaths.push((event.state.name, event.state.hp)); }
+        }
+        // every bus handle is gone
+        deaths
+    });
+
+    let mut goblin = Creature::new("Goblin", 10, 5);
+    let goblin_id = goblin.track(&bus);
+    goblin.take_damage(7);
+    goblin.apply(Status { effect: Effect::Poison(2), turns: 3 });
+    goblin.attack += 5;
+    goblin.changed();
+    drop(goblin);
+
+    // a whole fight: the dead report as they are dropped with their battle
+    let mut teams = vec![
+        vec![Creature::new("Knight", 14, 10).with_hp(100)],
+        vec![Creature::new("Spider", 8, 3), Creature::new("Troll", 18, 8).with_hp(90)],
+    ];
+    let ids: Vec<u64> = teams.iter_mut().flatten().map(|c| c.track(&bus)).collect();
+    assert!(!ids.contains(&goblin_id));
+    let mut battle = Battle::new(teams);
+    let outcome = battle.run_seeded(50, 100);
+    drop(battle);
+
+    // the last handle of the bus goes, the ui's stream ends
+    assert!(bus.unsubscribe(logger) && !bus.unsubscribe(logger));
+    drop(bus);
+    let deaths = runtime.block_on(ui).unwrap();
+    println!("{:?} {:?}", outcome, deaths);
+
+    let log = log.lock().unwrap();
+    assert_eq!(log[..5], ["Created Goblin hp 50", "Modified Goblin hp 43", "Modified Goblin hp 43", "Modified Goblin hp 43", "Destroyed Goblin hp 43"]);
+    assert_eq!(deaths.len(), 4);
+    assert_eq!(deaths[0], ("Goblin".to_string(), 43));
+    // the losers are destroyed at 0 hp, the winners with what they had left
+    let Outcome::Victory(_) = outcome else { panic!("{:?}", outcome) };
+    assert!(deaths[1..].iter().any(|(_, hp)| *hp == 0) && deaths[1..].iter().any(|(_, hp)| *hp > 0));
+    assert!(log.iter().any(|line| line.starts_with("Modified") && line.ends_with("hp 0")));
+}
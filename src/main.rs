@@ -1,8 +1,101 @@
+mod animal;
+mod animal_serde;
+mod bigint;
+mod combat;
+mod complex_approx;
+mod complex_fmt;
+mod complex_math;
+mod complex_soa;
+mod curves;
+mod dual;
+mod ecs;
+mod expr;
+mod fft;
+mod fractal;
+mod gaussian;
+mod lifecycle;
+mod matrix;
+mod monte_carlo;
+mod num;
+mod number_theory;
+mod polynomial;
+mod quaternion;
+mod rational;
+mod raster;
+
+use num::{Float, Num, One, Signed, Zero};
 use rand::Rng;
 use std::io::stdin;
 
+// the demo of every module, by name
+const DEMOS: &[(&str, fn())] = &[
+    ("animal", animal::main),
+    ("animal_serde", animal_serde::main),
+    ("bigint", bigint::main),
+    ("combat", combat::main),
+    ("complex_approx", complex_approx::main),
+    ("complex_fmt", complex_fmt::main),
+    ("complex_math", complex_math::main),
+    ("complex_soa", complex_soa::main),
+    ("curves", curves::main),
+    ("dual", dual::main),
+    ("ecs", ecs::main),
+    ("expr", expr::main),
+    ("fft", fft::main),
+    ("fractal", fractal::main),
+    ("gaussian", gaussian::main),
+    ("lifecycle", lifecycle::main),
+    ("matrix", matrix::main),
+    ("monte_carlo", monte_carlo::main),
+    ("num", num::main),
+    ("number_theory", number_theory::main),
+    ("polynomial", polynomial::main),
+    ("quaternion", quaternion::main),
+    ("rational", rational::main),
+    ("raster", raster::main),
+];
+
+// timings, only worth reading from a release build
+const BENCHMARKS: &[(&str, fn())] = &[
+    ("complex_soa", complex_soa::benchmark),
+    ("fft", fft::benchmark),
+    ("fractal", fractal::benchmark),
+];
+
+// the ones called name, or all of them
+fn run_each(table: &[(&str, fn())], name: Option<&str>)
+{
+    let chosen: Vec<&(&str, fn())> = table.iter().filter(|(n, _)| name.is_none_or(|name| name == *n)).collect();
+    if chosen.is_empty() {
+        let names: Vec<&str> = table.iter().map(|(n, _)| *n).collect();
+        println!("nothing called {}, there is {}", name.unwrap_or(""), names.join(", "));
+    }
+    for (n, run) in chosen {
+        println!("== {}", n);
+        run();
+    }
+}
+
+// cargo run -- demo [module]
+// cargo run --release -- bench [name]
+// cargo run -- calc
+fn run_command(args: &[String])
+{
+    let name = args.get(1).map(String::as_str);
+    match args[0].as_str() {
+        "demo" => run_each(DEMOS, name),
+        "bench" => run_each(BENCHMARKS, name),
+        "calc" => expr::calculator(),
+        other => println!("unknown command {}, try demo, bench or calc", other),
+    }
+}
+
 fn main()
 {
+    // with arguments it's the demos and benchmarks, without the game
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() { return run_command(&args); }
+
     // generate a number between 1 and 100
     let number = rand::thread_rng().gen_range(1..101);
 
@@ -45,19 +138,74 @@ fn main()
     }
 }
 
-struct Point {
-    x: f64,
-    y: f64,
+// the geometry is generic over the coordinates and defaults to f64, so
+// Point means Point<f64> and Rect<i32> is a box of pixels
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Point<T = f64> {
+    x: T,
+    y: T,
 }
 
-struct Line {
-    start: Point,
-    end: Point,
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Line<T = f64> {
+    start: Point<T>,
+    end: Point<T>,
+}
+
+// axis aligned box, min is the bottom left corner and max the top right one
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Rect<T = f64> {
+    min: Point<T>,
+    max: Point<T>,
+}
+
+// min and max on PartialOrd, the integers have them on Ord and the floats
+// inherent, neither covers both
+fn smaller<T: PartialOrd>(a: T, b: T) -> T { if b < a { b } else { a } }
+
+fn larger<T: PartialOrd>(a: T, b: T) -> T { if b > a { b } else { a } }
+
+impl<T: Num + PartialOrd + Copy> Rect<T>
+{
+    // the two corners can be given in any order
+    fn new(a: Point<T>, b: Point<T>) -> Rect<T>
+    {
+        Rect {
+            min: Point { x: smaller(a.x, b.x), y: smaller(a.y, b.y) },
+            max: Point { x: larger(a.x, b.x), y: larger(a.y, b.y) },
+        }
+    }
+
+    fn width(&self) -> T { self.max.x - self.min.x }
+
+    fn height(&self) -> T { self.max.y - self.min.y }
+
+    // smallest box containing both self and p
+    fn expand(self, p: Point<T>) -> Rect<T>
+    {
+        Rect {
+            min: Point { x: smaller(self.min.x, p.x), y: smaller(self.min.y, p.y) },
+            max: Point { x: larger(self.max.x, p.x), y: larger(self.max.y, p.y) },
+        }
+    }
+
+    fn union(self, other: Rect<T>) -> Rect<T>
+    {
+        self.expand(other.min).expand(other.max)
+    }
+
+    // None when the boxes don't overlap
+    fn intersection(self, other: Rect<T>) -> Option<Rect<T>>
+    {
+        let min = Point { x: larger(self.min.x, other.min.x), y: larger(self.min.y, other.min.y) };
+        let max = Point { x: smaller(self.max.x, other.max.x), y: smaller(self.max.y, other.max.y) };
+        if min.x <= max.x && min.y <= max.y { Some(Rect { min, max }) } else { None }
+    }
 }
 
-impl Line
+impl<T: Float> Line<T>
 {
-    fn len(&self) -> f64
+    fn len(&self) -> T
     {
         let x_diff = self.start.x - self.end.x;
         let y_diff = self.start.y - self.end.y;
@@ -163,71 +311,36 @@ fn higher_order_functions()
 
 /////////////////////////////////////
 
-trait Animal // when defining a class we can choose to implement this trait
-{
-    fn create(name: &'static str) -> Self; // Self is the type of the class that implements this trait
-
-    fn name(&self) -> &'static str;
+use animal::{Animal, Cat, Creature, Human};
 
-    fn talk(&self)
-    {
-        println!("{} cannot talk", self.name());
-    }
-}
-
-struct Human
-{
-    name: &'static str,
-}
-
-struct Cat
+trait Summable<T>
 {
-    name: &'static str,
+    fn sum(&self) -> T;
 }
 
-impl Animal for Human
+// once for every number type. a Vec<i32> still overflows when the total
+// doesn't fit (a panic in debug builds), a Vec<BigInt> never does
+impl<T: Num> Summable<T> for Vec<T>
 {
-    fn create(name: &'static str) -> Human
+    fn sum(&self) -> T
     {
-        Human { name: name }
-    }
-
-    fn name(&self) -> &'static str { self.name }
-
-    fn talk(&self)
-    {
-        println!("{} says hello", self.name);
+        let mut sum = T::zero();
+        for i in self { sum = sum + i.clone(); }
+        sum
     }
 }
 
-impl Animal for Cat
+// the exact total of plain ints. a method of its own, as a Summable<BigInt>
+// impl for Vec<i32> would make vec![1, 2, 3].sum() ambiguous. an i128 can't
+// overflow on anything that fits in memory, the BigInt is for what comes after
+trait SumExact
 {
-    fn create(name: &'static str) -> Cat
-    {
-        Cat { name: name }
-    }
-
-    fn name(&self) -> &'static str { self.name }
-
-    fn talk(&self)
-    {
-        println!("{} says meow", self.name);
-    }
-}
-
-trait Summable<T>
-{
-    fn sum(&self) -> T;
+    fn sum_exact(&self) -> bigint::BigInt;
 }
 
-impl Summable<i32> for Vec<i32>
+impl SumExact for Vec<i32>
 {
-    fn sum(&self) -> i32
-    {
-        let mut sum = 0;
-        for i in self { sum += *i; }
-        return sum;
-    }
+    fn sum_exact(&self) -> bigint::BigInt { bigint::BigInt::from(self.iter().map(|&x| x as i128).sum::<i128>()) }
 }
 
 fn traits()
@@ -237,10 +350,10 @@ fn traits()
     // traits can be implemented for any type
     // traits can be used as bounds to specify that a generic type may be any type that implements a trait
 
-    let person = Human { name: "John" };
+    let person = Human { name: "John".into() };
     person.talk();
 
-    let cat = Cat { name: "Misty" };
+    let cat = Cat { name: "Misty".into() };
     cat.talk();
 
     let human2: Human = Animal::create("Bob");
@@ -264,16 +377,71 @@ struct Circle { radius: f64 }
 #[derive(Debug)]
 struct Square { side: f64 }
 
-trait Shape { fn area(&self) -> f64; }
+trait Shape
+{
+    fn area(&self) -> f64;
+
+    // smallest axis aligned box the shape fits in
+    fn bounding_box(&self) -> Rect;
+
+    fn contains(&self, p: Point) -> bool;
+}
+
+// circles and squares are centered on the origin
 
 impl Shape for Circle
 {
     fn area(&self) -> f64 { std::f64::consts::PI * (self.radius * self.radius) }
+
+    fn bounding_box(&self) -> Rect
+    {
+        let r = self.radius;
+        Rect::new(Point { x: -r, y: -r }, Point { x: r, y: r })
+    }
+
+    fn contains(&self, p: Point) -> bool { p.x * p.x + p.y * p.y <= self.radius * self.radius }
 }
 
 impl Shape for Square
 {
     fn area(&self) -> f64 { self.side * self.side }
+
+    fn bounding_box(&self) -> Rect
+    {
+        let h = self.side / 2.0;
+        Rect::new(Point { x: -h, y: -h }, Point { x: h, y: h })
+    }
+
+    fn contains(&self, p: Point) -> bool
+    {
+        let h = self.side / 2.0;
+        p.x.abs() <= h && p.y.abs() <= h
+    }
+}
+
+// any shape moved away from the origin
+#[derive(Debug)]
+struct Translated<S: Shape>
+{
+    shape: S,
+    offset: Point,
+}
+
+impl<S: Shape> Shape for Translated<S>
+{
+    fn area(&self) -> f64 { self.shape.area() }
+
+    fn bounding_box(&self) -> Rect
+    {
+        let b = self.shape.bounding_box();
+        let (dx, dy) = (self.offset.x, self.offset.y);
+        Rect::new(Point { x: b.min.x + dx, y: b.min.y + dy }, Point { x: b.max.x + dx, y: b.max.y + dy })
+    }
+
+    fn contains(&self, p: Point) -> bool
+    {
+        self.shape.contains(Point { x: p.x - self.offset.x, y: p.y - self.offset.y })
+    }
 }
 
 // there are 3 ways of specifying a trait passed as a parameter
@@ -338,32 +506,11 @@ impl Person
 
 /////////////////////////////////////
 
-struct Creature
-{
-    name: String,
-    attack: i32,
-    defense: i32,
-}
-
-impl Creature {
-    fn new(name: &str, attack: i32, defense: i32) -> Creature
-    {
-        println!("{} has been created", name);
-        Creature { name: name.to_string(), attack, defense }
-    }
-}
-
-impl Drop for Creature
-{
-    fn drop(&mut self)
-    {
-        println!("{} has been destroyed", self.name);
-    }
-}
+// Creature, its attack and defense and the Drop message, moved to combat.rs
 
 fn main()
 {
-    let goblin = Creature::new("Goblin", 10, 5);
+    let goblin = combat::Creature::new("Goblin", 10, 5);
     println!("{} has {} attack and {} defense", goblin.name, goblin.attack, goblin.defense);
     drop(goblin); // drop is a function that calls the drop method of the object
     // when the object is dropped the drop method is called
@@ -374,10 +521,11 @@ fn main()
 
 /////////////////////////////////////
 
-use std::ops::{Add, Sub, Mul, Div, AddAssign, Neg};
+use std::iter::{Product, Sum};
+use std::ops::{Add, Sub, Mul, Div, AddAssign, SubAssign, MulAssign, DivAssign, Neg};
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
-struct Complex<T>
+#[derive(Debug, Default, PartialOrd, Ord, Clone, Copy)]
+pub struct Complex<T>
 {
     re: T,
     im: T,
@@ -392,29 +540,28 @@ impl<T> Complex<T>
 }
 
 impl<T> Add for Complex<T>
-where T: Add<Output = T>
+where T: Num
 {
     // You have to specify this thype because the compiler can't infer it
     type Output = Complex<T>;
 
     fn add(self, rhs: Self) -> Self::Output
     {
-        Complex::<i32> { re: self.re + rhs.re, im: self.im + rhs.im }
+        Complex::<T> { re: self.re + rhs.re, im: self.im + rhs.im }
     }
 }
 
 impl<T> AddAssign for Complex<T>
-where T: AddAssign
+where T: Num
 {
     fn add_assign(&mut self, rhs: Self)
     {
-        self.re += rhs.re;
-        self.im += rhs.im;
+        *self = self.clone() + rhs;
     }
 }
 
 impl<T> Neg for Complex<T>
-where T: Neg<Output = T>
+where T: Signed
 {
     type Output = Complex<T>;
 
@@ -424,22 +571,271 @@ where T: Neg<Output = T>
     }
 }
 
+impl<T> Sub for Complex<T>
+where T: Num
+{
+    type Output = Complex<T>;
+
+    fn sub(self, rhs: Self) -> Self::Output
+    {
+        Complex::<T> { re: self.re - rhs.re, im: self.im - rhs.im }
+    }
+}
+
+// (a + bi)(c + di) = (ac - bd) + (ad + bc)i
+// T only needs Clone, so parts that aren't Copy (big integers..) work too
+impl<T> Mul for Complex<T>
+where T: Num
+{
+    type Output = Complex<T>;
+
+    fn mul(self, rhs: Self) -> Self::Output
+    {
+        let re = self.re.clone() * rhs.re.clone() - self.im.clone() * rhs.im.clone();
+        let im = self.re * rhs.im + self.im * rhs.re;
+        Complex::<T> { re, im }
+    }
+}
+
+// multiply above and below by the conjugate of rhs:
+// (a + bi)/(c + di) = ((ac + bd) + (bc - ad)i) / (c^2 + d^2)
+impl<T> Div for Complex<T>
+where T: Num
+{
+    type Output = Complex<T>;
+
+    fn div(self, rhs: Self) -> Self::Output
+    {
+        let norm = rhs.re.clone() * rhs.re.clone() + rhs.im.clone() * rhs.im.clone();
+        let re = self.re.clone() * rhs.re.clone() + self.im.clone() * rhs.im.clone();
+        let im = self.im * rhs.re - self.re * rhs.im;
+        Complex::<T> { re: re / norm.clone(), im: im / norm }
+    }
+}
+
+impl<T> SubAssign for Complex<T>
+where T: Num
+{
+    fn sub_assign(&mut self, rhs: Self)
+    {
+        *self = self.clone() - rhs;
+    }
+}
+
+impl<T> MulAssign for Complex<T>
+where T: Num
+{
+    fn mul_assign(&mut self, rhs: Self)
+    {
+        *self = self.clone() * rhs;
+    }
+}
+
+impl<T> DivAssign for Complex<T>
+where T: Num
+{
+    fn div_assign(&mut self, rhs: Self)
+    {
+        *self = self.clone() / rhs;
+    }
+}
+
+// the same operators on references: &a + &b, a + &b, &a + b and a += &b
+macro_rules! complex_ref_ops {
+    ($($imp:ident $method:ident $assign_imp:ident $assign_method:ident),*) => {$(
+        impl<'a, 'b, T> $imp<&'b Complex<T>> for &'a Complex<T>
+        where T: Clone, Complex<T>: $imp<Output = Complex<T>>
+        {
+            type Output = Complex<T>;
+
+            fn $method(self, rhs: &'b Complex<T>) -> Complex<T> { self.clone().$method(rhs.clone()) }
+        }
+
+        impl<'b, T> $imp<&'b Complex<T>> for Complex<T>
+        where T: Clone, Complex<T>: $imp<Output = Complex<T>>
+        {
+            type Output = Complex<T>;
+
+            fn $method(self, rhs: &'b Complex<T>) -> Complex<T> { self.$method(rhs.clone()) }
+        }
+
+        impl<'a, T> $imp<Complex<T>> for &'a Complex<T>
+        where T: Clone, Complex<T>: $imp<Output = Complex<T>>
+        {
+            type Output = Complex<T>;
+
+            fn $method(self, rhs: Complex<T>) -> Complex<T> { self.clone().$method(rhs) }
+        }
+
+        impl<'b, T> $assign_imp<&'b Complex<T>> for Complex<T>
+        where T: Clone, Complex<T>: $assign_imp
+        {
+            fn $assign_method(&mut self, rhs: &'b Complex<T>) { self.$assign_method(rhs.clone()) }
+        }
+    )*};
+}
+
+complex_ref_ops!(
+    Add add AddAssign add_assign,
+    Sub sub SubAssign sub_assign,
+    Mul mul MulAssign mul_assign,
+    Div div DivAssign div_assign
+);
+
+// complex op scalar, the scalar is a complex number with no imaginary part
+
+impl<T> Add<T> for Complex<T>
+where T: Num
+{
+    type Output = Complex<T>;
+
+    fn add(self, rhs: T) -> Self::Output { Complex::<T> { re: self.re + rhs, im: self.im } }
+}
+
+impl<T> Sub<T> for Complex<T>
+where T: Num
+{
+    type Output = Complex<T>;
+
+    fn sub(self, rhs: T) -> Self::Output { Complex::<T> { re: self.re - rhs, im: self.im } }
+}
+
+impl<T> Mul<T> for Complex<T>
+where T: Num
+{
+    type Output = Complex<T>;
+
+    fn mul(self, rhs: T) -> Self::Output { Complex::<T> { re: self.re * rhs.clone(), im: self.im * rhs } }
+}
+
+impl<T> Div<T> for Complex<T>
+where T: Num
+{
+    type Output = Complex<T>;
+
+    fn div(self, rhs: T) -> Self::Output { Complex::<T> { re: self.re / rhs.clone(), im: self.im / rhs } }
+}
+
+impl<T> AddAssign<T> for Complex<T>
+where T: Num
+{
+    fn add_assign(&mut self, rhs: T) { self.re = self.re.clone() + rhs; }
+}
+
+impl<T> SubAssign<T> for Complex<T>
+where T: Num
+{
+    fn sub_assign(&mut self, rhs: T) { self.re = self.re.clone() - rhs; }
+}
+
+impl<T> MulAssign<T> for Complex<T>
+where T: Num
+{
+    fn mul_assign(&mut self, rhs: T)
+    {
+        *self = self.clone() * rhs;
+    }
+}
+
+impl<T> DivAssign<T> for Complex<T>
+where T: Num
+{
+    fn div_assign(&mut self, rhs: T)
+    {
+        *self = self.clone() / rhs;
+    }
+}
+
+// scalar op complex can't be written once for every T (the scalar type is
+// foreign), so it is spelled out for each primitive
+macro_rules! complex_primitive_ops {
+    ($($t:ty),*) => {$(
+        impl Add<Complex<$t>> for $t
+        {
+            type Output = Complex<$t>;
+
+            fn add(self, rhs: Complex<$t>) -> Complex<$t> { rhs + self }
+        }
+
+        impl Sub<Complex<$t>> for $t
+        {
+            type Output = Complex<$t>;
+
+            fn sub(self, rhs: Complex<$t>) -> Complex<$t> { Complex::new(self, 0 as $t) - rhs }
+        }
+
+        impl Mul<Complex<$t>> for $t
+        {
+            type Output = Complex<$t>;
+
+            fn mul(self, rhs: Complex<$t>) -> Complex<$t> { rhs * self }
+        }
+
+        impl Div<Complex<$t>> for $t
+        {
+            type Output = Complex<$t>;
+
+            fn div(self, rhs: Complex<$t>) -> Complex<$t> { Complex::new(self, 0 as $t) / rhs }
+        }
+
+        // a real number equals the complex number with the same real part and no imaginary part
+        impl PartialEq<$t> for Complex<$t>
+        {
+            fn eq(&self, rhs: &$t) -> bool { self.re == *rhs && self.im == 0 as $t }
+        }
+    )*};
+}
+
+complex_primitive_ops!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64);
+
+// a complex number is a Num whenever its parts are, so Complex<Complex<T>>,
+// Polynomial<Complex<T>> and Summable over complex numbers come for free
+impl<T: Num> Zero for Complex<T>
+{
+    fn zero() -> Complex<T> { Complex::new(T::zero(), T::zero()) }
+
+    fn is_zero(&self) -> bool { self.re.is_zero() && self.im.is_zero() }
+}
+
+impl<T: Num> One for Complex<T>
+{
+    fn one() -> Complex<T> { Complex::new(T::one(), T::zero()) }
+}
+
+impl<T: Num> Sum for Complex<T>
+{
+    fn sum<I: Iterator<Item = Complex<T>>>(iter: I) -> Complex<T> { iter.fold(Complex::zero(), |acc, c| acc + c) }
+}
+
+impl<'a, T: Num> Sum<&'a Complex<T>> for Complex<T>
+{
+    fn sum<I: Iterator<Item = &'a Complex<T>>>(iter: I) -> Complex<T> { iter.fold(Complex::zero(), |acc, c| acc + c) }
+}
+
+impl<T: Num> Product for Complex<T>
+{
+    fn product<I: Iterator<Item = Complex<T>>>(iter: I) -> Complex<T> { iter.fold(Complex::one(), |acc, c| acc * c) }
+}
+
+impl<'a, T: Num> Product<&'a Complex<T>> for Complex<T>
+{
+    fn product<I: Iterator<Item = &'a Complex<T>>>(iter: I) -> Complex<T> { iter.fold(Complex::one(), |acc, c| acc * c) }
+}
+
 // partial eq
 // full eq x = x
 // NAN = not a number, for floating point numbers 0/0 inf/inf
 // NAN == NAN -> false
 
-impl<T> PartialEq<T> for Complex<T> 
+// two complex numbers are equal when both parts are, so a NAN anywhere makes
+// the number unequal to everything, itself included. ne is left to its
+// default (!eq) so the two can never disagree
+impl<T> PartialEq for Complex<T>
     where T: PartialEq
 {
     fn eq(&self, rhs: &Self) -> bool
     {
-        self.re == rhs && self.im == rhs.im
-    }
-
-    fn ne(&self, rhs: &Self) -> bool
-    {
-        self.re != rhs && self.im != rhs.im
+        self.re == rhs.re && self.im == rhs.im
     }
 }
 
@@ -466,6 +862,17 @@ fn main()
     let i = Complex::new(1, 2);
     let j = -i;
     println!("{:?}", j);
+
+    // (1 + 2i)(3 + 4i) = -5 + 10i and back again
+    let k = a * b;
+    println!("{:?} {:?} {:?}", k, k / b, k - a);
+
+    // scalars on either side
+    println!("{:?} {:?}", 2.0 * d, d / 2.0);
+
+    let total: Complex<i32> = vec![a, b, k].into_iter().sum();
+    let powers: Complex<f64> = [d, d, d].iter().product();
+    println!("{:?} {:?}", total, powers);
 }
 
 /////////////////////////////////////
@@ -527,70 +934,18 @@ fn main()
 
 /////////////////////////////////////
 
-trait Animal {
-    // static: called as Animal::create()
-    // returns the type of the implementor
-    // fn create(name: &'static str) -> Self;
-
-    fn name(&self) -> &'static str;
-
-    fn talk(&self)
-    {
-        println!("{} cannot talk", self.name());
-    }
-}
-
-struct Human
-{
-    name: &'static str,
-}
-
-impl Animal for Human
-{
-    fn name(&self) -> &'static str
-    {
-        self.name
-    }
-
-    fn talk(&self)
-    {
-        println!("{} says hello", self.name());
-    }
-}
-
-struct Cat
-{
-    name: &'static str,
-}
-
-impl Animal for Cat
-{
-    fn name(&self) -> &'static str
-    {
-        self.name
-    }
-
-    fn talk(&self)
-    {
-        println!("{} says meow", self.name());
-    }
-}
-
-enum Creature {
-    Human(Human),
-    Cat(Cat),
-}
+// Animal, Human, Cat and the Creature enum moved to animal.rs
 
 fn main()
 {
     let mut creatures: Vec<Creature> = Vec::new();
-    // creatures.push(Human { name: "John" });
-    // creatures.push(Cat { name: "Misty" }); // panic! vec is of humans now
+    // creatures.push(Human { name: "John".into() });
+    // creatures.push(Cat { name: "Misty".into() }); // panic! vec is of humans now
     creatures.push(Creature::Human(
-        Human { name: "John" }
+        Human { name: "John".into() }
     ));
     creatures.push(Creature::Cat(
-        Cat { name: "Misty" }
+        Cat { name: "Misty".into() }
     ));
 
     for c: Creature in creatures: Vec<Creature>
@@ -606,8 +961,8 @@ fn main()
     // need to have the Sized trait to be able to push
     // as can't push objects of unknkown size
     let mut animals: Vec<Box<Animal>> = Vec::new();
-    animals.push(Box::new(Human { name: "John" }));
-    animals.push(Box::new(Cat { name: "Misty" }));
+    animals.push(Box::new(Human { name: "John".into() }));
+    animals.push(Box::new(Cat { name: "Misty".into() }));
 
     for a: Box<Animal> in animals.iter()
     {
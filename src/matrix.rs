@@ -0,0 +1,549 @@
+// dense matrices over f64 and Complex<f64>, stored row by row
+//
+// everything that can fail on bad input returns a MatrixError instead of
+// panicking: mismatched dimensions, non square matrices where a square one is
+// needed, and (numerically) singular ones. that includes the operators, &a * &b
+// is a Result. indexing with m[(i, j)] still panics out of range, like a Vec
+//
+// a matrix counts as singular when a pivot falls below n * EPSILON times its
+// largest entry, past that point the answers would be rounding noise
+
+use crate::num::Num;
+use crate::Complex;
+
+use std::fmt;
+use std::ops::{Add, Index, IndexMut, Mul, Neg, Sub};
+
+// what the algorithms need from an element on top of the field operations
+pub trait Scalar: Num + Copy + fmt::Debug + Neg<Output = Self>
+{
+    fn from_real(x: f64) -> Self;
+    fn conj(self) -> Self;
+    // |x|, what pivoting and the singularity checks compare
+    fn modulus(self) -> f64;
+}
+
+impl Scalar for f64
+{
+    fn from_real(x: f64) -> f64 { x }
+    fn conj(self) -> f64 { self }
+    fn modulus(self) -> f64 { self.abs() }
+}
+
+impl Scalar for Complex<f64>
+{
+    fn from_real(x: f64) -> Complex<f64> { Complex::new(x, 0.0) }
+    fn conj(self) -> Complex<f64> { Complex::<f64>::conj(self) }
+    fn modulus(self) -> f64 { self.abs() }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MatrixError
+{
+    // the operation only makes sense for square matrices
+    NotSquare { rows: usize, cols: usize },
+    // (rows, cols) of the operand that doesn't fit, vectors count as one column
+    DimensionMismatch { expected: (usize, usize), found: (usize, usize) },
+    // from_rows got rows of different lengths
+    RaggedRows { row: usize, expected: usize, found: usize },
+    Singular,
+}
+
+impl fmt::Display for MatrixError
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self {
+            MatrixError::NotSquare { rows, cols } => write!(f, "expected a square matrix, got {}x{}", rows, cols),
+            MatrixError::DimensionMismatch { expected, found } => {
+                write!(f, "dimension mismatch: expected {}x{}, found {}x{}", expected.0, expected.1, found.0, found.1)
+            }
+            MatrixError::RaggedRows { row, expected, found } => {
+                write!(f, "row {} has {} entries, expected {}", row, found, expected)
+            }
+            MatrixError::Singular => write!(f, "matrix is singular"),
+        }
+    }
+}
+
+impl std::error::Error for MatrixError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Matrix<T>
+{
+    rows: usize,
+    cols: usize,
+    data: Vec<T>,
+}
+
+impl<T: Scalar> Matrix<T>
+{
+    pub fn zeros(rows: usize, cols: usize) -> Matrix<T>
+    {
+        Matrix { rows, cols, data: vec![T::zero(); rows * cols] }
+    }
+
+    pub fn identity(n: usize) -> Matrix<T>
+    {
+        let mut m = Matrix::zeros(n, n);
+        for i in 0..n { m[(i, i)] = T::one(); }
+        m
+    }
+
+    // row major: data[i * cols + j] is the entry in row i, column j
+    pub fn from_vec(rows: usize, cols: usize, data: Vec<T>) -> Result<Matrix<T>, MatrixError>
+    {
+        if data.len() != rows * cols {
+            return Err(MatrixError::DimensionMismatch { expected: (rows, cols), found: (data.len(), 1) });
+        }
+        Ok(Matrix { rows, cols, data })
+    }
+
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Result<Matrix<T>, MatrixError>
+    {
+        let cols = rows.first().map_or(0, |r| r.len());
+        if let Some((row, r)) = rows.iter().enumerate().find(|(_, r)| r.len() != cols) {
+            return Err(MatrixError::RaggedRows { row, expected: cols, found: r.len() });
+        }
+        Ok(Matrix { rows: rows.len(), cols, data: rows.into_iter().flatten().collect() })
+    }
+
+    pub fn rows(&self) -> usize { self.rows }
+
+    pub fn cols(&self) -> usize { self.cols }
+
+    pub fn is_square(&self) -> bool { self.rows == self.cols }
+
+    pub fn row(&self, i: usize) -> &[T] { &self.data[i * self.cols..(i + 1) * self.cols] }
+
+    fn check_square(&self) -> Result<usize, MatrixError>
+    {
+        if self.is_square() { Ok(self.rows) } else { Err(MatrixError::NotSquare { rows: self.rows, cols: self.cols }) }
+    }
+
+    fn check_same_size(&self, other: &Matrix<T>) -> Result<(), MatrixError>
+    {
+        if self.rows == other.rows && self.cols == other.cols {
+            Ok(())
+        } else {
+            Err(MatrixError::DimensionMismatch { expected: (self.rows, self.cols), found: (other.rows, other.cols) })
+        }
+    }
+
+    pub fn transpose(&self) -> Matrix<T>
+    {
+        let mut t = Matrix::zeros(self.cols, self.rows);
+        for i in 0..self.rows {
+            for j in 0..self.cols { t[(j, i)] = self[(i, j)]; }
+        }
+        t
+    }
+
+    // the adjoint A*, the transpose with every entry conjugated
+    pub fn conjugate_transpose(&self) -> Matrix<T>
+    {
+        let mut t = self.transpose();
+        for x in t.data.iter_mut() { *x = x.conj(); }
+        t
+    }
+
+    pub fn scale(&self, c: T) -> Matrix<T>
+    {
+        Matrix { rows: self.rows, cols: self.cols, data: self.data.iter().map(|x| *x * c).collect() }
+    }
+
+    pub fn mul_vec(&self, v: &[T]) -> Result<Vec<T>, MatrixError>
+    {
+        if v.len() != self.cols {
+            return Err(MatrixError::DimensionMismatch { expected: (self.cols, 1), found: (v.len(), 1) });
+        }
+        Ok((0..self.rows).map(|i| dot(self.row(i), v)).collect())
+    }
+
+    // the largest |entry|, 0 for an empty matrix
+    pub fn max_abs(&self) -> f64
+    {
+        self.data.iter().map(|x| x.modulus()).fold(0.0, f64::max)
+    }
+
+    /////////////////////////////////////
+
+    pub fn lu(&self) -> Result<Lu<T>, MatrixError>
+    {
+        let n = self.check_square()?;
+        let tolerance = n as f64 * f64::EPSILON * self.max_abs();
+        let mut a = self.clone();
+        let mut perm: Vec<usize> = (0..n).collect();
+        let mut swaps = 0;
+
+        for k in 0..n {
+            // partial pivoting: the largest entry left in the column, so no
+            // multiplier is bigger than 1
+            let p = (k..n).max_by(|&i, &j| a[(i, k)].modulus().total_cmp(&a[(j, k)].modulus())).unwrap();
+            if a[(p, k)].modulus() <= tolerance { return Err(MatrixError::Singular); }
+            if p != k {
+                a.swap_rows(p, k);
+                perm.swap(p, k);
+                swaps += 1;
+            }
+
+            let pivot = a[(k, k)];
+            for i in k + 1..n {
+                let factor = a[(i, k)] / pivot;
+                a[(i, k)] = factor;
+                for j in k + 1..n {
+                    a[(i, j)] = a[(i, j)] - factor * a[(k, j)];
+                }
+            }
+        }
+
+        Ok(Lu { lu: a, perm, sign: if swaps % 2 == 0 { 1.0 } else { -1.0 } })
+    }
+
+    fn swap_rows(&mut self, a: usize, b: usize)
+    {
+        for j in 0..self.cols { self.data.swap(a * self.cols + j, b * self.cols + j); }
+    }
+
+    // 0 for a singular matrix, an error only when it isn't square
+    pub fn det(&self) -> Result<T, MatrixError>
+    {
+        match self.lu() {
+            Ok(lu) => Ok(lu.det()),
+            Err(MatrixError::Singular) => Ok(T::zero()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn inverse(&self) -> Result<Matrix<T>, MatrixError>
+    {
+        self.lu()?.inverse()
+    }
+
+    // x with A x = b
+    pub fn solve(&self, b: &[T]) -> Result<Vec<T>, MatrixError>
+    {
+        self.lu()?.solve(b)
+    }
+
+    // householder QR of any m x n matrix: Q is m x m and unitary, R is m x n
+    // and upper triangular. never fails, rank deficiency shows up as zeros on
+    // the diagonal of R
+    pub fn qr(&self) -> Qr<T>
+    {
+        let (m, n) = (self.rows, self.cols);
+        let mut r = self.clone();
+        let mut q = Matrix::identity(m);
+
+        for k in 0..n.min(m.saturating_sub(1)) {
+            // v = x - alpha e1 with |alpha| = |x| and the phase opposite to
+            // x[0], so nothing cancels in v[0]
+            let norm = (k..m).map(|i| r[(i, k)].modulus().powi(2)).sum::<f64>().sqrt();
+            if norm == 0.0 { continue; }
+            let x0 = r[(k, k)];
+            let phase = if x0.modulus() == 0.0 { T::one() } else { x0 / T::from_real(x0.modulus()) };
+            let alpha = -(phase * T::from_real(norm));
+
+            let mut v: Vec<T> = (k..m).map(|i| r[(i, k)]).collect();
+            v[0] = v[0] - alpha;
+            let v_norm_sqr: f64 = v.iter().map(|x| x.modulus().powi(2)).sum();
+            if v_norm_sqr == 0.0 { continue; }
+            let beta = T::from_real(2.0 / v_norm_sqr);
+
+            // H = I - beta v v*, R <- H R and Q <- Q H (H is its own inverse)
+            for j in 0..n {
+                let s = (k..m).fold(T::zero(), |acc, i| acc + v[i - k].conj() * r[(i, j)]) * beta;
+                for i in k..m { r[(i, j)] = r[(i, j)] - v[i - k] * s; }
+            }
+            for i in 0..m {
+                let s = (k..m).fold(T::zero(), |acc, j| acc + q[(i, j)] * v[j - k]) * beta;
+                for j in k..m { q[(i, j)] = q[(i, j)] - s * v[j - k].conj(); }
+            }
+        }
+
+        // below the diagonal only rounding errors are left
+        for j in 0..n {
+            for i in j + 1..m { r[(i, j)] = T::zero(); }
+        }
+        Qr { q, r }
+    }
+}
+
+fn dot<T: Scalar>(a: &[T], b: &[T]) -> T
+{
+    a.iter().zip(b).fold(T::zero(), |acc, (x, y)| acc + *x * *y)
+}
+
+impl<T> Index<(usize, usize)> for Matrix<T>
+{
+    type Output = T;
+
+    fn index(&self, (i, j): (usize, usize)) -> &T
+    {
+        assert!(i < self.rows && j < self.cols, "index ({}, {}) out of range for a {}x{} matrix", i, j, self.rows, self.cols);
+        &self.data[i * self.cols + j]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Matrix<T>
+{
+    fn index_mut(&mut self, (i, j): (usize, usize)) -> &mut T
+    {
+        assert!(i < self.rows && j < self.cols, "index ({}, {}) out of range for a {}x{} matrix", i, j, self.rows, self.cols);
+        &mut self.data[i * self.cols + j]
+    }
+}
+
+/////////////////////////////////////
+
+// L and U packed in one matrix (L has ones on its diagonal, which aren't
+// stored), with P A = L U. perm[i] is the row of A that ended up in row i
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lu<T>
+{
+    lu: Matrix<T>,
+    perm: Vec<usize>,
+    // the sign of the permutation
+    sign: f64,
+}
+
+impl<T: Scalar> Lu<T>
+{
+    pub fn lower(&self) -> Matrix<T>
+    {
+        let n = self.lu.rows;
+        let mut l = Matrix::identity(n);
+        for i in 0..n {
+            for j in 0..i { l[(i, j)] = self.lu[(i, j)]; }
+        }
+        l
+    }
+
+    pub fn upper(&self) -> Matrix<T>
+    {
+        let n = self.lu.rows;
+        let mut u = Matrix::zeros(n, n);
+        for i in 0..n {
+            for j in i..n { u[(i, j)] = self.lu[(i, j)]; }
+        }
+        u
+    }
+
+    pub fn permutation(&self) -> &[usize] { &self.perm }
+
+    pub fn det(&self) -> T
+    {
+        (0..self.lu.rows).fold(T::from_real(self.sign), |acc, i| acc * self.lu[(i, i)])
+    }
+
+    pub fn solve(&self, b: &[T]) -> Result<Vec<T>, MatrixError>
+    {
+        let n = self.lu.rows;
+        if b.len() != n {
+            return Err(MatrixError::DimensionMismatch { expected: (n, 1), found: (b.len(), 1) });
+        }
+
+        // L y = P b, then U x = y
+        let mut x: Vec<T> = self.perm.iter().map(|&p| b[p]).collect();
+        for i in 0..n {
+            for j in 0..i { x[i] = x[i] - self.lu[(i, j)] * x[j]; }
+        }
+        for i in (0..n).rev() {
+            for j in i + 1..n { x[i] = x[i] - self.lu[(i, j)] * x[j]; }
+            x[i] = x[i] / self.lu[(i, i)];
+        }
+        Ok(x)
+    }
+
+    // one solve per column of b
+    pub fn solve_matrix(&self, b: &Matrix<T>) -> Result<Matrix<T>, MatrixError>
+    {
+        if b.rows != self.lu.rows {
+            return Err(MatrixError::DimensionMismatch { expected: (self.lu.rows, b.cols), found: (b.rows, b.cols) });
+        }
+        let bt = b.transpose();
+        let mut xt = Matrix::zeros(b.cols, b.rows);
+        for j in 0..b.cols {
+            let column = self.solve(bt.row(j))?;
+            xt.data[j * b.rows..(j + 1) * b.rows].copy_from_slice(&column);
+        }
+        Ok(xt.transpose())
+    }
+
+    pub fn inverse(&self) -> Result<Matrix<T>, MatrixError>
+    {
+        self.solve_matrix(&Matrix::identity(self.lu.rows))
+    }
+}
+
+// A = Q R
+#[derive(Debug, Clone, PartialEq)]
+pub struct Qr<T>
+{
+    pub q: Matrix<T>,
+    pub r: Matrix<T>,
+}
+
+impl<T: Scalar> Qr<T>
+{
+    // the x minimizing |A x - b|, for A with at least as many rows as columns
+    // and full column rank: R x = Q* b on the top n rows
+    pub fn solve_least_squares(&self, b: &[T]) -> Result<Vec<T>, MatrixError>
+    {
+        let (m, n) = (self.r.rows, self.r.cols);
+        if m < n {
+            return Err(MatrixError::DimensionMismatch { expected: (n, n), found: (m, n) });
+        }
+        let y = self.q.conjugate_transpose().mul_vec(b)?;
+
+        let tolerance = m.max(n) as f64 * f64::EPSILON * self.r.max_abs();
+        let mut x = y[..n].to_vec();
+        for i in (0..n).rev() {
+            if self.r[(i, i)].modulus() <= tolerance { return Err(MatrixError::Singular); }
+            for j in i + 1..n { x[i] = x[i] - self.r[(i, j)] * x[j]; }
+            x[i] = x[i] / self.r[(i, i)];
+        }
+        Ok(x)
+    }
+}
+
+/////////////////////////////////////
+
+// the operators check the dimensions too, so they return a Result: (&a * &b)?
+
+impl<'a, T: Scalar> Add<&'a Matrix<T>> for &'a Matrix<T>
+{
+    type Output = Result<Matrix<T>, MatrixError>;
+
+    fn add(self, other: &Matrix<T>) -> Result<Matrix<T>, MatrixError>
+    {
+        self.check_same_size(other)?;
+        let data = self.data.iter().zip(&other.data).map(|(a, b)| *a + *b).collect();
+        Ok(Matrix { rows: self.rows, cols: self.cols, data })
+    }
+}
+
+impl<'a, T: Scalar> Sub<&'a Matrix<T>> for &'a Matrix<T>
+{
+    type Output = Result<Matrix<T>, MatrixError>;
+
+    fn sub(self, other: &Matrix<T>) -> Result<Matrix<T>, MatrixError>
+    {
+        self.check_same_size(other)?;
+        let data = self.data.iter().zip(&other.data).map(|(a, b)| *a - *b).collect();
+        Ok(Matrix { rows: self.rows, cols: self.cols, data })
+    }
+}
+
+impl<'a, T: Scalar> Mul<&'a Matrix<T>> for &'a Matrix<T>
+{
+    type Output = Result<Matrix<T>, MatrixError>;
+
+    fn mul(self, other: &Matrix<T>) -> Result<Matrix<T>, MatrixError>
+    {
+        if self.cols != other.rows {
+            return Err(MatrixError::DimensionMismatch { expected: (self.cols, other.cols), found: (other.rows, other.cols) });
+        }
+        // i-k-j order walks both matrices along their rows
+        let mut product = Matrix::zeros(self.rows, other.cols);
+        for i in 0..self.rows {
+            for k in 0..self.cols {
+                let a = self[(i, k)];
+                for j in 0..other.cols {
+                    product.data[i * other.cols + j] = product.data[i * other.cols + j] + a * other[(k, j)];
+                }
+            }
+        }
+        Ok(product)
+    }
+}
+
+impl<T: Scalar> Neg for &Matrix<T>
+{
+    type Output = Matrix<T>;
+
+    fn neg(self) -> Matrix<T> { self.scale(-T::one()) }
+}
+
+// rows on their own lines, the format flags go to every entry
+impl<T: Scalar + fmt::Display> fmt::Display for Matrix<T>
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        for i in 0..self.rows {
+            for (j, x) in self.row(i).iter().enumerate() {
+                if j > 0 { f.write_str("  ")?; }
+                fmt::Display::fmt(x, f)?;
+            }
+            if i + 1 < self.rows { writeln!(f)?; }
+        }
+        Ok(())
+    }
+}
+
+/////////////////////////////////////
+
+pub fn main()
+{
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    let close = |a: &Matrix<Complex<f64>>, b: &Matrix<Complex<f64>>| (a - b).unwrap().max_abs() < 1e-10;
+
+    // 2x + y - z = 8, -3x - y + 2z = -11, -2x + y + 2z = -3 has x = 2, y = 3, z = -1
+    let a = Matrix::from_rows(vec![vec![2.0, 1.0, -1.0], vec![-3.0, -1.0, 2.0], vec![-2.0, 1.0, 2.0]]).unwrap();
+    let x = a.solve(&[8.0, -11.0, -3.0]).unwrap();
+    println!("x = {:?}", x);
+    assert!(x.iter().zip([2.0, 3.0, -1.0]).all(|(a, b)| (a - b).abs() < 1e-12));
+    assert!((a.det().unwrap() - -1.0).abs() < 1e-12);
+
+    let inverse = a.inverse().unwrap();
+    println!("A^-1 =\n{:8.3}", inverse);
+    assert!((&(&a * &inverse).unwrap() - &Matrix::identity(3)).unwrap().max_abs() < 1e-12);
+
+    // errors instead of panics
+    let singular = Matrix::from_rows(vec![vec![1.0, 2.0], vec![2.0, 4.0]]).unwrap();
+    assert_eq!(singular.inverse(), Err(MatrixError::Singular));
+    assert_eq!(singular.det(), Ok(0.0));
+    assert!(matches!(&a * &singular, Err(MatrixError::DimensionMismatch { .. })));
+    assert!(matches!(Matrix::<f64>::zeros(2, 3).lu(), Err(MatrixError::NotSquare { rows: 2, cols: 3 })));
+    assert!(matches!(Matrix::from_rows(vec![vec![1.0], vec![1.0, 2.0]]), Err(MatrixError::RaggedRows { row: 1, .. })));
+    println!("{}", a.solve(&[1.0]).unwrap_err());
+
+    // random complex matrices: P A = L U, A = Q R with Q unitary, A x = b
+    let mut rng = StdRng::seed_from_u64(36);
+    let mut random = |rows: usize, cols: usize| {
+        let data = (0..rows * cols).map(|_| Complex::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0))).collect();
+        Matrix::from_vec(rows, cols, data).unwrap()
+    };
+    for n in [1, 2, 5, 12] {
+        let a = random(n, n);
+        let lu = a.lu().unwrap();
+        let mut pa = Matrix::zeros(n, n);
+        for (i, &p) in lu.permutation().iter().enumerate() {
+            for j in 0..n { pa[(i, j)] = a[(p, j)]; }
+        }
+        assert!(close(&(&lu.lower() * &lu.upper()).unwrap(), &pa), "PA = LU for n = {}", n);
+
+        let qr = a.qr();
+        assert!(close(&(&qr.q * &qr.r).unwrap(), &a), "A = QR for n = {}", n);
+        assert!(close(&(&qr.q.conjugate_transpose() * &qr.q).unwrap(), &Matrix::identity(n)), "Q*Q = I for n = {}", n);
+
+        let b: Vec<Complex<f64>> = random(n, 1).data;
+        let x = a.solve(&b).unwrap();
+        let back = a.mul_vec(&x).unwrap();
+        assert!(back.iter().zip(&b).all(|(p, q)| (*p - *q).abs() < 1e-10));
+
+        // det(A*) is the conjugate of det(A)
+        let d = a.det().unwrap();
+        assert!((a.conjugate_transpose().det().unwrap() - d.conj()).abs() < 1e-10);
+    }
+
+    // least squares line through noisy points on y = 2x + 1
+    let xs: Vec<f64> = (0..20).map(|i| i as f64 / 4.0).collect();
+    let ys: Vec<f64> = xs.iter().map(|x| 2.0 * x + 1.0 + rng.gen_range(-0.05..0.05)).collect();
+    let design = Matrix::from_rows(xs.iter().map(|x| vec![*x, 1.0]).collect()).unwrap();
+    let fit = design.qr().solve_least_squares(&ys).unwrap();
+    println!("fit: y = {:.4} x + {:.4}", fit[0], fit[1]);
+    assert!((fit[0] - 2.0).abs() < 0.05 && (fit[1] - 1.0).abs() < 0.05);
+}
@@ -0,0 +1,188 @@
+// monte carlo over shapes: uniform points inside a shape and area estimates
+// for unions and intersections that have no closed form
+
+use crate::num::Float;
+use crate::{Circle, Point, Rect, Shape};
+
+use rand::Rng;
+
+// a value estimated from random samples together with its standard error,
+// the sampling below produces f64 ones
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Estimate<T = f64>
+{
+    pub value: T,
+    pub std_error: T,
+    pub samples: usize,
+}
+
+impl<T: Float> Estimate<T>
+{
+    // interval value +- z standard errors, z = 1.96 is the usual 95%
+    pub fn confidence_interval(&self, z: T) -> (T, T)
+    {
+        (self.value - z * self.std_error, self.value + z * self.std_error)
+    }
+
+    pub fn interval_95(&self) -> (T, T) { self.confidence_interval(T::from_f64(1.96)) }
+
+    pub fn interval_99(&self) -> (T, T) { self.confidence_interval(T::from_f64(2.576)) }
+
+    // how many standard errors away from the estimate `truth` is
+    pub fn z_score(&self, truth: T) -> T
+    {
+        if self.std_error.is_zero() {
+            if self.value == truth { T::zero() } else { T::infinity() }
+        } else {
+            (self.value - truth) / self.std_error
+        }
+    }
+}
+
+fn random_point_in<R: Rng + ?Sized>(rect: &Rect, rng: &mut R) -> Point
+{
+    Point {
+        x: rect.min.x + rng.gen::<f64>() * rect.width(),
+        y: rect.min.y + rng.gen::<f64>() * rect.height(),
+    }
+}
+
+// rejection sampling: throw points in the bounding box until one lands inside.
+// gives up after max_attempts, which only matters for shapes with (almost) no area
+pub fn sample_inside<R: Rng + ?Sized>(shape: &dyn Shape, rng: &mut R, max_attempts: usize) -> Option<Point>
+{
+    let bbox = shape.bounding_box();
+    (0..max_attempts)
+        .map(|_| random_point_in(&bbox, rng))
+        .find(|p| shape.contains(*p))
+}
+
+// n uniform points inside the shape, fewer if sampling keeps missing
+pub fn samples_inside<R: Rng + ?Sized>(shape: &dyn Shape, rng: &mut R, n: usize) -> Vec<Point>
+{
+    const ATTEMPTS_PER_POINT: usize = 10_000;
+    (0..n).map_while(|_| sample_inside(shape, rng, ATTEMPTS_PER_POINT)).collect()
+}
+
+// hit or miss: the fraction of points of `region` that pass `inside`,
+// scaled by the area of the region. the hits are binomial, so the
+// standard error is area * sqrt(p * (1 - p) / n)
+fn hit_or_miss<R, F>(region: &Rect, samples: usize, rng: &mut R, inside: F) -> Estimate
+where
+    R: Rng + ?Sized,
+    F: Fn(Point) -> bool,
+{
+    let box_area = region.width() * region.height();
+    if samples == 0 || box_area == 0.0 {
+        return Estimate { value: 0.0, std_error: 0.0, samples };
+    }
+
+    let hits = (0..samples).filter(|_| inside(random_point_in(region, rng))).count();
+    let p = hits as f64 / samples as f64;
+    Estimate {
+        value: box_area * p,
+        std_error: box_area * (p * (1.0 - p) / samples as f64).sqrt(),
+        samples,
+    }
+}
+
+pub fn estimate_area<R: Rng + ?Sized>(shape: &dyn Shape, samples: usize, rng: &mut R) -> Estimate
+{
+    hit_or_miss(&shape.bounding_box(), samples, rng, |p| shape.contains(p))
+}
+
+// area covered by at least one of the shapes
+pub fn estimate_union_area<R: Rng + ?Sized>(shapes: &[&dyn Shape], samples: usize, rng: &mut R) -> Estimate
+{
+    let region = match shapes.iter().map(|s| s.bounding_box()).reduce(Rect::union) {
+        Some(region) => region,
+        None => return Estimate { value: 0.0, std_error: 0.0, samples: 0 },
+    };
+    hit_or_miss(&region, samples, rng, |p| shapes.iter().any(|s| s.contains(p)))
+}
+
+// area covered by all of the shapes
+pub fn estimate_intersection_area<R: Rng + ?Sized>(shapes: &[&dyn Shape], samples: usize, rng: &mut R) -> Estimate
+{
+    let mut boxes = shapes.iter().map(|s| s.bounding_box());
+    let first = match boxes.next() {
+        Some(first) => first,
+        None => return Estimate { value: 0.0, std_error: 0.0, samples: 0 },
+    };
+    // if the boxes don't overlap the shapes can't either, the answer is exactly 0
+    let region = match boxes.try_fold(first, Rect::intersection) {
+        Some(region) => region,
+        None => return Estimate { value: 0.0, std_error: 0.0, samples },
+    };
+    hit_or_miss(&region, samples, rng, |p| shapes.iter().all(|s| s.contains(p)))
+}
+
+// the unit circle has area pi, and its box has area 4
+pub fn estimate_pi<R: Rng + ?Sized>(samples: usize, rng: &mut R) -> Estimate
+{
+    estimate_area(&Circle { radius: 1.0 }, samples, rng)
+}
+
+/////////////////////////////////////
+
+pub fn main()
+{
+    use crate::{Square, Translated};
+
+    let mut rng = rand::thread_rng();
+
+    let pi = estimate_pi(1_000_000, &mut rng);
+    let (lo, hi) = pi.interval_95();
+    println!("pi ~ {} +- {} (95%: {} .. {})", pi.value, pi.std_error, lo, hi);
+
+    // two unit circles one radius apart
+    let left = Translated { shape: Circle { radius: 1.0 }, offset: Point { x: -0.5, y: 0.0 } };
+    let right = Translated { shape: Circle { radius: 1.0 }, offset: Point { x: 0.5, y: 0.0 } };
+    let both = estimate_intersection_area(&[&left, &right], 200_000, &mut rng);
+    println!("lens: {:?}", both.interval_99());
+    let either = estimate_union_area(&[&left, &right], 200_000, &mut rng);
+    println!("union: {:?}", either.interval_99());
+
+    let square = Square { side: 1.0 };
+    let points = samples_inside(&square, &mut rng, 5);
+    println!("points in the square: {:?}", points);
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::Translated;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    // statistical check, seeded so it can't flake: with a correct sampler the
+    // estimate is farther than 4 standard errors from pi only about once in 16000 runs
+    #[test]
+    fn pi_within_four_standard_errors()
+    {
+        let mut rng = StdRng::seed_from_u64(2024);
+        let pi = estimate_pi(1_000_000, &mut rng);
+        assert!(pi.z_score(std::f64::consts::PI).abs() < 4.0, "pi estimate is off: {:?}", pi);
+    }
+
+    // two unit circles one radius apart: the lens in the middle has area
+    // 2pi/3 - sqrt(3)/2 and the union is 2pi minus that. the exact areas lie
+    // inside the 99% intervals of the estimates
+    #[test]
+    fn lens_and_union_of_two_circles()
+    {
+        let mut rng = StdRng::seed_from_u64(2024);
+        let left = Translated { shape: Circle { radius: 1.0 }, offset: Point { x: -0.5, y: 0.0 } };
+        let right = Translated { shape: Circle { radius: 1.0 }, offset: Point { x: 0.5, y: 0.0 } };
+        let lens = 2.0 * std::f64::consts::PI / 3.0 - 3f64.sqrt() / 2.0;
+        let union = 2.0 * std::f64::consts::PI - lens;
+
+        let both = estimate_intersection_area(&[&left, &right], 200_000, &mut rng);
+        let (lo, hi) = both.interval_99();
+        assert!(lo <= lens && lens <= hi, "lens estimate is off: {:?}", both);
+        let either = estimate_union_area(&[&left, &right], 200_000, &mut rng);
+        let (lo, hi) = either.interval_99();
+        assert!(lo <= union && union <= hi, "union estimate is off: {:?}", either);
+    }
+}
@@ -0,0 +1,319 @@
+// the numeric traits generic code in this crate is written against
+//
+//   Zero, One   the two identities
+//   Num         + - * / and both identities, what Complex<T> and Summable need
+//   Signed      Num with negation, abs and signum
+//   Integer     Num with an order and division with remainder
+//   Float       Signed plus the real functions of f32 and f64
+//
+// the primitives implement all that applies to them (the unsigned ones aren't
+// Signed), the crate's own number types implement their part next to their
+// definitions: BigInt, Rational and Complex in their files, Dual in dual.rs
+//
+// the methods are spelled like the inherent ones of the primitives, which win
+// when both are in scope, so f64 code keeps calling the std functions
+
+use std::ops::{Add, Div, Mul, Neg, Rem, Sub};
+
+pub trait Zero: Sized + Add<Output = Self>
+{
+    fn zero() -> Self;
+    fn is_zero(&self) -> bool;
+}
+
+pub trait One: Sized + Mul<Output = Self>
+{
+    fn one() -> Self;
+}
+
+// nothing to implement, anything with the operators and identities is a Num
+pub trait Num: Clone + PartialEq + Zero + One + Sub<Output = Self> + Div<Output = Self> {}
+
+impl<T> Num for T where T: Clone + PartialEq + Zero + One + Sub<Output = T> + Div<Output = T> {}
+
+pub trait Signed: Num + Neg<Output = Self>
+{
+    fn abs(&self) -> Self;
+    // -1, 0 or 1 (floats keep the sign of a zero and pass NAN through)
+    fn signum(&self) -> Self;
+    fn is_negative(&self) -> bool;
+    fn is_positive(&self) -> bool;
+}
+
+pub trait Integer: Num + Ord + Rem<Output = Self>
+{
+    // / and % round toward zero, these round toward minus infinity, so the
+    // remainder has the sign of the divisor
+    fn div_floor(&self, other: &Self) -> Self;
+    fn mod_floor(&self, other: &Self) -> Self;
+    // never negative, gcd(0, 0) = 0
+    fn gcd(&self, other: &Self) -> Self;
+    // never negative, 0 if either is
+    fn lcm(&self, other: &Self) -> Self;
+    fn is_even(&self) -> bool;
+    fn is_odd(&self) -> bool { !self.is_even() }
+}
+
+pub trait Float: Signed + Copy + PartialOrd
+{
+    fn nan() -> Self;
+    fn infinity() -> Self;
+    fn neg_infinity() -> Self;
+    fn epsilon() -> Self;
+    fn pi() -> Self;
+    // constants in generic code: T::from_f64(0.5)
+    fn from_f64(x: f64) -> Self;
+    fn to_f64(self) -> f64;
+
+    fn is_nan(self) -> bool;
+    fn is_finite(self) -> bool;
+    fn floor(self) -> Self;
+    fn ceil(self) -> Self;
+    fn round(self) -> Self;
+    fn min(self, other: Self) -> Self;
+    fn max(self, other: Self) -> Self;
+    fn copysign(self, sign: Self) -> Self;
+
+    fn sqrt(self) -> Self;
+    fn exp(self) -> Self;
+    fn ln(self) -> Self;
+    fn powi(self, n: i32) -> Self;
+    fn powf(self, n: Self) -> Self;
+    fn hypot(self, other: Self) -> Self;
+    fn sin(self) -> Self;
+    fn cos(self) -> Self;
+    fn tan(self) -> Self;
+    fn asin(self) -> Self;
+    fn acos(self) -> Self;
+    fn atan(self) -> Self;
+    fn atan2(self, other: Self) -> Self;
+    fn sinh(self) -> Self;
+    fn cosh(self) -> Self;
+    fn tanh(self) -> Self;
+}
+
+/////////////////////////////////////
+
+macro_rules! zero_one_impl {
+    ($($t:ty),*) => {$(
+        impl Zero for $t
+        {
+            fn zero() -> $t { 0 as $t }
+            fn is_zero(&self) -> bool { *self == 0 as $t }
+        }
+
+        impl One for $t
+        {
+            fn one() -> $t { 1 as $t }
+        }
+    )*};
+}
+
+zero_one_impl!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64);
+
+macro_rules! signed_impl {
+    ($($t:ty),*) => {$(
+        impl Signed for $t
+        {
+            fn abs(&self) -> $t { <$t>::abs(*self) }
+            fn signum(&self) -> $t { <$t>::signum(*self) }
+            fn is_negative(&self) -> bool { *self < 0 as $t }
+            fn is_positive(&self) -> bool { *self > 0 as $t }
+        }
+    )*};
+}
+
+signed_impl!(i8, i16, i32, i64, i128, isize, f32, f64);
+
+// the gcd is worked out on the unsigned magnitudes, so MIN works as long as
+// the answer fits: gcd(MIN, 0) and gcd(MIN, MIN) panic like MIN.abs() does
+macro_rules! integer_signed_impl {
+    ($($t:ty, $u:ty);*) => {$(
+        impl Integer for $t
+        {
+            fn div_floor(&self, other: &$t) -> $t
+            {
+                let (q, r) = (*self / *other, *self % *other);
+                if r != 0 && (r < 0) != (*other < 0) { q - 1 } else { q }
+            }
+
+            fn mod_floor(&self, other: &$t) -> $t
+            {
+                let r = *self % *other;
+                if r != 0 && (r < 0) != (*other < 0) { r + *other } else { r }
+            }
+
+            fn gcd(&self, other: &$t) -> $t
+            {
+                let g = self.unsigned_abs().gcd(&other.unsigned_abs());
+                <$t>::try_from(g).expect("gcd doesn't fit in the signed type")
+            }
+
+            fn lcm(&self, other: &$t) -> $t
+            {
+                if *self == 0 || *other == 0 { return 0; }
+                (*self / self.gcd(other) * *other).abs()
+            }
+
+            fn is_even(&self) -> bool { *self % 2 == 0 }
+        }
+    )*};
+}
+
+macro_rules! integer_unsigned_impl {
+    ($($t:ty),*) => {$(
+        impl Integer for $t
+        {
+            fn div_floor(&self, other: &$t) -> $t { *self / *other }
+
+            fn mod_floor(&self, other: &$t) -> $t { *self % *other }
+
+            fn gcd(&self, other: &$t) -> $t
+            {
+                let (mut a, mut b) = (*self, *other);
+                while b != 0 { (a, b) = (b, a % b); }
+                a
+            }
+
+            fn lcm(&self, other: &$t) -> $t
+            {
+                if *self == 0 || *other == 0 { return 0; }
+                *self / self.gcd(other) * *other
+            }
+
+            fn is_even(&self) -> bool { *self % 2 == 0 }
+        }
+    )*};
+}
+
+integer_signed_impl!(i8, u8; i16, u16; i32, u32; i64, u64; i128, u128; isize, usize);
+integer_unsigned_impl!(u8, u16, u32, u64, u128, usize);
+
+macro_rules! float_impl {
+    ($($t:ident),*) => {$(
+        impl Float for $t
+        {
+            fn nan() -> $t { $t::NAN }
+            fn infinity() -> $t { $t::INFINITY }
+            fn neg_infinity() -> $t { $t::NEG_INFINITY }
+            fn epsilon() -> $t { $t::EPSILON }
+            fn pi() -> $t { std::$t::consts::PI }
+            fn from_f64(x: f64) -> $t { x as $t }
+            fn to_f64(self) -> f64 { self as f64 }
+
+            fn is_nan(self) -> bool { $t::is_nan(self) }
+            fn is_finite(self) -> bool { $t::is_finite(self) }
+            fn floor(self) -> $t { $t::floor(self) }
+            fn ceil(self) -> $t { $t::ceil(self) }
+            fn round(self) -> $t { $t::round(self) }
+            fn min(self, other: $t) -> $t { $t::min(self, other) }
+            fn max(self, other: $t) -> $t { $t::max(self, other) }
+            fn copysign(self, sign: $t) -> $t { $t::copysign(self, sign) }
+
+            fn sqrt(self) -> $t { $t::sqrt(self) }
+            fn exp(self) -> $t { $t::exp(self) }
+            fn ln(self) -> $t { $t::ln(self) }
+            fn powi(self, n: i32) -> $t { $t::powi(self, n) }
+            fn powf(self, n: $t) -> $t { $t::powf(self, n) }
+            fn hypot(self, other: $t) -> $t { $t::hypot(self, other) }
+            fn sin(self) -> $t { $t::sin(self) }
+            fn cos(self) -> $t { $t::cos(self) }
+            fn tan(self) -> $t { $t::tan(self) }
+            fn asin(self) -> $t { $t::asin(self) }
+            fn acos(self) -> $t { $t::acos(self) }
+            fn atan(self) -> $t { $t::atan(self) }
+            fn atan2(self, other: $t) -> $t { $t::atan2(self, other) }
+            fn sinh(self) -> $t { $t::sinh(self) }
+            fn cosh(self) -> $t { $t::cosh(self) }
+            fn tanh(self) -> $t { $t::tanh(self) }
+        }
+    )*};
+}
+
+float_impl!(f32, f64);
+
+/////////////////////////////////////
+
+pub fn main()
+{
+    use crate::bigint::BigInt;
+    use crate::monte_carlo::Estimate;
+    use crate::rational::Rational;
+    use crate::{Complex, Line, Point, Rect, Summable};
+
+    // one function, every integer type
+    fn check_floor<T: Integer + Signed + From<i8> + std::fmt::Debug>()
+    {
+        let t = |x: i8| T::from(x);
+        assert_eq!(t(-7).div_floor(&t(2)), t(-4));
+        assert_eq!(t(-7).mod_floor(&t(2)), t(1));
+        assert_eq!(t(7).mod_floor(&t(-2)), t(-1));
+        assert_eq!(t(-7) / t(2), t(-3));
+        assert_eq!(t(-12).gcd(&t(18)), t(6));
+        assert_eq!(t(-4).lcm(&t(6)), t(12));
+        assert!(t(-3).is_odd() && t(0).is_even());
+        assert_eq!(t(-5).signum(), t(-1));
+    }
+    check_floor::<i8>();
+    check_floor::<i64>();
+    check_floor::<i128>();
+    check_floor::<BigInt>();
+    assert_eq!(i32::MIN.gcd(&6), 2);
+    assert_eq!(48u8.gcd(&18), 6);
+
+    // and every float type
+    fn newton_sqrt<T: Float>(x: T) -> T
+    {
+        let mut guess = x.max(T::one());
+        for _ in 0..60 { guess = (guess + x / guess) * T::from_f64(0.5); }
+        guess
+    }
+    assert!((newton_sqrt(2.0f32) - 2f32.sqrt()).abs() <= f32::EPSILON);
+    assert!((newton_sqrt(1e10f64) - 1e5).abs() < 1e-9);
+
+    // the whole of Float, through identities that hold in either precision
+    fn check_float<T: Float + std::fmt::Debug>()
+    {
+        let t = T::from_f64;
+        let close = |a: T, b: T| (a - b).abs().to_f64() < 1e-5;
+
+        assert!(T::nan().is_nan() && !T::nan().is_finite() && !T::infinity().is_finite());
+        assert!(T::neg_infinity() < t(-1e30) && T::one() + T::epsilon() > T::one());
+        assert_eq!((t(-1.5).floor(), t(-1.5).ceil(), t(-1.5).round()), (t(-2.0), t(-1.0), t(-2.0)));
+        assert_eq!((t(1.0).min(t(2.0)), t(1.0).max(t(2.0))), (t(1.0), t(2.0)));
+        assert_eq!(t(3.0).copysign(t(-0.0)), t(-3.0));
+
+        assert!(close(t(2.0).ln().exp(), t(2.0)) && close(t(2.0).powi(10), t(1024.0)));
+        assert!(close(t(2.0).powf(t(0.5)), t(2.0).sqrt()) && close(t(3.0).hypot(t(4.0)), t(5.0)));
+
+        let x = t(0.5);
+        assert!(close(x.sin().powi(2) + x.cos().powi(2), T::one()) && close(x.tan(), x.sin() / x.cos()));
+        assert!(close(x.sin().asin(), x) && close(x.cos().acos(), x) && close(x.tan().atan(), x));
+        assert!(close(T::one().atan2(T::one()), T::pi() / t(4.0)) && close(T::pi().sin(), T::zero()));
+        assert!(close(x.cosh().powi(2) - x.sinh().powi(2), T::one()) && close(x.tanh(), x.sinh() / x.cosh()));
+    }
+    check_float::<f32>();
+    check_float::<f64>();
+
+    // Summable is written once for any Num
+    println!("sum = {}", vec![1.5, 2.5, 3.0].sum());
+    assert_eq!(vec![Complex::new(1, 2), Complex::new(3, -4)].sum(), Complex::new(4, -2));
+    let halves: Vec<Rational<i64>> = (1..=4).map(|k| Rational::new(1, 1 << k)).collect();
+    assert_eq!(halves.sum(), Rational::new(15, 16));
+    let big: Vec<BigInt> = vec![BigInt::from(i32::MAX); 3];
+    assert_eq!(big.sum(), BigInt::from(3 * i32::MAX as i64));
+
+    // so are the geometry types: integer rectangles for pixels, f32 lines
+    let a = Rect::new(Point { x: 0, y: 10 }, Point { x: 10, y: 0 });
+    let b = Rect::new(Point { x: 5, y: 5 }, Point { x: 20, y: 20 });
+    let overlap = a.intersection(b).unwrap();
+    assert_eq!((overlap.width(), overlap.height()), (5, 5));
+    assert_eq!(a.union(b).max, Point { x: 20, y: 20 });
+    assert_eq!(Line { start: Point { x: 0.0f32, y: 0.0 }, end: Point { x: 3.0, y: 4.0 } }.len(), 5.0);
+
+    // and the statistics
+    let estimate = Estimate { value: 2.0f32, std_error: 0.5, samples: 100 };
+    assert_eq!(estimate.confidence_interval(2.0), (1.0, 3.0));
+    assert_eq!(estimate.z_score(1.0), 2.0);
+    println!("numeric traits ok");
+}
@@ -0,0 +1,277 @@
+// primes, factorization and modular arithmetic on machine integers
+//
+// is_prime is miller rabin with a fixed set of bases, which is a proof (not a
+// probability) for every u64. factor splits what trial division leaves over
+// with pollard's rho in brent's version. products mod m go through u128, so
+// nothing here overflows for any u64 modulus
+
+use crate::num::Integer;
+
+// with these bases miller rabin has no false positives below 3.3 * 10^24,
+// which covers every u64 and then some
+const WITNESSES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+/////////////////////////////////////
+
+// is_composite[i] for 0..=n, false for the primes (and 0 and 1 are marked
+// composite even though they aren't, they just aren't prime either)
+pub fn sieve(n: usize) -> Vec<bool>
+{
+    let mut composite = vec![false; n + 1];
+    for c in composite.iter_mut().take(2) { *c = true; }
+    let mut p = 2;
+    while p * p <= n {
+        if !composite[p] {
+            // the smaller multiples were crossed off by the smaller primes
+            for m in (p * p..=n).step_by(p) { composite[m] = true; }
+        }
+        p += 1;
+    }
+    composite
+}
+
+pub fn primes_up_to(n: usize) -> Vec<u64>
+{
+    sieve(n).iter().enumerate().filter(|(_, &c)| !c).map(|(p, _)| p as u64).collect()
+}
+
+/////////////////////////////////////
+
+// (a + b) mod m for a, b < m, without overflowing
+fn add_mod(a: u128, b: u128, m: u128) -> u128
+{
+    if a >= m - b { a - (m - b) } else { a + b }
+}
+
+// a b mod m. a single u128 product when it fits (always for u64 moduli),
+// otherwise double and add
+pub fn mul_mod(a: u128, b: u128, m: u128) -> u128
+{
+    let (mut a, mut b) = (a % m, b % m);
+    if let Some(p) = a.checked_mul(b) { return p % m; }
+    let mut result = 0;
+    while b > 0 {
+        if b & 1 == 1 { result = add_mod(result, a, m); }
+        a = add_mod(a, a, m);
+        b >>= 1;
+    }
+    result
+}
+
+// base^exp mod m by squaring, m > 0
+pub fn pow_mod(base: u128, mut exp: u128, m: u128) -> u128
+{
+    let mut base = base % m;
+    let mut result = 1 % m;
+    while exp > 0 {
+        if exp & 1 == 1 { result = mul_mod(result, base, m); }
+        base = mul_mod(base, base, m);
+        exp >>= 1;
+    }
+    result
+}
+
+pub fn is_prime(n: u64) -> bool { is_prime_u128(n as u128) }
+
+// deterministic below 3.3 * 10^24. above that a composite would have to be a
+// strong pseudoprime to all twelve bases at once, none is known
+pub fn is_prime_u128(n: u128) -> bool
+{
+    if n < 2 { return false; }
+    for &p in &WITNESSES {
+        if n.is_multiple_of(p as u128) { return n == p as u128; }
+    }
+
+    // n - 1 = d 2^s with d odd
+    let s = (n - 1).trailing_zeros();
+    let d = (n - 1) >> s;
+    WITNESSES.iter().all(|&a| {
+        let mut x = pow_mod(a as u128, d, n);
+        if x == 1 || x == n - 1 { return true; }
+        for _ in 1..s {
+            x = mul_mod(x, x, n);
+            if x == n - 1 { return true; }
+        }
+        false
+    })
+}
+
+/////////////////////////////////////
+
+// a non trivial factor of an odd composite n. x -> x^2 + c walks into a cycle
+// mod every prime factor p after about sqrt(p) steps, brent's cycle finding
+// notices through gcd(|x - y|, n), multiplying m differences together so the
+// gcd only runs once per batch
+fn pollard_rho(n: u64) -> u64
+{
+    let n128 = n as u128;
+    let f = |x: u64, c: u64| add_mod(mul_mod(x as u128, x as u128, n128), c as u128, n128) as u64;
+    let m = 128;
+
+    // a bad c cycles mod n itself (the gcd comes out as n), then the next one
+    for c in 1.. {
+        let (mut y, mut g, mut r, mut q) = (2u64, 1u64, 1u64, 1u64);
+        let (mut x, mut ys) = (y, y);
+        while g == 1 {
+            x = y;
+            for _ in 0..r { y = f(y, c); }
+            let mut k = 0;
+            while k < r && g == 1 {
+                ys = y;
+                for _ in 0..m.min(r - k) {
+                    y = f(y, c);
+                    q = mul_mod(q as u128, x.abs_diff(y) as u128, n128) as u64;
+                }
+                g = q.gcd(&n);
+                k += m;
+            }
+            r *= 2;
+        }
+        // the batch overshot: redo its steps one at a time
+        if g == n {
+            loop {
+                ys = f(ys, c);
+                g = x.abs_diff(ys).gcd(&n);
+                if g > 1 { break; }
+            }
+        }
+        if g != n { return g; }
+    }
+    unreachable!()
+}
+
+// the prime factors in increasing order, repeated as often as they divide n.
+// factor(1) is empty, factor(0) panics
+pub fn factor(mut n: u64) -> Vec<u64>
+{
+    assert!(n > 0, "0 has no factorization");
+    let mut factors = vec![];
+
+    // the small primes are cheaper to divide out than to find with rho
+    for &p in &WITNESSES {
+        while n.is_multiple_of(p) {
+            factors.push(p);
+            n /= p;
+        }
+    }
+
+    let mut pending = vec![n];
+    while let Some(m) = pending.pop() {
+        if m == 1 { continue; }
+        if is_prime(m) {
+            factors.push(m);
+        } else {
+            let d = pollard_rho(m);
+            pending.push(d);
+            pending.push(m / d);
+        }
+    }
+    factors.sort_unstable();
+    factors
+}
+
+/////////////////////////////////////
+
+// (g, x, y) with a x + b y = g = gcd(a, b), g >= 0
+pub fn extended_gcd(a: i64, b: i64) -> (i64, i64, i64)
+{
+    // in i128 so MIN and the intermediate products can't overflow
+    let (mut r0, mut r1) = (a as i128, b as i128);
+    let (mut x0, mut x1) = (1i128, 0i128);
+    let (mut y0, mut y1) = (0i128, 1i128);
+    while r1 != 0 {
+        let q = r0 / r1;
+        (r0, r1) = (r1, r0 - q * r1);
+        (x0, x1) = (x1, x0 - q * x1);
+        (y0, y1) = (y1, y0 - q * y1);
+    }
+    if r0 < 0 { (r0, x0, y0) = (-r0, -x0, -y0); }
+    let fit = |v: i128| i64::try_from(v).expect("extended_gcd result doesn't fit in i64");
+    (fit(r0), fit(x0), fit(y0))
+}
+
+// x in [0, m) with a x = 1 mod m, None when a and m share a factor.
+// panics when m isn't positive
+pub fn mod_inverse(a: i64, m: i64) -> Option<i64>
+{
+    assert!(m > 0, "modulus must be positive, got {}", m);
+    let (g, x, _) = extended_gcd(a.rem_euclid(m), m);
+    if g != 1 { return None; }
+    Some(x.rem_euclid(m))
+}
+
+// chinese remainder theorem: the x with x = r mod m for every (r, m), as
+// (x, lcm of the moduli) with 0 <= x < lcm. the moduli don't have to be
+// coprime, None when the congruences contradict each other or the lcm
+// doesn't fit in an i64. panics on a modulus that isn't positive
+pub fn crt(congruences: &[(i64, i64)]) -> Option<(i64, i64)>
+{
+    let (mut x, mut lcm) = (0i128, 1i128);
+    for &(r, m) in congruences {
+        assert!(m > 0, "modulus must be positive, got {}", m);
+        let (r, m) = ((r as i128).rem_euclid(m as i128), m as i128);
+
+        // x + lcm t = r mod m  <=>  (lcm / g) t = (r - x) / g mod (m / g)
+        let g = lcm.gcd(&m);
+        if (r - x) % g != 0 { return None; }
+        let m_g = m / g;
+        // lcm / g and m / g are coprime by now, the inverse always exists
+        let inverse = mod_inverse(((lcm / g) % m_g) as i64, m_g as i64).unwrap() as i128;
+        let t = ((r - x) / g).rem_euclid(m_g) * inverse % m_g;
+
+        x += lcm * t;
+        lcm = i64::try_from(lcm * m_g).ok()? as i128;
+        x = x.rem_euclid(lcm);
+    }
+    Some((x as i64, lcm as i64))
+}
+
+/////////////////////////////////////
+
+pub fn main()
+{
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    let primes = primes_up_to(1_000_000);
+    assert_eq!(primes.len(), 78_498);
+    println!("{} primes below a million, the last is {}", primes.len(), primes.last().unwrap());
+
+    // miller rabin agrees with the sieve, then on the hard cases: carmichael
+    // numbers, strong pseudoprimes to several bases, primes next to 2^64
+    let composite = sieve(100_000);
+    for (n, &c) in composite.iter().enumerate() { assert_eq!(is_prime(n as u64), !c, "{}", n); }
+    for n in [561, 41_041, 3_215_031_751, 3_825_123_056_546_413_051] { assert!(!is_prime(n), "{}", n); }
+    for p in [(1 << 61) - 1, 18_446_744_073_709_551_557, 1_000_000_007] { assert!(is_prime(p), "{}", p); }
+    assert!(is_prime_u128((1 << 89) - 1) && is_prime_u128((1 << 127) - 1));
+    assert!(!is_prime_u128(((1u128 << 61) - 1) * ((1 << 61) - 1)));
+
+    assert_eq!(factor(600_851_475_143), [71, 839, 1471, 6857]);
+    assert_eq!(factor(u64::MAX), [3, 5, 17, 257, 641, 65_537, 6_700_417]);
+    assert_eq!(factor(1_000_000_007 * 998_244_353), [998_244_353, 1_000_000_007]);
+    assert_eq!(factor(1 << 40), vec![2; 40]);
+    assert!(factor(1).is_empty());
+
+    // random numbers: the factors are prime and multiply back
+    let mut rng = StdRng::seed_from_u64(43);
+    for _ in 0..200 {
+        let n: u64 = rng.gen_range(2..u64::MAX);
+        let factors = factor(n);
+        assert!(factors.iter().all(|&p| is_prime(p)));
+        assert_eq!(factors.iter().product::<u64>(), n);
+    }
+    println!("2^64 - 1 = {:?}", factor(u64::MAX));
+
+    assert_eq!(extended_gcd(240, 46), (2, -9, 47));
+    assert_eq!(mod_inverse(3, 11), Some(4));
+    assert_eq!(mod_inverse(-3, 11), Some(7));
+    assert_eq!(mod_inverse(6, 9), None);
+
+    // the sun tzu problem, then moduli that share factors
+    assert_eq!(crt(&[(2, 3), (3, 5), (2, 7)]), Some((23, 105)));
+    assert_eq!(crt(&[(3, 4), (5, 6)]), Some((11, 12)));
+    assert_eq!(crt(&[(1, 4), (2, 6)]), None);
+    assert_eq!(crt(&[(-1, 1_000_000_007), (0, 998_244_353)]).map(|(x, _)| (x + 1) % 1_000_000_007), Some(0));
+    assert_eq!(crt(&[(0, 1 << 40), (0, (1 << 30) - 1)]), None);
+    assert_eq!(crt(&[]), Some((0, 1)));
+}
@@ -0,0 +1,478 @@
+// polynomials over any number type, plus a root finder for the ones with
+// f64 or Complex<f64> coefficients
+//
+// coefficients are stored lowest power first, [1, 0, 3] is 1 + 3x^2. the
+// highest one is never zero, so the zero polynomial has no coefficients at all
+// and equal polynomials always have equal vectors
+//
+// division and gcd expect the coefficients to form a field: on integers the
+// quotients get truncated. with floats the remainder that should vanish is
+// rarely an exact zero, gcd_approx takes a tolerance for that
+
+use crate::num::Zero;
+use crate::Complex;
+
+use std::ops::{Add, Div, Mul, Neg, Rem, Sub};
+
+type C64 = Complex<f64>;
+
+const ZERO: C64 = Complex { re: 0.0, im: 0.0 };
+
+// what a coefficient has to support: a ring, division is only asked for by
+// the functions that need it
+pub trait Coefficient: Clone + PartialEq + Zero + Sub<Output = Self> + Mul<Output = Self> {}
+
+impl<T> Coefficient for T where T: Clone + PartialEq + Zero + Sub<Output = T> + Mul<Output = T> {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polynomial<T>
+{
+    coeffs: Vec<T>,
+}
+
+// k c for a T that no integer converts into: double and add, log k additions
+fn times<T: Coefficient>(c: T, mut k: usize) -> T
+{
+    let mut result = T::zero();
+    let mut power = c;
+    while k > 0 {
+        if k & 1 == 1 { result = result + power.clone(); }
+        power = power.clone() + power;
+        k >>= 1;
+    }
+    result
+}
+
+impl<T: Coefficient> Polynomial<T>
+{
+    pub fn new(coeffs: Vec<T>) -> Polynomial<T>
+    {
+        let mut p = Polynomial { coeffs };
+        p.trim();
+        p
+    }
+
+    fn trim(&mut self)
+    {
+        while self.coeffs.last().is_some_and(|c| *c == T::zero()) {
+            self.coeffs.pop();
+        }
+    }
+
+    pub fn zero() -> Polynomial<T> { Polynomial { coeffs: vec![] } }
+
+    // c x^n
+    pub fn monomial(c: T, n: usize) -> Polynomial<T>
+    {
+        let mut coeffs = vec![T::zero(); n];
+        coeffs.push(c);
+        Polynomial::new(coeffs)
+    }
+
+    pub fn coeffs(&self) -> &[T] { &self.coeffs }
+
+    pub fn is_zero(&self) -> bool { self.coeffs.is_empty() }
+
+    // None for the zero polynomial
+    pub fn degree(&self) -> Option<usize> { self.coeffs.len().checked_sub(1) }
+
+    pub fn leading(&self) -> Option<&T> { self.coeffs.last() }
+
+    // horner: a0 + x (a1 + x (a2 + ...)), one multiplication per coefficient
+    pub fn eval(&self, x: T) -> T
+    {
+        self.coeffs.iter().rev().fold(T::zero(), |acc, c| acc * x.clone() + c.clone())
+    }
+
+    pub fn derivative(&self) -> Polynomial<T>
+    {
+        Polynomial::new(self.coeffs.iter().enumerate().skip(1).map(|(k, c)| times(c.clone(), k)).collect())
+    }
+
+    pub fn scale(&self, c: &T) -> Polynomial<T>
+    {
+        Polynomial::new(self.coeffs.iter().map(|a| a.clone() * c.clone()).collect())
+    }
+}
+
+impl<T: Coefficient + Div<Output = T>> Polynomial<T>
+{
+    // long division, self = q divisor + r with r of lower degree than the
+    // divisor. panics on a zero divisor like integer division does
+    pub fn div_rem(&self, divisor: &Polynomial<T>) -> (Polynomial<T>, Polynomial<T>)
+    {
+        let lead = divisor.leading().expect("polynomial division by zero").clone();
+        let d = divisor.coeffs.len();
+        if self.coeffs.len() < d { return (Polynomial::zero(), self.clone()); }
+
+        let mut rem = self.coeffs.clone();
+        let mut quot = vec![T::zero(); rem.len() - d + 1];
+        for i in (0..quot.len()).rev() {
+            let q = rem[i + d - 1].clone() / lead.clone();
+            for (j, c) in divisor.coeffs.iter().enumerate() {
+                rem[i + j] = rem[i + j].clone() - q.clone() * c.clone();
+            }
+            // cancelled by construction, whatever rounding left behind
+            rem[i + d - 1] = T::zero();
+            quot[i] = q;
+        }
+        rem.truncate(d - 1);
+        (Polynomial::new(quot), Polynomial::new(rem))
+    }
+
+    // divided by its leading coefficient, the zero polynomial stays zero
+    pub fn monic(&self) -> Polynomial<T>
+    {
+        match self.leading() {
+            Some(lead) => {
+                let lead = lead.clone();
+                Polynomial::new(self.coeffs.iter().map(|c| c.clone() / lead.clone()).collect())
+            }
+            None => Polynomial::zero(),
+        }
+    }
+
+    // euclid, monic so the answer is unique. exact for exact coefficients
+    pub fn gcd(&self, other: &Polynomial<T>) -> Polynomial<T>
+    {
+        let (mut a, mut b) = (self.clone(), other.clone());
+        while !b.is_zero() {
+            let r = a.div_rem(&b).1;
+            a = b;
+            b = r;
+        }
+        a.monic()
+    }
+}
+
+/////////////////////////////////////
+
+impl<'a, T: Coefficient> Add<&'a Polynomial<T>> for &'a Polynomial<T>
+{
+    type Output = Polynomial<T>;
+
+    fn add(self, other: &Polynomial<T>) -> Polynomial<T>
+    {
+        let n = self.coeffs.len().max(other.coeffs.len());
+        let at = |p: &Polynomial<T>, i: usize| p.coeffs.get(i).cloned().unwrap_or_else(T::zero);
+        Polynomial::new((0..n).map(|i| at(self, i) + at(other, i)).collect())
+    }
+}
+
+impl<'a, T: Coefficient> Sub<&'a Polynomial<T>> for &'a Polynomial<T>
+{
+    type Output = Polynomial<T>;
+
+    fn sub(self, other: &Polynomial<T>) -> Polynomial<T>
+    {
+        let n = self.coeffs.len().max(other.coeffs.len());
+        let at = |p: &Polynomial<T>, i: usize| p.coeffs.get(i).cloned().unwrap_or_else(T::zero);
+        Polynomial::new((0..n).map(|i| at(self, i) - at(other, i)).collect())
+    }
+}
+
+// schoolbook, fft::convolve is the faster choice for long Complex<f64> ones
+impl<'a, T: Coefficient> Mul<&'a Polynomial<T>> for &'a Polynomial<T>
+{
+    type Output = Polynomial<T>;
+
+    fn mul(self, other: &Polynomial<T>) -> Polynomial<T>
+    {
+        if self.is_zero() || other.is_zero() { return Polynomial::zero(); }
+        let mut coeffs = vec![T::zero(); self.coeffs.len() + other.coeffs.len() - 1];
+        for (i, a) in self.coeffs.iter().enumerate() {
+            for (j, b) in other.coeffs.iter().enumerate() {
+                coeffs[i + j] = coeffs[i + j].clone() + a.clone() * b.clone();
+            }
+        }
+        Polynomial::new(coeffs)
+    }
+}
+
+impl<'a, T: Coefficient + Div<Output = T>> Div<&'a Polynomial<T>> for &'a Polynomial<T>
+{
+    type Output = Polynomial<T>;
+
+    fn div(self, other: &Polynomial<T>) -> Polynomial<T> { self.div_rem(other).0 }
+}
+
+impl<'a, T: Coefficient + Div<Output = T>> Rem<&'a Polynomial<T>> for &'a Polynomial<T>
+{
+    type Output = Polynomial<T>;
+
+    fn rem(self, other: &Polynomial<T>) -> Polynomial<T> { self.div_rem(other).1 }
+}
+
+// the by value versions borrow and forward to the ones above
+macro_rules! polynomial_value_ops {
+    ($($trait:ident $method:ident [$($bound:tt)*]),*) => {
+        $(
+            impl<T: Coefficient $($bound)*> $trait for Polynomial<T>
+            {
+                type Output = Polynomial<T>;
+
+                fn $method(self, other: Polynomial<T>) -> Polynomial<T> { (&self).$method(&other) }
+            }
+        )*
+    };
+}
+
+polynomial_value_ops!(
+    Add add [],
+    Sub sub [],
+    Mul mul [],
+    Div div [+ Div<Output = T>],
+    Rem rem [+ Div<Output = T>]
+);
+
+impl<T: Coefficient + Neg<Output = T>> Neg for Polynomial<T>
+{
+    type Output = Polynomial<T>;
+
+    fn neg(self) -> Polynomial<T> { Polynomial::new(self.coeffs.into_iter().map(|c| -c).collect()) }
+}
+
+/////////////////////////////////////
+
+// the coefficients the root finder takes: anything it can measure and turn
+// into a Complex<f64>
+pub trait FloatCoefficient: Coefficient + Div<Output = Self>
+{
+    fn modulus(&self) -> f64;
+    fn to_complex(&self) -> C64;
+}
+
+impl FloatCoefficient for f64
+{
+    fn modulus(&self) -> f64 { self.abs() }
+    fn to_complex(&self) -> C64 { Complex::new(*self, 0.0) }
+}
+
+impl FloatCoefficient for C64
+{
+    fn modulus(&self) -> f64 { self.abs() }
+    fn to_complex(&self) -> C64 { *self }
+}
+
+// how the root finder did
+#[derive(Debug, Clone, PartialEq)]
+pub struct RootReport
+{
+    // one per degree, multiple roots repeated
+    pub roots: Vec<C64>,
+    pub iterations: usize,
+    // every root settled before the iteration limit
+    pub converged: bool,
+    // the largest |p(z)| over the roots, relative to the size of the terms
+    // (sum |a_k| |z|^k). around 1e-16 the root is as good as f64 can tell
+    pub max_residual: f64,
+    // the largest step of the last iteration, relative to |z|
+    pub max_correction: f64,
+}
+
+// p(z), p'(z) and sum |a_k| |z|^k in one horner pass
+fn eval_with_derivative(coeffs: &[C64], z: C64) -> (C64, C64, f64)
+{
+    let (mut p, mut dp, mut size) = (ZERO, ZERO, 0.0);
+    let r = z.abs();
+    for c in coeffs.iter().rev() {
+        dp = dp * z + p;
+        p = p * z + *c;
+        size = size * r + c.abs();
+    }
+    (p, dp, size)
+}
+
+fn relative_residual(p: C64, size: f64) -> f64
+{
+    if size == 0.0 { 0.0 } else { p.abs() / size }
+}
+
+// aberth-ehrlich: newton's step for every root at once, each one pushed away
+// from the others, so they don't all run to the same root.
+//   w_k = N_k / (1 - N_k sum_{j != k} 1 / (z_k - z_j)),  N_k = p(z_k) / p'(z_k)
+// converges cubically to simple roots and linearly to multiple ones
+fn aberth(coeffs: &[C64], tolerance: f64, max_iterations: usize) -> RootReport
+{
+    // zero roots are exact, and would only put p'(z) = 0 in the way
+    let zeros = coeffs.iter().take_while(|c| **c == ZERO).count();
+    let coeffs = &coeffs[zeros..];
+    let n = coeffs.len() - 1;
+    let mut roots = vec![ZERO; zeros];
+    if n == 0 {
+        return RootReport { roots, iterations: 0, converged: true, max_residual: 0.0, max_correction: 0.0 };
+    }
+
+    // start on a circle around the centroid of the roots, big enough to hold
+    // them. the odd angle offset keeps the guesses off the real axis, where
+    // real polynomials would trap conjugate pairs
+    let lead = coeffs[n];
+    let center = -coeffs[n - 1] / (lead * n as f64);
+    let radius = (0..n)
+        .map(|k| (coeffs[k] / lead).abs().powf(1.0 / (n - k) as f64))
+        .fold(0.0, f64::max)
+        .max(f64::MIN_POSITIVE);
+    let mut z: Vec<C64> = (0..n)
+        .map(|k| center + Complex::<f64>::from_polar(radius, 2.0 * std::f64::consts::PI * k as f64 / n as f64 + 0.4))
+        .collect();
+
+    // a root is done once its step is below tolerance, or p(z) is down to rounding noise
+    let noise = 4.0 * f64::EPSILON * n as f64;
+    let mut done = vec![false; n];
+    let mut iterations = 0;
+    let mut max_correction = 0.0;
+    while iterations < max_iterations && done.contains(&false) {
+        iterations += 1;
+        max_correction = 0.0;
+        for k in 0..n {
+            if done[k] { continue; }
+            let (p, dp, size) = eval_with_derivative(coeffs, z[k]);
+            if relative_residual(p, size) <= noise {
+                done[k] = true;
+                continue;
+            }
+
+            let newton = p / dp;
+            let repulsion: C64 = (0..n).filter(|&j| j != k).map(|j| (z[k] - z[j]).inv()).sum();
+            let mut w = newton / (-(newton * repulsion) + 1.0);
+            if !w.is_finite() {
+                // p'(z) = 0 on a guess, nudge it off the critical point
+                w = Complex::new(radius, radius) * 1e-3;
+            }
+            z[k] -= w;
+
+            let step = w.abs() / z[k].abs().max(f64::MIN_POSITIVE);
+            max_correction = f64::max(max_correction, step);
+            if step <= tolerance { done[k] = true; }
+        }
+    }
+
+    let max_residual = z.iter()
+        .map(|root| {
+            let (p, _, size) = eval_with_derivative(coeffs, *root);
+            relative_residual(p, size)
+        })
+        .fold(0.0, f64::max);
+    roots.extend(z);
+    RootReport { roots, iterations, converged: !done.contains(&false), max_residual, max_correction }
+}
+
+impl<T: FloatCoefficient> Polynomial<T>
+{
+    // all the complex roots, tolerance is the relative size of the last step
+    pub fn roots_with(&self, tolerance: f64, max_iterations: usize) -> RootReport
+    {
+        let coeffs: Vec<C64> = self.coeffs.iter().map(|c| c.to_complex()).collect();
+        if coeffs.is_empty() {
+            // every number is a root of 0, there's nothing sensible to list
+            return RootReport { roots: vec![], iterations: 0, converged: true, max_residual: 0.0, max_correction: 0.0 };
+        }
+        aberth(&coeffs, tolerance, max_iterations)
+    }
+
+    pub fn roots(&self) -> RootReport { self.roots_with(1e-14, 500) }
+
+    // euclid where coefficients below eps (relative to the largest one) count
+    // as zero. the remainders are kept monic so eps means the same every step
+    pub fn gcd_approx(&self, other: &Polynomial<T>, eps: f64) -> Polynomial<T>
+    {
+        let drop_small = |p: Polynomial<T>, scale: f64| {
+            let mut coeffs = p.coeffs;
+            while coeffs.last().is_some_and(|c| c.modulus() <= eps * scale) { coeffs.pop(); }
+            Polynomial { coeffs }
+        };
+        let largest = |p: &Polynomial<T>| p.coeffs.iter().map(|c| c.modulus()).fold(1.0, f64::max);
+
+        let (mut a, mut b) = (self.monic(), other.monic());
+        while !b.is_zero() {
+            let scale = largest(&a).max(largest(&b));
+            let r = drop_small(a.div_rem(&b).1, scale);
+            a = b;
+            b = r.monic();
+        }
+        a
+    }
+}
+
+impl Polynomial<C64>
+{
+    // (x - r1)(x - r2)...
+    pub fn from_roots(roots: &[C64]) -> Polynomial<C64>
+    {
+        roots.iter().fold(Polynomial::new(vec![Complex::new(1.0, 0.0)]), |p, r| {
+            &p * &Polynomial::new(vec![-*r, Complex::new(1.0, 0.0)])
+        })
+    }
+}
+
+/////////////////////////////////////
+
+// every expected root has a found one within tol, matched one to one
+fn same_roots(found: &[C64], expected: &[C64], tol: f64) -> bool
+{
+    let mut left = found.to_vec();
+    found.len() == expected.len() && expected.iter().all(|e| {
+        let nearest = (0..left.len()).min_by(|&i, &j| (left[i] - *e).abs().total_cmp(&(left[j] - *e).abs()));
+        match nearest {
+            Some(i) if (left[i] - *e).abs() <= tol => { left.swap_remove(i); true }
+            _ => false,
+        }
+    })
+}
+
+pub fn main()
+{
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    let c = |re: f64, im: f64| Complex::new(re, im);
+
+    // (x - 1)(x - 2)(x - 3) = x^3 - 6x^2 + 11x - 6
+    let p = Polynomial::new(vec![-6.0, 11.0, -6.0, 1.0]);
+    assert_eq!(p.eval(4.0), 6.0);
+    assert_eq!(p.derivative(), Polynomial::new(vec![11.0, -12.0, 3.0]));
+
+    let (q, r) = p.div_rem(&Polynomial::new(vec![-1.0, 1.0]));
+    assert_eq!(q, Polynomial::new(vec![6.0, -5.0, 1.0]));
+    assert!(r.is_zero());
+    assert_eq!(&(&q * &Polynomial::new(vec![-1.0, 1.0])) + &r, p);
+
+    // (x - 1)(x + 5) shares x - 1 with p
+    let other = Polynomial::new(vec![-5.0, 4.0, 1.0]);
+    let g = p.gcd_approx(&other, 1e-12);
+    println!("gcd = {:?}", g.coeffs());
+    assert_eq!(g.degree(), Some(1));
+    assert!((g.coeffs()[0] + 1.0).abs() < 1e-12);
+
+    let report = p.roots();
+    println!("roots of x^3 - 6x^2 + 11x - 6: {:?}", report);
+    assert!(report.converged && same_roots(&report.roots, &[c(1.0, 0.0), c(2.0, 0.0), c(3.0, 0.0)], 1e-12));
+
+    // x^2 + 1, a real polynomial with no real roots
+    let report = Polynomial::new(vec![1.0, 0.0, 1.0]).roots();
+    assert!(same_roots(&report.roots, &[c(0.0, 1.0), c(0.0, -1.0)], 1e-12));
+
+    // x^5 - x^2 has a double root at 0 and the cube roots of unity
+    let report = Polynomial::new(vec![0.0, 0.0, -1.0, 0.0, 0.0, 1.0]).roots();
+    let third = 3f64.sqrt() / 2.0;
+    let expected = [c(0.0, 0.0), c(0.0, 0.0), c(1.0, 0.0), c(-0.5, third), c(-0.5, -third)];
+    assert!(same_roots(&report.roots, &expected, 1e-12));
+
+    // a triple root only comes out to about the cube root of f64's precision
+    let report = Polynomial::from_roots(&[c(1.0, 0.0); 3]).roots();
+    println!("(x - 1)^3: {} iterations, {:?}", report.iterations, report.roots);
+    assert!(same_roots(&report.roots, &[c(1.0, 0.0); 3], 1e-4));
+
+    // random complex roots, back from the expanded polynomial
+    let mut rng = StdRng::seed_from_u64(34);
+    for degree in [1, 2, 5, 10, 20] {
+        let expected: Vec<C64> = (0..degree).map(|_| c(rng.gen_range(-2.0..2.0), rng.gen_range(-2.0..2.0))).collect();
+        let report = Polynomial::from_roots(&expected).roots();
+        println!(
+            "degree {:>2}: {:>3} iterations, converged {}, residual {:.1e}",
+            degree, report.iterations, report.converged, report.max_residual
+        );
+        assert!(report.converged && report.max_residual < 1e-13);
+        assert!(same_roots(&report.roots, &expected, 1e-6), "degree {}: {:?}", degree, report.roots);
+    }
+}
@@ -0,0 +1,473 @@
+// quaternions w + xi + yj + zk, built the same way as Complex<T>: the
+// arithmetic is generic over the parts, everything that needs sqrt or trig is
+// spelled out for f32 and f64
+//
+// i^2 = j^2 = k^2 = ijk = -1, so the product doesn't commute: ij = k but
+// ji = -k. a / b is a b^-1, the inverse on the right
+//
+// unit quaternions are rotations: q turns the vector v into q v q^-1. they
+// compose by multiplication (p * q rotates by q first, then by p), interpolate
+// smoothly with slerp, and have none of the gimbal lock of euler angles
+
+use std::fmt;
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Quaternion<T>
+{
+    pub w: T,
+    pub x: T,
+    pub y: T,
+    pub z: T,
+}
+
+impl<T> Quaternion<T>
+{
+    pub fn new(w: T, x: T, y: T, z: T) -> Quaternion<T>
+    {
+        Quaternion { w, x, y, z }
+    }
+}
+
+impl<T> Add for Quaternion<T>
+where T: Add<Output = T>
+{
+    type Output = Quaternion<T>;
+
+    fn add(self, rhs: Self) -> Self::Output
+    {
+        Quaternion { w: self.w + rhs.w, x: self.x + rhs.x, y: self.y + rhs.y, z: self.z + rhs.z }
+    }
+}
+
+impl<T> Sub for Quaternion<T>
+where T: Sub<Output = T>
+{
+    type Output = Quaternion<T>;
+
+    fn sub(self, rhs: Self) -> Self::Output
+    {
+        Quaternion { w: self.w - rhs.w, x: self.x - rhs.x, y: self.y - rhs.y, z: self.z - rhs.z }
+    }
+}
+
+impl<T> Neg for Quaternion<T>
+where T: Neg<Output = T>
+{
+    type Output = Quaternion<T>;
+
+    fn neg(self) -> Self::Output
+    {
+        Quaternion { w: -self.w, x: -self.x, y: -self.y, z: -self.z }
+    }
+}
+
+// hamilton product, from distributing and i^2 = j^2 = k^2 = ijk = -1
+impl<T> Mul for Quaternion<T>
+where T: Clone + Add<Output = T> + Sub<Output = T> + Mul<Output = T>
+{
+    type Output = Quaternion<T>;
+
+    fn mul(self, rhs: Self) -> Self::Output
+    {
+        let (a1, b1, c1, d1) = (self.w, self.x, self.y, self.z);
+        let (a2, b2, c2, d2) = (rhs.w, rhs.x, rhs.y, rhs.z);
+        Quaternion {
+            w: a1.clone() * a2.clone() - b1.clone() * b2.clone() - c1.clone() * c2.clone() - d1.clone() * d2.clone(),
+            x: a1.clone() * b2.clone() + b1.clone() * a2.clone() + c1.clone() * d2.clone() - d1.clone() * c2.clone(),
+            y: a1.clone() * c2.clone() - b1.clone() * d2.clone() + c1.clone() * a2.clone() + d1.clone() * b2.clone(),
+            z: a1 * d2 + b1 * c2 - c1 * b2 + d1 * a2,
+        }
+    }
+}
+
+// a b^-1 = a conj(b) / |b|^2
+impl<T> Div for Quaternion<T>
+where T: Clone + Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Div<Output = T> + Neg<Output = T>
+{
+    type Output = Quaternion<T>;
+
+    fn div(self, rhs: Self) -> Self::Output
+    {
+        let norm = rhs.w.clone() * rhs.w.clone()
+            + rhs.x.clone() * rhs.x.clone()
+            + rhs.y.clone() * rhs.y.clone()
+            + rhs.z.clone() * rhs.z.clone();
+        let conj = Quaternion { w: rhs.w, x: -rhs.x, y: -rhs.y, z: -rhs.z };
+        self * conj / norm
+    }
+}
+
+impl<T> AddAssign for Quaternion<T>
+where T: AddAssign
+{
+    fn add_assign(&mut self, rhs: Self)
+    {
+        self.w += rhs.w;
+        self.x += rhs.x;
+        self.y += rhs.y;
+        self.z += rhs.z;
+    }
+}
+
+impl<T> SubAssign for Quaternion<T>
+where T: SubAssign
+{
+    fn sub_assign(&mut self, rhs: Self)
+    {
+        self.w -= rhs.w;
+        self.x -= rhs.x;
+        self.y -= rhs.y;
+        self.z -= rhs.z;
+    }
+}
+
+// q *= r is q = q r, r applied on the right
+impl<T> MulAssign for Quaternion<T>
+where T: Clone + Add<Output = T> + Sub<Output = T> + Mul<Output = T>
+{
+    fn mul_assign(&mut self, rhs: Self)
+    {
+        *self = self.clone() * rhs;
+    }
+}
+
+impl<T> DivAssign for Quaternion<T>
+where T: Clone + Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Div<Output = T> + Neg<Output = T>
+{
+    fn div_assign(&mut self, rhs: Self)
+    {
+        *self = self.clone() / rhs;
+    }
+}
+
+// the same operators on references: &a + &b, a + &b, &a + b and a += &b
+macro_rules! quaternion_ref_ops {
+    ($($imp:ident $method:ident $assign_imp:ident $assign_method:ident),*) => {$(
+        impl<'a, 'b, T> $imp<&'b Quaternion<T>> for &'a Quaternion<T>
+        where T: Clone, Quaternion<T>: $imp<Output = Quaternion<T>>
+        {
+            type Output = Quaternion<T>;
+
+            fn $method(self, rhs: &'b Quaternion<T>) -> Quaternion<T> { self.clone().$method(rhs.clone()) }
+        }
+
+        impl<'b, T> $imp<&'b Quaternion<T>> for Quaternion<T>
+        where T: Clone, Quaternion<T>: $imp<Output = Quaternion<T>>
+        {
+            type Output = Quaternion<T>;
+
+            fn $method(self, rhs: &'b Quaternion<T>) -> Quaternion<T> { self.$method(rhs.clone()) }
+        }
+
+        impl<'a, T> $imp<Quaternion<T>> for &'a Quaternion<T>
+        where T: Clone, Quaternion<T>: $imp<Output = Quaternion<T>>
+        {
+            type Output = Quaternion<T>;
+
+            fn $method(self, rhs: Quaternion<T>) -> Quaternion<T> { self.clone().$method(rhs) }
+        }
+
+        impl<'b, T> $assign_imp<&'b Quaternion<T>> for Quaternion<T>
+        where T: Clone, Quaternion<T>: $assign_imp
+        {
+            fn $assign_method(&mut self, rhs: &'b Quaternion<T>) { self.$assign_method(rhs.clone()) }
+        }
+    )*};
+}
+
+quaternion_ref_ops!(
+    Add add AddAssign add_assign,
+    Sub sub SubAssign sub_assign,
+    Mul mul MulAssign mul_assign,
+    Div div DivAssign div_assign
+);
+
+// quaternion op scalar, a real scalar commutes with everything
+
+impl<T> Mul<T> for Quaternion<T>
+where T: Clone + Mul<Output = T>
+{
+    type Output = Quaternion<T>;
+
+    fn mul(self, rhs: T) -> Self::Output
+    {
+        Quaternion { w: self.w * rhs.clone(), x: self.x * rhs.clone(), y: self.y * rhs.clone(), z: self.z * rhs }
+    }
+}
+
+impl<T> Div<T> for Quaternion<T>
+where T: Clone + Div<Output = T>
+{
+    type Output = Quaternion<T>;
+
+    fn div(self, rhs: T) -> Self::Output
+    {
+        Quaternion { w: self.w / rhs.clone(), x: self.x / rhs.clone(), y: self.y / rhs.clone(), z: self.z / rhs }
+    }
+}
+
+impl<T> MulAssign<T> for Quaternion<T>
+where T: Clone + MulAssign
+{
+    fn mul_assign(&mut self, rhs: T)
+    {
+        self.w *= rhs.clone();
+        self.x *= rhs.clone();
+        self.y *= rhs.clone();
+        self.z *= rhs;
+    }
+}
+
+impl<T> DivAssign<T> for Quaternion<T>
+where T: Clone + DivAssign
+{
+    fn div_assign(&mut self, rhs: T)
+    {
+        self.w /= rhs.clone();
+        self.x /= rhs.clone();
+        self.y /= rhs.clone();
+        self.z /= rhs;
+    }
+}
+
+// "1+2i+3j+4k", the format flags go to every part like for Complex
+impl<T: fmt::Display> fmt::Display for Quaternion<T>
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        let part = |v: &T, plus: bool| match (plus || f.sign_plus(), f.precision()) {
+            (true, Some(p)) => format!("{:+.*}", p, v),
+            (true, None) => format!("{:+}", v),
+            (false, Some(p)) => format!("{:.*}", p, v),
+            (false, None) => format!("{}", v),
+        };
+        write!(f, "{}{}i{}j{}k", part(&self.w, false), part(&self.x, true), part(&self.y, true), part(&self.z, true))
+    }
+}
+
+/////////////////////////////////////
+
+macro_rules! quaternion_float_impl {
+    ($($t:ident),*) => {$(
+        impl Mul<Quaternion<$t>> for $t
+        {
+            type Output = Quaternion<$t>;
+
+            fn mul(self, rhs: Quaternion<$t>) -> Quaternion<$t> { rhs * self }
+        }
+
+        impl Quaternion<$t>
+        {
+            pub const IDENTITY: Quaternion<$t> = Quaternion { w: 1.0, x: 0.0, y: 0.0, z: 0.0 };
+
+            // the pure quaternion xi + yj + zk that stands for a vector
+            pub fn from_vector(v: [$t; 3]) -> Quaternion<$t> { Quaternion::new(0.0, v[0], v[1], v[2]) }
+
+            pub fn vector(self) -> [$t; 3] { [self.x, self.y, self.z] }
+
+            pub fn conj(self) -> Quaternion<$t> { Quaternion::new(self.w, -self.x, -self.y, -self.z) }
+
+            pub fn dot(self, other: Quaternion<$t>) -> $t
+            {
+                self.w * other.w + self.x * other.x + self.y * other.y + self.z * other.z
+            }
+
+            pub fn norm_sqr(self) -> $t { self.dot(self) }
+
+            pub fn norm(self) -> $t { self.norm_sqr().sqrt() }
+
+            pub fn inv(self) -> Quaternion<$t> { self.conj() / self.norm_sqr() }
+
+            // scaled to norm 1, the only quaternions that are rotations
+            pub fn normalize(self) -> Quaternion<$t> { self / self.norm() }
+
+            /////////////////////////////////////
+
+            // a turn by angle (radians, counterclockwise looking down the axis)
+            // around axis, which doesn't have to be normalized
+            pub fn from_axis_angle(axis: [$t; 3], angle: $t) -> Quaternion<$t>
+            {
+                let length = (axis[0] * axis[0] + axis[1] * axis[1] + axis[2] * axis[2]).sqrt();
+                if length == 0.0 { return Quaternion::<$t>::IDENTITY; }
+                let (sin, cos) = (angle / 2.0).sin_cos();
+                let s = sin / length;
+                Quaternion::new(cos, axis[0] * s, axis[1] * s, axis[2] * s)
+            }
+
+            // unit axis and an angle in [0, pi]. with no rotation at all any axis
+            // would do, that case gives the x axis
+            pub fn to_axis_angle(self) -> ([$t; 3], $t)
+            {
+                // q and -q are the same rotation, pick the one with w >= 0
+                let q = if self.w < 0.0 { -self.normalize() } else { self.normalize() };
+                let s = (q.x * q.x + q.y * q.y + q.z * q.z).sqrt();
+                if s == 0.0 { return ([1.0, 0.0, 0.0], 0.0); }
+                // atan2 stays accurate for small angles where acos(w) doesn't
+                let angle = 2.0 * s.atan2(q.w);
+                ([q.x / s, q.y / s, q.z / s], angle)
+            }
+
+            // roll around x, then pitch around y, then yaw around z (the
+            // aerospace z-y-x convention), for code that still thinks in euler angles
+            pub fn from_euler(roll: $t, pitch: $t, yaw: $t) -> Quaternion<$t>
+            {
+                Quaternion::<$t>::from_axis_angle([0.0, 0.0, 1.0], yaw)
+                    * Quaternion::<$t>::from_axis_angle([0.0, 1.0, 0.0], pitch)
+                    * Quaternion::<$t>::from_axis_angle([1.0, 0.0, 0.0], roll)
+            }
+
+            // (roll, pitch, yaw), pitch in [-pi/2, pi/2]. at pitch +-pi/2 roll and
+            // yaw turn around the same axis and only their difference is known
+            pub fn to_euler(self) -> ($t, $t, $t)
+            {
+                let q = self.normalize();
+                let roll = (2.0 * (q.w * q.x + q.y * q.z)).atan2(1.0 - 2.0 * (q.x * q.x + q.y * q.y));
+                let pitch = (2.0 * (q.w * q.y - q.z * q.x)).clamp(-1.0, 1.0).asin();
+                let yaw = (2.0 * (q.w * q.z + q.x * q.y)).atan2(1.0 - 2.0 * (q.y * q.y + q.z * q.z));
+                (roll, pitch, yaw)
+            }
+
+            // the 3x3 matrix m[row][col] doing the same rotation to column vectors
+            pub fn to_rotation_matrix(self) -> [[$t; 3]; 3]
+            {
+                let q = self.normalize();
+                let (w, x, y, z) = (q.w, q.x, q.y, q.z);
+                [
+                    [1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y - w * z), 2.0 * (x * z + w * y)],
+                    [2.0 * (x * y + w * z), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z - w * x)],
+                    [2.0 * (x * z - w * y), 2.0 * (y * z + w * x), 1.0 - 2.0 * (x * x + y * y)],
+                ]
+            }
+
+            // expects a proper rotation matrix (orthogonal, det 1). shepperd's
+            // method: start from the largest of w, x, y, z so the square root
+            // and the division stay well away from zero
+            pub fn from_rotation_matrix(m: [[$t; 3]; 3]) -> Quaternion<$t>
+            {
+                let trace = m[0][0] + m[1][1] + m[2][2];
+                let q = if trace > m[0][0].max(m[1][1]).max(m[2][2]) {
+                    let s = 2.0 * (1.0 + trace).sqrt();
+                    Quaternion::new(s / 4.0, (m[2][1] - m[1][2]) / s, (m[0][2] - m[2][0]) / s, (m[1][0] - m[0][1]) / s)
+                } else if m[0][0] >= m[1][1] && m[0][0] >= m[2][2] {
+                    let s = 2.0 * (1.0 + m[0][0] - m[1][1] - m[2][2]).sqrt();
+                    Quaternion::new((m[2][1] - m[1][2]) / s, s / 4.0, (m[0][1] + m[1][0]) / s, (m[0][2] + m[2][0]) / s)
+                } else if m[1][1] >= m[2][2] {
+                    let s = 2.0 * (1.0 + m[1][1] - m[0][0] - m[2][2]).sqrt();
+                    Quaternion::new((m[0][2] - m[2][0]) / s, (m[0][1] + m[1][0]) / s, s / 4.0, (m[1][2] + m[2][1]) / s)
+                } else {
+                    let s = 2.0 * (1.0 + m[2][2] - m[0][0] - m[1][1]).sqrt();
+                    Quaternion::new((m[1][0] - m[0][1]) / s, (m[0][2] + m[2][0]) / s, (m[1][2] + m[2][1]) / s, s / 4.0)
+                };
+                q.normalize()
+            }
+
+            // q v q^-1 for a unit q, expanded so it costs two cross products:
+            // v + 2w (u x v) + 2 u x (u x v) with u the vector part of q
+            pub fn rotate_point(self, p: [$t; 3]) -> [$t; 3]
+            {
+                let cross = |a: [$t; 3], b: [$t; 3]| {
+                    [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+                };
+                let u = self.vector();
+                let t = cross(u, p);
+                let t = [2.0 * t[0], 2.0 * t[1], 2.0 * t[2]];
+                let c = cross(u, t);
+                [p[0] + self.w * t[0] + c[0], p[1] + self.w * t[1] + c[1], p[2] + self.w * t[2] + c[2]]
+            }
+
+            // spherical linear interpolation between two unit quaternions: constant
+            // angular speed along the shortest way round, t = 0 gives self and
+            // t = 1 gives other
+            pub fn slerp(self, other: Quaternion<$t>, t: $t) -> Quaternion<$t>
+            {
+                // q and -q are the same rotation, going to the nearer one is shorter
+                let mut cos = self.dot(other);
+                let other = if cos < 0.0 { cos = -cos; -other } else { other };
+
+                // nearly the same rotation: sin(theta) is about 0, a straight
+                // line is just as good and doesn't divide by it
+                if cos > 0.9995 {
+                    return (self * (1.0 - t) + other * t).normalize();
+                }
+
+                let theta = cos.min(1.0).acos();
+                let sin = theta.sin();
+                (self * ((1.0 - t) * theta).sin() + other * (t * theta).sin()) / sin
+            }
+        }
+    )*};
+}
+
+quaternion_float_impl!(f32, f64);
+
+/////////////////////////////////////
+
+pub fn main()
+{
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+    type Q = Quaternion<f64>;
+
+    let close = |a: Q, b: Q| (a - b).norm() < 1e-12 || (a + b).norm() < 1e-12;
+    let close3 = |a: [f64; 3], b: [f64; 3]| a.iter().zip(&b).all(|(x, y)| (x - y).abs() < 1e-12);
+
+    // the multiplication table
+    let one = Q::IDENTITY;
+    let i = Q::new(0.0, 1.0, 0.0, 0.0);
+    let j = Q::new(0.0, 0.0, 1.0, 0.0);
+    let k = Q::new(0.0, 0.0, 0.0, 1.0);
+    assert!(i * i == -one && j * j == -one && k * k == -one && i * j * k == -one);
+    assert!(i * j == k && j * k == i && k * i == j && j * i == -k);
+
+    let q = Q::new(1.0, 2.0, 3.0, 4.0);
+    println!("q = {}, |q| = {}, q^-1 = {:.3}", q, q.norm(), q.inv());
+    assert!(close(q * q.inv(), one) && close(q.inv() * q, one));
+    assert!(close((q * i) / i, q) && close((q * k) / q, q * k * q.inv()));
+
+    // a quarter turn around z takes x to y
+    let quarter = Q::from_axis_angle([0.0, 0.0, 1.0], FRAC_PI_2);
+    assert!(close3(quarter.rotate_point([1.0, 0.0, 0.0]), [0.0, 1.0, 0.0]));
+    let (axis, angle) = quarter.to_axis_angle();
+    assert!(close3(axis, [0.0, 0.0, 1.0]) && (angle - FRAC_PI_2).abs() < 1e-12);
+
+    // half way there is an eighth of a turn
+    assert!(close(one.slerp(quarter, 0.5), Q::from_axis_angle([0.0, 0.0, 1.0], FRAC_PI_4)));
+
+    let mut rng = StdRng::seed_from_u64(37);
+    let mut random_rotation = || {
+        let axis = [rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0)];
+        Q::from_axis_angle(axis, rng.gen_range(-PI..PI))
+    };
+    for _ in 0..1000 {
+        let (p, q) = (random_rotation(), random_rotation());
+        let v = [0.3, -1.2, 2.0];
+
+        // rotate_point agrees with q v q^-1 and with the matrix
+        let sandwich = (q * Q::from_vector(v) * q.conj()).vector();
+        assert!(close3(q.rotate_point(v), sandwich));
+        let m = q.to_rotation_matrix();
+        let by_matrix = [0, 1, 2].map(|r| m[r][0] * v[0] + m[r][1] * v[1] + m[r][2] * v[2]);
+        assert!(close3(by_matrix, sandwich));
+
+        // round trips
+        assert!(close(Q::from_rotation_matrix(m), q));
+        let (axis, angle) = q.to_axis_angle();
+        assert!(close(Q::from_axis_angle(axis, angle), q));
+        let (roll, pitch, yaw) = q.to_euler();
+        assert!(close(Q::from_euler(roll, pitch, yaw), q));
+
+        // the product rotates by q first, then by p
+        assert!(close3((p * q).rotate_point(v), p.rotate_point(q.rotate_point(v))));
+
+        // slerp stays on the unit sphere and runs at constant speed
+        let mid = p.slerp(q, 0.5);
+        assert!((mid.norm() - 1.0).abs() < 1e-12);
+        let (_, total) = (p.conj() * q).to_axis_angle();
+        let (_, half) = (p.conj() * mid).to_axis_angle();
+        assert!((2.0 * half - total).abs() < 1e-9);
+    }
+    println!("rotations agree as quaternions, matrices, axis-angle and euler angles");
+}
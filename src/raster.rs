@@ -0,0 +1,472 @@
+// software rasterizer: draws shapes, polygons, circles and lines into an rgb
+// buffer and reads / writes it as binary ppm, no image crates involved
+//
+// pixel (x, y) covers the square [x, x + 1) x [y, y + 1), y grows downwards
+
+use crate::curves::{ClosedPath, Curve};
+use crate::{Point, Shape};
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+// samples per pixel side when the coverage has to be found by sampling
+const SUPERSAMPLE: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Color
+{
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Color
+{
+    pub const BLACK: Color = Color { r: 0, g: 0, b: 0 };
+    pub const WHITE: Color = Color { r: 255, g: 255, b: 255 };
+
+    pub fn new(r: u8, g: u8, b: u8) -> Color { Color { r, g, b } }
+
+    // alpha = 0 keeps self, alpha = 1 gives other
+    pub fn mix(self, other: Color, alpha: f64) -> Color
+    {
+        let alpha = alpha.clamp(0.0, 1.0);
+        let channel = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * alpha).round() as u8;
+        Color {
+            r: channel(self.r, other.r),
+            g: channel(self.g, other.g),
+            b: channel(self.b, other.b),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum PpmError
+{
+    Io(io::Error),
+    BadHeader(String),
+    // only 8 bit images (max value 255) are supported
+    UnsupportedMaxValue(u32),
+    Truncated { expected: usize, found: usize },
+}
+
+impl fmt::Display for PpmError
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self {
+            PpmError::Io(e) => write!(f, "io error: {}", e),
+            PpmError::BadHeader(msg) => write!(f, "bad ppm header: {}", msg),
+            PpmError::UnsupportedMaxValue(v) => write!(f, "unsupported max value {}, only 255 is supported", v),
+            PpmError::Truncated { expected, found } => {
+                write!(f, "pixel data is truncated, expected {} bytes, found {}", expected, found)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PpmError {}
+
+impl From<io::Error> for PpmError
+{
+    fn from(e: io::Error) -> PpmError { PpmError::Io(e) }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Canvas
+{
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+}
+
+impl Canvas
+{
+    pub fn new(width: usize, height: usize, background: Color) -> Canvas
+    {
+        Canvas { width, height, pixels: vec![background; width * height] }
+    }
+
+    pub fn width(&self) -> usize { self.width }
+
+    pub fn height(&self) -> usize { self.height }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<Color>
+    {
+        if x < self.width && y < self.height { Some(self.pixels[y * self.width + x]) } else { None }
+    }
+
+    // anything outside of the canvas is silently clipped
+    pub fn set(&mut self, x: i64, y: i64, color: Color)
+    {
+        self.blend(x, y, color, 1.0);
+    }
+
+    // paints color over the pixel with the given coverage between 0 and 1
+    pub fn blend(&mut self, x: i64, y: i64, color: Color, coverage: f64)
+    {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height || coverage <= 0.0 {
+            return;
+        }
+        let pixel = &mut self.pixels[y as usize * self.width + x as usize];
+        *pixel = pixel.mix(color, coverage);
+    }
+
+    /////////////////////////////////////
+
+    // any shape, translated so that its origin lands on `at`.
+    // coverage is estimated by sampling a grid inside every pixel
+    pub fn fill_shape(&mut self, shape: &dyn Shape, at: Point, color: Color)
+    {
+        let bbox = shape.bounding_box();
+        let x0 = ((bbox.min.x + at.x).floor() as i64).max(0);
+        let y0 = ((bbox.min.y + at.y).floor() as i64).max(0);
+        let x1 = ((bbox.max.x + at.x).ceil() as i64).min(self.width as i64);
+        let y1 = ((bbox.max.y + at.y).ceil() as i64).min(self.height as i64);
+        let step = 1.0 / SUPERSAMPLE as f64;
+
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let mut inside = 0;
+                for sy in 0..SUPERSAMPLE {
+                    for sx in 0..SUPERSAMPLE {
+                        let p = Point {
+                            x: x as f64 + (sx as f64 + 0.5) * step - at.x,
+                            y: y as f64 + (sy as f64 + 0.5) * step - at.y,
+                        };
+                        if shape.contains(p) { inside += 1; }
+                    }
+                }
+                self.blend(x, y, color, inside as f64 / (SUPERSAMPLE * SUPERSAMPLE) as f64);
+            }
+        }
+    }
+
+    // closed paths have an outline already, so they go through the scanline filler
+    pub fn fill_path(&mut self, path: &ClosedPath, color: Color)
+    {
+        self.fill_polygon(path.outline(), color);
+    }
+
+    // scanline fill with the nonzero rule. every pixel row is scanned several times,
+    // the spans between crossings add up the horizontal coverage exactly
+    pub fn fill_polygon(&mut self, points: &[Point], color: Color)
+    {
+        if points.len() < 3 || self.width == 0 { return; }
+
+        let (min_y, max_y) = points.iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), p| (lo.min(p.y), hi.max(p.y)));
+        let y0 = (min_y.floor() as i64).max(0);
+        let y1 = (max_y.ceil() as i64).min(self.height as i64);
+        let weight = 1.0 / SUPERSAMPLE as f64;
+
+        let mut coverage = vec![0.0; self.width];
+        let mut crossings: Vec<(f64, i32)> = Vec::new();
+        for y in y0..y1 {
+            coverage.iter_mut().for_each(|c| *c = 0.0);
+
+            for s in 0..SUPERSAMPLE {
+                let scan_y = y as f64 + (s as f64 + 0.5) * weight;
+                crossings.clear();
+                for (i, a) in points.iter().enumerate() {
+                    let b = points[(i + 1) % points.len()];
+                    if (a.y <= scan_y) != (b.y <= scan_y) {
+                        let x = a.x + (scan_y - a.y) * (b.x - a.x) / (b.y - a.y);
+                        // a nan or infinite vertex has no place on the row, leave its edges out
+                        if x.is_finite() { crossings.push((x, if b.y > a.y { 1 } else { -1 })); }
+                    }
+                }
+                crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+                let mut winding = 0;
+                for pair in crossings.windows(2) {
+                    winding += pair[0].1;
+                    if winding != 0 { add_span(&mut coverage, pair[0].0, pair[1].0, weight); }
+                }
+            }
+
+            for (x, c) in coverage.iter().enumerate() {
+                self.blend(x as i64, y, color, c.min(1.0));
+            }
+        }
+    }
+
+    // anti aliased disc, the coverage is the distance of the pixel center from the edge
+    pub fn fill_circle(&mut self, center: Point, radius: f64, color: Color)
+    {
+        let x0 = ((center.x - radius).floor() as i64).max(0);
+        let y0 = ((center.y - radius).floor() as i64).max(0);
+        let x1 = ((center.x + radius).ceil() as i64).min(self.width as i64);
+        let y1 = ((center.y + radius).ceil() as i64).min(self.height as i64);
+
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let dx = x as f64 + 0.5 - center.x;
+                let dy = y as f64 + 0.5 - center.y;
+                let d = (dx * dx + dy * dy).sqrt();
+                self.blend(x, y, color, (radius - d + 0.5).clamp(0.0, 1.0));
+            }
+        }
+    }
+
+    // midpoint circle algorithm, a one pixel outline without anti aliasing
+    pub fn draw_circle(&mut self, cx: i64, cy: i64, radius: i64, color: Color)
+    {
+        let mut x = radius;
+        let mut y = 0;
+        let mut err = 1 - radius;
+        while x >= y {
+            for (dx, dy) in [(x, y), (y, x), (-y, x), (-x, y), (-x, -y), (-y, -x), (y, -x), (x, -y)] {
+                self.set(cx + dx, cy + dy, color);
+            }
+            y += 1;
+            if err < 0 {
+                err += 2 * y + 1;
+            } else {
+                x -= 1;
+                err += 2 * (y - x) + 1;
+            }
+        }
+    }
+
+    // bresenham: integer only, one pixel per step along the major axis
+    pub fn draw_line_bresenham(&mut self, x0: i64, y0: i64, x1: i64, y1: i64, color: Color)
+    {
+        let dx = (x1 - x0).abs();
+        let dy = -(y1 - y0).abs();
+        let sx = if x0 < x1 { 1 } else { -1 };
+        let sy = if y0 < y1 { 1 } else { -1 };
+        let mut err = dx + dy;
+        let (mut x, mut y) = (x0, y0);
+
+        loop {
+            self.set(x, y, color);
+            if x == x1 && y == y1 { break; }
+            let e2 = 2 * err;
+            if e2 >= dy { err += dy; x += sx; }
+            if e2 <= dx { err += dx; y += sy; }
+        }
+    }
+
+    // xiaolin wu: anti aliased, every step splits the ink between the two pixels
+    // closest to the line. end points are at pixel centers
+    pub fn draw_line_wu(&mut self, from: Point, to: Point, color: Color)
+    {
+        let steep = (to.y - from.y).abs() > (to.x - from.x).abs();
+        let (mut a, mut b) = if steep {
+            (Point { x: from.y, y: from.x }, Point { x: to.y, y: to.x })
+        } else {
+            (from, to)
+        };
+        if a.x > b.x { std::mem::swap(&mut a, &mut b); }
+
+        let dx = b.x - a.x;
+        let gradient = if dx == 0.0 { 1.0 } else { (b.y - a.y) / dx };
+
+        let mut plot = |major: i64, minor: i64, coverage: f64| {
+            if steep { self.blend(minor, major, color, coverage) } else { self.blend(major, minor, color, coverage) }
+        };
+
+        // pixel centers sit at .5, shift so that integer coordinates are centers
+        let start = (a.x - 0.5).round() as i64;
+        let end = (b.x - 0.5).round() as i64;
+        let mut y = a.y - 0.5 + gradient * (start as f64 + 0.5 - a.x);
+        for x in start..=end {
+            let base = y.floor();
+            let frac = y - base;
+            plot(x, base as i64, 1.0 - frac);
+            plot(x, base as i64 + 1, frac);
+            y += gradient;
+        }
+    }
+
+    // any curve, flattened and drawn with wu lines
+    pub fn draw_curve(&mut self, curve: &dyn Curve, tolerance: f64, color: Color)
+    {
+        for pair in curve.flatten(tolerance).windows(2) {
+            self.draw_line_wu(pair[0], pair[1], color);
+        }
+    }
+
+    /////////////////////////////////////
+
+    // binary ppm (P6)
+    pub fn write_ppm<W: Write>(&self, mut out: W) -> io::Result<()>
+    {
+        write!(out, "P6\n{} {}\n255\n", self.width, self.height)?;
+        let bytes: Vec<u8> = self.pixels.iter().flat_map(|c| [c.r, c.g, c.b]).collect();
+        out.write_all(&bytes)?;
+        out.flush()
+    }
+
+    pub fn save_ppm(&self, path: &str) -> io::Result<()>
+    {
+        self.write_ppm(BufWriter::new(File::create(path)?))
+    }
+
+    pub fn from_ppm(bytes: &[u8]) -> Result<Canvas, PpmError>
+    {
+        // header: magic, width, height and max value separated by whitespace,
+        // with # comments running to the end of the line
+        let mut pos = 0;
+        let mut fields = Vec::new();
+        while fields.len() < 4 {
+            while pos < bytes.len() && (bytes[pos].is_ascii_whitespace() || bytes[pos] == b'#') {
+                if bytes[pos] == b'#' {
+                    while pos < bytes.len() && bytes[pos] != b'\n' { pos += 1; }
+                } else {
+                    pos += 1;
+                }
+            }
+            let start = pos;
+            while pos < bytes.len() && !bytes[pos].is_ascii_whitespace() { pos += 1; }
+            if start == pos { return Err(PpmError::BadHeader("header ends early".to_string())); }
+            fields.push(String::from_utf8_lossy(&bytes[start..pos]).into_owned());
+        }
+        // exactly one whitespace byte separates the header from the pixels
+        pos += 1;
+
+        if fields[0] != "P6" {
+            return Err(PpmError::BadHeader(format!("expected P6, found {}", fields[0])));
+        }
+        let number = |s: &str| s.parse::<usize>().map_err(|_| PpmError::BadHeader(format!("{} is not a number", s)));
+        let width = number(&fields[1])?;
+        let height = number(&fields[2])?;
+        let max_value = number(&fields[3])?;
+        if max_value != 255 { return Err(PpmError::UnsupportedMaxValue(max_value as u32)); }
+
+        let expected = width.checked_mul(height).and_then(|n| n.checked_mul(3))
+            .ok_or_else(|| PpmError::BadHeader(format!("{} x {} pixels is too large", width, height)))?;
+        let data = bytes.get(pos..).unwrap_or(&[]);
+        if data.len() < expected {
+            return Err(PpmError::Truncated { expected, found: data.len() });
+        }
+        let pixels = data[..expected].chunks(3).map(|c| Color::new(c[0], c[1], c[2])).collect();
+        Ok(Canvas { width, height, pixels })
+    }
+
+    pub fn load_ppm(path: &str) -> Result<Canvas, PpmError>
+    {
+        Canvas::from_ppm(&std::fs::read(path)?)
+    }
+
+    // for golden image tests: how many pixels have a channel that differs by
+    // more than tolerance. None if the sizes don't even match
+    pub fn diff(&self, other: &Canvas, tolerance: u8) -> Option<usize>
+    {
+        if self.width != other.width || self.height != other.height { return None; }
+        let close = |a: u8, b: u8| a.abs_diff(b) <= tolerance;
+        Some(self.pixels.iter()
+            .zip(&other.pixels)
+            .filter(|(a, b)| !(close(a.r, b.r) && close(a.g, b.g) && close(a.b, b.b)))
+            .count())
+    }
+}
+
+// adds weight * (how much of each pixel lies in [from, to]) to the row
+fn add_span(row: &mut [f64], from: f64, to: f64, weight: f64)
+{
+    let from = from.max(0.0);
+    let to = to.min(row.len() as f64);
+    if to <= from { return; }
+
+    let first = from.floor() as usize;
+    let last = to.floor() as usize;
+    if first == last {
+        row[first] += (to - from) * weight;
+        return;
+    }
+    row[first] += (first as f64 + 1.0 - from) * weight;
+    for c in &mut row[first + 1..last] { *c += weight; }
+    if last < row.len() { row[last] += (to - last as f64) * weight; }
+}
+
+/////////////////////////////////////
+
+pub fn main()
+{
+    use crate::curves::{CubicBezier, Path};
+    use crate::{Circle, Square};
+
+    let mut canvas = Canvas::new(200, 120, Color::WHITE);
+
+    canvas.fill_shape(&Circle { radius: 30.0 }, Point { x: 40.0, y: 40.0 }, Color::new(200, 30, 30));
+    canvas.fill_shape(&Square { side: 40.0 }, Point { x: 110.0, y: 40.0 }, Color::new(30, 30, 200));
+    canvas.fill_circle(Point { x: 170.0, y: 40.0 }, 20.0, Color::new(30, 160, 30));
+
+    let triangle = [Point { x: 20.0, y: 110.0 }, Point { x: 60.0, y: 80.0 }, Point { x: 100.0, y: 110.0 }];
+    canvas.fill_polygon(&triangle, Color::BLACK);
+
+    let leaf = Path::new(Point { x: 120.0, y: 110.0 })
+        .quad_to(Point { x: 150.0, y: 60.0 }, Point { x: 180.0, y: 110.0 })
+        .close();
+    canvas.fill_path(&leaf, Color::new(120, 80, 0));
+
+    canvas.draw_line_bresenham(0, 0, 199, 119, Color::BLACK);
+    canvas.draw_line_wu(Point { x: 0.5, y: 119.5 }, Point { x: 199.5, y: 0.5 }, Color::BLACK);
+    canvas.draw_circle(100, 60, 55, Color::new(128, 128, 128));
+    let wave = CubicBezier::new(
+        Point { x: 10.0, y: 60.0 },
+        Point { x: 70.0, y: 0.0 },
+        Point { x: 130.0, y: 120.0 },
+        Point { x: 190.0, y: 60.0 },
+    );
+    canvas.draw_curve(&wave, 0.25, Color::new(200, 0, 200));
+    println!("{} x {} canvas, middle pixel {:?}", canvas.width(), canvas.height(), canvas.get(100, 60));
+    assert_eq!(canvas.get(200, 0), None);
+
+    // the ppm reads back as the same canvas, in memory and from the temp directory
+    let mut bytes = Vec::new();
+    canvas.write_ppm(&mut bytes).unwrap();
+    assert!(bytes.starts_with(b"P6\n200 120\n255\n"));
+    assert_eq!(Canvas::from_ppm(&bytes).unwrap(), canvas);
+
+    let path = std::env::temp_dir().join("shapes.ppm");
+    let path = path.to_string_lossy();
+    match canvas.save_ppm(&path) {
+        Ok(()) => {
+            println!("wrote {}", path);
+            assert_eq!(Canvas::load_ppm(&path).unwrap(), canvas);
+        }
+        Err(e) => println!("could not write {}: {}", path, e),
+    }
+
+    // a nan vertex drops its edges instead of panicking in the sort
+    let mut broken = Canvas::new(10, 10, Color::WHITE);
+    let with_nan = [Point { x: 1.0, y: 1.0 }, Point { x: f64::NAN, y: 5.0 }, Point { x: 8.0, y: 8.0 }, Point { x: 1.0, y: 8.0 }];
+    broken.fill_polygon(&with_nan, Color::BLACK);
+
+    // golden images, drawn pixel by pixel: a square on the pixel grid is
+    // covered exactly, a bresenham line has one pixel per column
+    let mut square = Canvas::new(10, 10, Color::WHITE);
+    let corners = [Point { x: 2.0, y: 2.0 }, Point { x: 6.0, y: 2.0 }, Point { x: 6.0, y: 6.0 }, Point { x: 2.0, y: 6.0 }];
+    square.fill_polygon(&corners, Color::BLACK);
+    let mut golden = Canvas::new(10, 10, Color::WHITE);
+    for y in 2..6 {
+        for x in 2..6 { golden.set(x, y, Color::BLACK); }
+    }
+    assert_eq!(square.diff(&golden, 0), Some(0));
+    assert_eq!(square.pixels.iter().filter(|&&c| c == Color::BLACK).count(), 16);
+
+    let mut line = Canvas::new(10, 10, Color::WHITE);
+    line.draw_line_bresenham(0, 0, 9, 3, Color::BLACK);
+    let mut golden = Canvas::new(10, 10, Color::WHITE);
+    for (x, y) in [(0, 0), (1, 0), (2, 1), (3, 1), (4, 1), (5, 2), (6, 2), (7, 2), (8, 3), (9, 3)] {
+        golden.set(x, y, Color::BLACK);
+    }
+    assert_eq!(line.diff(&golden, 0), Some(0));
+    assert_eq!(line.diff(&square, 0), Some(24));
+    assert_eq!(line.diff(&canvas, 255), None);
+
+    // broken files say what's wrong with them
+    assert!(matches!(Canvas::from_ppm(b"P3 2 2 255\n"), Err(PpmError::BadHeader(_))));
+    assert!(matches!(Canvas::from_ppm(b"P6 2 2"), Err(PpmError::BadHeader(_))));
+    assert!(matches!(Canvas::from_ppm(b"P6 2 x 255\n"), Err(PpmError::BadHeader(_))));
+    assert!(matches!(Canvas::from_ppm(b"P6 2 2 65535\n"), Err(PpmError::UnsupportedMaxValue(65535))));
+    assert!(matches!(Canvas::from_ppm(b"P6 2 2 255\n\x01\x02\x03"), Err(PpmError::Truncated { expected: 12, found: 3 })));
+    // a size whose byte count doesn't even fit a usize
+    let huge = Canvas::from_ppm(b"P6 99999999999 99999999999 255\n").unwrap_err();
+    println!("{}", huge);
+    assert!(matches!(huge, PpmError::BadHeader(_)));
+}
@@ -0,0 +1,705 @@
+// exact fractions n/d over signed integers
+//
+// always kept in lowest terms with a positive denominator, so equal numbers
+// have equal parts and == and hash can compare them field by field
+//
+// the checked_ methods return an error instead of overflowing. the operators
+// call them and panic on an error, like integer arithmetic in a debug build
+// does. the intermediate values are kept small (common factors are cancelled
+// before multiplying), so an operation only fails when it has to
+
+use crate::num::{Integer, One, Signed, Zero};
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter::{Product, Sum};
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+use std::str::FromStr;
+
+// the integers a rational can be made of
+pub trait CheckedInt: Integer + fmt::Debug + fmt::Display + FromStr
+{
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+    fn checked_sub(&self, other: &Self) -> Option<Self>;
+    fn checked_mul(&self, other: &Self) -> Option<Self>;
+    // only fails for MIN / -1 (and division by zero)
+    fn checked_div(&self, other: &Self) -> Option<Self>;
+    fn checked_neg(&self) -> Option<Self>;
+    fn from_i64(x: i64) -> Option<Self>;
+    fn to_f64(&self) -> f64;
+}
+
+macro_rules! checked_int_impl {
+    ($($t:ident),*) => {$(
+        impl CheckedInt for $t
+        {
+            fn checked_add(&self, other: &$t) -> Option<$t> { $t::checked_add(*self, *other) }
+            fn checked_sub(&self, other: &$t) -> Option<$t> { $t::checked_sub(*self, *other) }
+            fn checked_mul(&self, other: &$t) -> Option<$t> { $t::checked_mul(*self, *other) }
+            fn checked_div(&self, other: &$t) -> Option<$t> { $t::checked_div(*self, *other) }
+            fn checked_neg(&self) -> Option<$t> { $t::checked_neg(*self) }
+            fn from_i64(x: i64) -> Option<$t> { $t::try_from(x).ok() }
+            fn to_f64(&self) -> f64 { *self as f64 }
+        }
+    )*};
+}
+
+checked_int_impl!(i8, i16, i32, i64, i128, isize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RationalError
+{
+    DivisionByZero,
+    // the exact result doesn't fit in the integer type
+    Overflow,
+    // NAN or an infinity passed to from_f64 / approximate
+    NotFinite,
+}
+
+impl fmt::Display for RationalError
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        match self {
+            RationalError::DivisionByZero => write!(f, "division by zero"),
+            RationalError::Overflow => write!(f, "the result does not fit in the integer type"),
+            RationalError::NotFinite => write!(f, "cannot convert a non finite float to a rational"),
+        }
+    }
+}
+
+impl std::error::Error for RationalError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rational<T>
+{
+    numer: T,
+    denom: T,
+}
+
+fn overflow<T>(x: Option<T>) -> Result<T, RationalError>
+{
+    x.ok_or(RationalError::Overflow)
+}
+
+// euclid on the values as they are, MIN has no absolute value. the result can
+// come out negative, but it divides both either way
+fn gcd<T: CheckedInt>(a: &T, b: &T) -> T
+{
+    let (mut a, mut b) = (a.clone(), b.clone());
+    while b != T::zero() {
+        // MIN % -1 overflows, and anything % +-1 is 0 anyway
+        if b == T::one() || T::one().checked_neg().is_some_and(|m| b == m) { return T::one(); }
+        let r = a.clone() % b.clone();
+        a = b;
+        b = r;
+    }
+    if a < T::zero() { a.checked_neg().unwrap_or(a) } else { a }
+}
+
+// floor division for d > 0: (q, r) with n = q d + r and 0 <= r < d
+fn floor_div_rem<T: CheckedInt>(n: &T, d: &T) -> (T, T)
+{
+    let (q, r) = (n.clone() / d.clone(), n.clone() % d.clone());
+    if r < T::zero() { (q - T::one(), r + d.clone()) } else { (q, r) }
+}
+
+impl<T: CheckedInt> Rational<T>
+{
+    // panics if d is 0 or the reduced fraction doesn't fit
+    pub fn new(n: T, d: T) -> Rational<T>
+    {
+        match Rational::checked_new(n, d) {
+            Ok(r) => r,
+            Err(e) => panic!("invalid rational: {}", e),
+        }
+    }
+
+    pub fn checked_new(n: T, d: T) -> Result<Rational<T>, RationalError>
+    {
+        if d == T::zero() { return Err(RationalError::DivisionByZero); }
+        if n == T::zero() { return Ok(Rational { numer: T::zero(), denom: T::one() }); }
+
+        let g = gcd(&n, &d);
+        let (mut n, mut d) = (overflow(n.checked_div(&g))?, overflow(d.checked_div(&g))?);
+        if d < T::zero() {
+            n = overflow(n.checked_neg())?;
+            d = overflow(d.checked_neg())?;
+        }
+        Ok(Rational { numer: n, denom: d })
+    }
+
+    pub fn from_integer(n: T) -> Rational<T> { Rational { numer: n, denom: T::one() } }
+
+    pub fn zero() -> Rational<T> { Rational::from_integer(T::zero()) }
+
+    pub fn one() -> Rational<T> { Rational::from_integer(T::one()) }
+
+    pub fn numer(&self) -> &T { &self.numer }
+
+    pub fn denom(&self) -> &T { &self.denom }
+
+    pub fn is_integer(&self) -> bool { self.denom == T::one() }
+
+    pub fn is_negative(&self) -> bool { self.numer < T::zero() }
+
+    /////////////////////////////////////
+
+    // a/b + c/d over the least common denominator: with g = gcd(b, d),
+    // (a (d/g) + c (b/g)) / (b (d/g))
+    pub fn checked_add(&self, other: &Rational<T>) -> Result<Rational<T>, RationalError>
+    {
+        let g = gcd(&self.denom, &other.denom);
+        let (b, d) = (self.denom.clone() / g.clone(), other.denom.clone() / g);
+        let left = overflow(self.numer.checked_mul(&d))?;
+        let right = overflow(other.numer.checked_mul(&b))?;
+        let n = overflow(left.checked_add(&right))?;
+        Rational::checked_new(n, overflow(self.denom.checked_mul(&d))?)
+    }
+
+    pub fn checked_neg(&self) -> Result<Rational<T>, RationalError>
+    {
+        Ok(Rational { numer: overflow(self.numer.checked_neg())?, denom: self.denom.clone() })
+    }
+
+    pub fn checked_sub(&self, other: &Rational<T>) -> Result<Rational<T>, RationalError>
+    {
+        let g = gcd(&self.denom, &other.denom);
+        let (b, d) = (self.denom.clone() / g.clone(), other.denom.clone() / g);
+        let left = overflow(self.numer.checked_mul(&d))?;
+        let right = overflow(other.numer.checked_mul(&b))?;
+        let n = overflow(left.checked_sub(&right))?;
+        Rational::checked_new(n, overflow(self.denom.checked_mul(&d))?)
+    }
+
+    // cross cancel first: (a/b)(c/d) = ((a/g1)(c/g2)) / ((b/g2)(d/g1))
+    // with g1 = gcd(a, d) and g2 = gcd(c, b), then nothing is left to reduce
+    pub fn checked_mul(&self, other: &Rational<T>) -> Result<Rational<T>, RationalError>
+    {
+        if self.numer == T::zero() || other.numer == T::zero() { return Ok(Rational::zero()); }
+        let g1 = gcd(&self.numer, &other.denom);
+        let g2 = gcd(&other.numer, &self.denom);
+        let a = overflow(self.numer.checked_div(&g1))?;
+        let c = overflow(other.numer.checked_div(&g2))?;
+        let b = overflow(self.denom.checked_div(&g2))?;
+        let d = overflow(other.denom.checked_div(&g1))?;
+        Rational::checked_new(overflow(a.checked_mul(&c))?, overflow(b.checked_mul(&d))?)
+    }
+
+    pub fn checked_recip(&self) -> Result<Rational<T>, RationalError>
+    {
+        Rational::checked_new(self.denom.clone(), self.numer.clone())
+    }
+
+    pub fn checked_div(&self, other: &Rational<T>) -> Result<Rational<T>, RationalError>
+    {
+        self.checked_mul(&other.checked_recip()?)
+    }
+
+    // integer powers by repeated squaring, negative ones through the reciprocal
+    pub fn checked_pow(&self, n: i32) -> Result<Rational<T>, RationalError>
+    {
+        let mut base = if n < 0 { self.checked_recip()? } else { self.clone() };
+        let mut exp = n.unsigned_abs();
+        let mut result = Rational::one();
+        while exp > 0 {
+            if exp & 1 == 1 { result = result.checked_mul(&base)?; }
+            exp >>= 1;
+            if exp > 0 { base = base.checked_mul(&base)?; }
+        }
+        Ok(result)
+    }
+
+    pub fn recip(&self) -> Rational<T> { expect(self.checked_recip(), "reciprocal") }
+
+    pub fn pow(&self, n: i32) -> Rational<T> { expect(self.checked_pow(n), "power") }
+
+    pub fn abs(&self) -> Rational<T>
+    {
+        if self.is_negative() { -self.clone() } else { self.clone() }
+    }
+
+    // the largest integer <= self
+    pub fn floor(&self) -> T { floor_div_rem(&self.numer, &self.denom).0 }
+
+    /////////////////////////////////////
+
+    pub fn to_f64(&self) -> f64
+    {
+        self.numer.to_f64() / self.denom.to_f64()
+    }
+
+    // the exact value of the float, every finite f64 is a fraction with a
+    // power of two below. fails if that doesn't fit, like 0.1 in an i32
+    pub fn from_f64(x: f64) -> Result<Rational<T>, RationalError>
+    {
+        if !x.is_finite() { return Err(RationalError::NotFinite); }
+        let two = overflow(T::from_i64(2))?;
+        let mut y = x.abs();
+        let mut denom = T::one();
+        // doubling is exact and a float with a fraction is below 2^52, this
+        // stops after at most 1074 steps
+        while y.fract() != 0.0 {
+            y *= 2.0;
+            denom = overflow(denom.checked_mul(&two))?;
+        }
+        let mut scale = T::one();
+        while y >= 9007199254740992.0 {
+            y /= 2.0;
+            scale = overflow(scale.checked_mul(&two))?;
+        }
+        let mut numer = overflow(overflow(T::from_i64(y as i64))?.checked_mul(&scale))?;
+        if x < 0.0 { numer = overflow(numer.checked_neg())?; }
+        Rational::checked_new(numer, denom)
+    }
+
+    // the fraction closest to x with a denominator of at most max_denominator.
+    // the convergents of the continued fraction of x are the best
+    // approximations, between two of them the semiconvergent
+    // (p0 + k p1) / (q0 + k q1) with the largest allowed k can be closer still.
+    // Overflow only when the integer part of x doesn't fit T
+    pub fn approximate(x: f64, max_denominator: T) -> Result<Rational<T>, RationalError>
+    {
+        if !x.is_finite() { return Err(RationalError::NotFinite); }
+        if max_denominator < T::one() { return Err(RationalError::DivisionByZero); }
+
+        let mut y = x.abs();
+        let (mut p0, mut q0, mut p1, mut q1) = (T::zero(), T::one(), T::one(), T::zero());
+        // the term that didn't make it, the semiconvergents stop below it
+        let mut next_term = None;
+        loop {
+            let whole = y.floor();
+            let a = if whole < 9.2e18 { T::from_i64(whole as i64) } else { None };
+            // a term too big for T or a convergent past the bound ends the
+            // expansion at the convergent before. only the integer part of x
+            // itself has nothing before it
+            let p2q2 = a.as_ref().and_then(|a| {
+                let q2 = a.checked_mul(&q1)?.checked_add(&q0)?;
+                let p2 = a.checked_mul(&p1)?.checked_add(&p0)?;
+                Some((p2, q2))
+            });
+            match p2q2 {
+                Some((p2, q2)) if q2 <= max_denominator => (p0, q0, p1, q1) = (p1, q1, p2, q2),
+                _ if q1.is_zero() => return Err(RationalError::Overflow),
+                _ => {
+                    next_term = a;
+                    break;
+                }
+            }
+
+            let fraction = y - whole;
+            if fraction == 0.0 { break; }
+            y = 1.0 / fraction;
+        }
+
+        let convergent = Rational::checked_new(p1.clone(), q1.clone())?;
+        let mut k = (max_denominator - q0.clone()) / q1.clone();
+        if let Some(a) = next_term {
+            if k >= a { k = a - T::one(); }
+        }
+        let semi_numer = overflow(overflow(k.checked_mul(&p1))?.checked_add(&p0));
+        let semi_denom = overflow(overflow(k.checked_mul(&q1))?.checked_add(&q0));
+        let best = match (semi_numer, semi_denom) {
+            (Ok(n), Ok(d)) if k > T::zero() => {
+                let semi = Rational::checked_new(n, d)?;
+                if (semi.to_f64() - x.abs()).abs() < (convergent.to_f64() - x.abs()).abs() { semi } else { convergent }
+            }
+            _ => convergent,
+        };
+        if x < 0.0 { best.checked_neg() } else { Ok(best) }
+    }
+}
+
+fn expect<T>(result: Result<T, RationalError>, what: &str) -> T
+{
+    match result {
+        Ok(r) => r,
+        Err(e) => panic!("rational {} failed: {}", what, e),
+    }
+}
+
+impl<T: CheckedInt> From<T> for Rational<T>
+{
+    fn from(n: T) -> Rational<T> { Rational::from_integer(n) }
+}
+
+/////////////////////////////////////
+
+// a/b against c/d without the products a d and c b, which could overflow:
+// compare the integer parts, and if they match the fractional parts, whose
+// order is the reverse of the order of their reciprocals
+fn compare<T: CheckedInt>(a: &T, b: &T, c: &T, d: &T) -> Ordering
+{
+    let (mut a, mut b, mut c, mut d) = (a.clone(), b.clone(), c.clone(), d.clone());
+    loop {
+        let (q1, r1) = floor_div_rem(&a, &b);
+        let (q2, r2) = floor_div_rem(&c, &d);
+        if q1 != q2 { return q1.cmp(&q2); }
+        match (r1 == T::zero(), r2 == T::zero()) {
+            (true, true) => return Ordering::Equal,
+            (true, false) => return Ordering::Less,
+            (false, true) => return Ordering::Greater,
+            // r1/b vs r2/d is d/r2 vs b/r1
+            (false, false) => (a, b, c, d) = (d, r2, b, r1),
+        }
+    }
+}
+
+impl<T: CheckedInt> Ord for Rational<T>
+{
+    fn cmp(&self, other: &Self) -> Ordering
+    {
+        compare(&self.numer, &self.denom, &other.numer, &other.denom)
+    }
+}
+
+impl<T: CheckedInt> PartialOrd for Rational<T>
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }
+}
+
+/////////////////////////////////////
+
+// the operators panic where the checked_ methods return an error
+
+impl<T: CheckedInt> Add for Rational<T>
+{
+    type Output = Rational<T>;
+
+    fn add(self, rhs: Self) -> Self::Output { expect(self.checked_add(&rhs), "addition") }
+}
+
+impl<T: CheckedInt> Sub for Rational<T>
+{
+    type Output = Rational<T>;
+
+    fn sub(self, rhs: Self) -> Self::Output { expect(self.checked_sub(&rhs), "subtraction") }
+}
+
+impl<T: CheckedInt> Mul for Rational<T>
+{
+    type Output = Rational<T>;
+
+    fn mul(self, rhs: Self) -> Self::Output { expect(self.checked_mul(&rhs), "multiplication") }
+}
+
+impl<T: CheckedInt> Div for Rational<T>
+{
+    type Output = Rational<T>;
+
+    fn div(self, rhs: Self) -> Self::Output { expect(self.checked_div(&rhs), "division") }
+}
+
+impl<T: CheckedInt> Neg for Rational<T>
+{
+    type Output = Rational<T>;
+
+    fn neg(self) -> Self::Output { expect(self.checked_neg(), "negation") }
+}
+
+// the assignments, and the same operators on references: &a + &b, a + &b,
+// &a + b and a += &b
+macro_rules! rational_ref_ops {
+    ($($imp:ident $method:ident $assign_imp:ident $assign_method:ident),*) => {$(
+        impl<T: CheckedInt> $assign_imp for Rational<T>
+        {
+            fn $assign_method(&mut self, rhs: Rational<T>) { *self = self.clone().$method(rhs); }
+        }
+
+        impl<'a, 'b, T: CheckedInt> $imp<&'b Rational<T>> for &'a Rational<T>
+        {
+            type Output = Rational<T>;
+
+            fn $method(self, rhs: &'b Rational<T>) -> Rational<T> { self.clone().$method(rhs.clone()) }
+        }
+
+        impl<'b, T: CheckedInt> $imp<&'b Rational<T>> for Rational<T>
+        {
+            type Output = Rational<T>;
+
+            fn $method(self, rhs: &'b Rational<T>) -> Rational<T> { self.$method(rhs.clone()) }
+        }
+
+        impl<'a, T: CheckedInt> $imp<Rational<T>> for &'a Rational<T>
+        {
+            type Output = Rational<T>;
+
+            fn $method(self, rhs: Rational<T>) -> Rational<T> { self.clone().$method(rhs) }
+        }
+
+        impl<'b, T: CheckedInt> $assign_imp<&'b Rational<T>> for Rational<T>
+        {
+            fn $assign_method(&mut self, rhs: &'b Rational<T>) { *self = self.clone().$method(rhs.clone()); }
+        }
+
+        // rational op integer
+        impl<T: CheckedInt> $imp<T> for Rational<T>
+        {
+            type Output = Rational<T>;
+
+            fn $method(self, rhs: T) -> Rational<T> { self.$method(Rational::from_integer(rhs)) }
+        }
+
+        impl<T: CheckedInt> $assign_imp<T> for Rational<T>
+        {
+            fn $assign_method(&mut self, rhs: T) { *self = self.clone().$method(Rational::from_integer(rhs)); }
+        }
+    )*};
+}
+
+rational_ref_ops!(
+    Add add AddAssign add_assign,
+    Sub sub SubAssign sub_assign,
+    Mul mul MulAssign mul_assign,
+    Div div DivAssign div_assign
+);
+
+// integer op rational, spelled out per primitive for the same reason as for Complex
+macro_rules! rational_primitive_ops {
+    ($($t:ty),*) => {$(
+        impl Add<Rational<$t>> for $t
+        {
+            type Output = Rational<$t>;
+
+            fn add(self, rhs: Rational<$t>) -> Rational<$t> { Rational::from_integer(self) + rhs }
+        }
+
+        impl Sub<Rational<$t>> for $t
+        {
+            type Output = Rational<$t>;
+
+            fn sub(self, rhs: Rational<$t>) -> Rational<$t> { Rational::from_integer(self) - rhs }
+        }
+
+        impl Mul<Rational<$t>> for $t
+        {
+            type Output = Rational<$t>;
+
+            fn mul(self, rhs: Rational<$t>) -> Rational<$t> { Rational::from_integer(self) * rhs }
+        }
+
+        impl Div<Rational<$t>> for $t
+        {
+            type Output = Rational<$t>;
+
+            fn div(self, rhs: Rational<$t>) -> Rational<$t> { Rational::from_integer(self) / rhs }
+        }
+    )*};
+}
+
+rational_primitive_ops!(i8, i16, i32, i64, i128, isize);
+
+impl<T: CheckedInt> Sum for Rational<T>
+{
+    fn sum<I: Iterator<Item = Rational<T>>>(iter: I) -> Rational<T> { iter.fold(Rational::zero(), |acc, r| acc + r) }
+}
+
+impl<'a, T: CheckedInt> Sum<&'a Rational<T>> for Rational<T>
+{
+    fn sum<I: Iterator<Item = &'a Rational<T>>>(iter: I) -> Rational<T> { iter.fold(Rational::zero(), |acc, r| acc + r) }
+}
+
+impl<T: CheckedInt> Product for Rational<T>
+{
+    fn product<I: Iterator<Item = Rational<T>>>(iter: I) -> Rational<T> { iter.fold(Rational::one(), |acc, r| acc * r) }
+}
+
+impl<'a, T: CheckedInt> Product<&'a Rational<T>> for Rational<T>
+{
+    fn product<I: Iterator<Item = &'a Rational<T>>>(iter: I) -> Rational<T> { iter.fold(Rational::one(), |acc, r| acc * r) }
+}
+
+impl<T: CheckedInt> Zero for Rational<T>
+{
+    fn zero() -> Rational<T> { Rational::zero() }
+
+    fn is_zero(&self) -> bool { self.numer.is_zero() }
+}
+
+impl<T: CheckedInt> One for Rational<T>
+{
+    fn one() -> Rational<T> { Rational::one() }
+}
+
+impl<T: CheckedInt> Signed for Rational<T>
+{
+    fn abs(&self) -> Rational<T> { Rational::abs(self) }
+
+    fn signum(&self) -> Rational<T>
+    {
+        match self.numer.cmp(&T::zero()) {
+            Ordering::Less => -Rational::one(),
+            Ordering::Equal => Rational::zero(),
+            Ordering::Greater => Rational::one(),
+        }
+    }
+
+    fn is_negative(&self) -> bool { Rational::is_negative(self) }
+
+    fn is_positive(&self) -> bool { self.numer > T::zero() }
+}
+
+/////////////////////////////////////
+
+// "3/4", or just "3" for an integer. width and fill pad the whole fraction
+impl<T: CheckedInt> fmt::Display for Rational<T>
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        let s = if self.is_integer() { self.numer.to_string() } else { format!("{}/{}", self.numer, self.denom) };
+        f.pad_integral(!self.is_negative(), "", s.trim_start_matches('-'))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseErrorKind
+{
+    Empty,
+    InvalidNumerator,
+    InvalidDenominator,
+    ZeroDenominator,
+    // the fraction is fine, but its lowest terms don't fit
+    Overflow,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseRationalError
+{
+    pub kind: ParseErrorKind,
+    // byte offset into the input where the bad part starts
+    pub position: usize,
+}
+
+impl fmt::Display for ParseRationalError
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result
+    {
+        let what = match self.kind {
+            ParseErrorKind::Empty => "empty string",
+            ParseErrorKind::InvalidNumerator => "invalid numerator",
+            ParseErrorKind::InvalidDenominator => "invalid denominator",
+            ParseErrorKind::ZeroDenominator => "zero denominator",
+            ParseErrorKind::Overflow => "value out of range",
+        };
+        write!(f, "cannot parse rational number: {} at position {}", what, self.position)
+    }
+}
+
+impl std::error::Error for ParseRationalError {}
+
+// "n/d" or "n", with spaces allowed around either number
+impl<T: CheckedInt> FromStr for Rational<T>
+{
+    type Err = ParseRationalError;
+
+    fn from_str(s: &str) -> Result<Rational<T>, ParseRationalError>
+    {
+        let part = |from: usize, to: usize, kind: ParseErrorKind| -> Result<T, ParseRationalError> {
+            let raw = &s[from..to];
+            let position = from + (raw.len() - raw.trim_start().len());
+            raw.trim().parse().map_err(|_| ParseRationalError { kind, position })
+        };
+
+        if s.trim().is_empty() { return Err(ParseRationalError { kind: ParseErrorKind::Empty, position: 0 }); }
+        let (numer, denom, slash) = match s.find('/') {
+            Some(slash) => (
+                part(0, slash, ParseErrorKind::InvalidNumerator)?,
+                part(slash + 1, s.len(), ParseErrorKind::InvalidDenominator)?,
+                slash,
+            ),
+            None => (part(0, s.len(), ParseErrorKind::InvalidNumerator)?, T::one(), 0),
+        };
+        Rational::checked_new(numer, denom).map_err(|e| {
+            let kind = match e {
+                RationalError::DivisionByZero => ParseErrorKind::ZeroDenominator,
+                _ => ParseErrorKind::Overflow,
+            };
+            ParseRationalError { kind, position: if kind == ParseErrorKind::ZeroDenominator { slash + 1 } else { 0 } }
+        })
+    }
+}
+
+/////////////////////////////////////
+
+pub fn main()
+{
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    let r = |n: i64, d: i64| Rational::new(n, d);
+
+    assert_eq!(r(6, -8), r(-3, 4));
+    assert_eq!(r(1, 3) + r(1, 6), r(1, 2));
+    assert_eq!(r(3, 4) * r(2, 9) / r(1, 6), Rational::one());
+    assert_eq!(2 - r(1, 2), r(3, 2));
+    assert_eq!(r(2, 3).pow(-3), r(27, 8));
+    assert_eq!([r(1, 2), r(1, 3), r(1, 6)].iter().sum::<Rational<i64>>(), Rational::one());
+    assert_eq!(r(-7, 2).floor(), -4);
+    println!("{} + {} = {}", r(1, 3), r(1, 6), r(1, 3) + r(1, 6));
+    println!("[{:>8}] [{:<8}] [{:+}]", r(-3, 4), r(5, 1), r(1, 2));
+
+    // errors instead of wrapping
+    let big = Rational::<i8>::new(100, 1);
+    assert_eq!(big.checked_add(&big), Err(RationalError::Overflow));
+    assert_eq!(Rational::<i8>::new(-128, 1).checked_neg(), Err(RationalError::Overflow));
+    assert_eq!(r(1, 2).checked_div(&Rational::zero()), Err(RationalError::DivisionByZero));
+    // the cancelling keeps the intermediates small: (120/7)(7/120) never computes 840
+    assert_eq!(Rational::<i8>::new(120, 7) * Rational::new(7, 120), Rational::one());
+
+    // text
+    assert_eq!("3/4".parse::<Rational<i32>>(), Ok(Rational::new(3, 4)));
+    assert_eq!(" -10 / 4 ".parse::<Rational<i32>>(), Ok(Rational::new(-5, 2)));
+    assert_eq!("7".parse::<Rational<i32>>(), Ok(Rational::from_integer(7)));
+    for text in ["3/0", "x/2", "3/", ""] {
+        println!("{:>6} -> {}", text, text.parse::<Rational<i32>>().unwrap_err());
+    }
+    assert_eq!(r(-3, 4).to_string().parse::<Rational<i64>>(), Ok(r(-3, 4)));
+
+    // floats: exact, and best approximations under a bound
+    assert_eq!(Rational::<i64>::from_f64(0.375), Ok(r(3, 8)));
+    assert_eq!(Rational::<i64>::from_f64(0.1), Ok(r(3602879701896397, 36028797018963968)));
+    assert_eq!(Rational::<i32>::from_f64(0.1), Err(RationalError::Overflow));
+    assert_eq!(Rational::<i64>::from_f64(f64::NAN), Err(RationalError::NotFinite));
+    let pi = std::f64::consts::PI;
+    assert_eq!(Rational::<i64>::approximate(pi, 10), Ok(r(22, 7)));
+    assert_eq!(Rational::<i64>::approximate(pi, 1000), Ok(r(355, 113)));
+    assert_eq!(Rational::<i64>::approximate(-0.1, 1000), Ok(r(-1, 10)));
+    // the convergents of 0.3 are 0, 1/3 and 3/10. below 10 the semiconvergent
+    // 2/7 between the last two beats 1/3
+    assert_eq!(Rational::<i64>::approximate(0.3, 9), Ok(r(2, 7)));
+    // a next term too big for the integer type ends the expansion, it's no error
+    assert_eq!(Rational::<i32>::approximate(3.0000000001, 1000), Ok(Rational::new(3, 1)));
+    assert_eq!(Rational::<i64>::approximate(1e-19, 100), Ok(r(0, 1)));
+    assert_eq!(Rational::<i8>::approximate(0.001, 10), Ok(Rational::new(0, 1)));
+    assert_eq!(Rational::<i8>::approximate(300.0, 10), Err(RationalError::Overflow));
+
+    // ordering agrees with exact cross multiplication in a wider type, even at
+    // the edges of i16 where a d and c b would overflow
+    let mut rng = StdRng::seed_from_u64(39);
+    let mut values: Vec<Rational<i16>> = (0..2000)
+        .map(|_| {
+            let d = if rng.gen_bool(0.5) { rng.gen_range(1..10) } else { rng.gen_range(1..=i16::MAX) };
+            Rational::new(rng.gen_range(i16::MIN + 1..=i16::MAX), d)
+        })
+        .collect();
+    for pair in values.windows(2) {
+        let (a, b) = (&pair[0], &pair[1]);
+        let wide = (*a.numer() as i64 * *b.denom() as i64).cmp(&(*b.numer() as i64 * *a.denom() as i64));
+        assert_eq!(a.cmp(b), wide, "{} vs {}", a, b);
+    }
+    values.sort();
+    assert!(values.windows(2).all(|p| p[0].to_f64() <= p[1].to_f64()));
+
+    // every approximation is at least as close as any fraction with a smaller denominator
+    for _ in 0..200 {
+        let x: f64 = rng.gen_range(-5.0..5.0);
+        let bound = rng.gen_range(1..60);
+        let best = Rational::<i64>::approximate(x, bound).unwrap();
+        let error = (best.to_f64() - x).abs();
+        for d in 1..=bound {
+            let n = (x * d as f64).round() as i64;
+            assert!(error <= (n as f64 / d as f64 - x).abs() + 1e-15, "{} for {} within {}", best, x, bound);
+        }
+    }
+    println!("ordering and approximations check out");
+}